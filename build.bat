@@ -5,5 +5,4 @@ if exist "%cd%/target" (
     rmdir /s /q "%cd%/target"
 )
 
-cmd /c "cd /d ""%cd%/libs/zlib_wrapper"" && build.bat"
 cargo build --release
\ No newline at end of file
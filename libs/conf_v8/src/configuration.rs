@@ -1,20 +1,47 @@
 
 use conv;
 use meta_data;
+use meta_data::diff::Diff;
+use meta_data::merge::Conflict;
+use meta_data::external::{ContainerKind, ExternalObject};
+use meta_data::extension::Extension;
+use meta_data::writer::{Dependency, FilterReport};
 use structure;
 use structure::block::Block;
+use structure::layout::{ContainerLayout, ContainerFormat};
+use structure::source::{self, StreamSource};
 use settings::Settings;
+use error::{ConfError, io_error, metadata_error, settings_error};
 
-use {GROUP_BLOKS_FLAG, DEFAULT_BLOCK_SIZE};
+use std::fs::File;
+use std::io::{Read, Seek};
+
+use DEFAULT_BLOCK_SIZE;
 
 /// Конфигурация
 pub struct CF {
     blocks: Vec<Block>, // блоки конфигурации
+    container: ContainerLayout, // префикс и размер оглавления конфигурационного файла
+    workers: usize, // количество потоков для упаковки и распаковки блоков
 }
 
 impl CF {
     pub fn new(blocks: Vec<Block>) -> CF {
-        return CF { blocks: blocks };
+        return CF::with_container(blocks, ContainerLayout::new());
+    }
+
+    // Конфигурация с префиксом и размером оглавления исходного конфигурационного файла
+    fn with_container(blocks: Vec<Block>, container: ContainerLayout) -> CF {
+        return CF {
+            blocks: blocks,
+            container: container,
+            workers: structure::writer::default_workers(),
+        };
+    }
+
+    /// Установить количество потоков для упаковки и распаковки блоков
+    pub fn set_workers(&mut self, workers: usize) {
+        self.workers = workers;
     }
 
     /// Добавить новый блок в конфигурацию
@@ -22,11 +49,34 @@ impl CF {
         self.blocks.push(block);
     }
 
-    /// Получить префикс конфигурационного файла
-    pub fn prefix() -> Vec<u8> {
+    /// Возвращает формат адресов конфигурационного файла
+    pub fn container_format(&self) -> ContainerFormat {
+        return self.container.format();
+    }
+
+    /// Установить формат адресов, в котором будут собраны конфигурационный файл
+    /// и оглавления его составных блоков
+    pub fn set_container_format(&mut self, format: ContainerFormat) -> Result<(), ConfError> {
+
+        for block in &self.blocks {
+            block.set_container_format(format)?;
+        }
+
+        if self.container.format() != format {
+            self.container = ContainerLayout {
+                toc_page_size: self.container.toc_page_size,
+                ..ContainerLayout::with_format(format)
+            };
+        }
+
+        Ok(())
+    }
+
+    /// Получить префикс конфигурационного файла с указанным форматом адресов
+    pub fn prefix(format: ContainerFormat) -> Vec<u8> {
 
         let mut data: Vec<u8> = Vec::new();
-        data.extend_from_slice(&GROUP_BLOKS_FLAG);                         // маркер группы
+        data.extend_from_slice(format.group_flag());                       // маркер группы
         data.extend_from_slice(&conv::int32_to_bytes(DEFAULT_BLOCK_SIZE)); // размер блока по умолчанию
         data.extend_from_slice(&[0x00, 0x00, 0x00, 0x00]);                 // unknown
         data.extend_from_slice(&[0x00, 0x00, 0x00, 0x00]);                 // unknown
@@ -34,28 +84,174 @@ impl CF {
         return data;
     }
 
-    /// Получить объект на основании данных конфигурационного файла
-    pub fn from_cf(data: &Vec<u8>) -> CF {
-        return CF::new(structure::reader::from_cf(data));
+    /// Получить объект на основании данных конфигурационного файла.
+    /// Данные не копируются: блоки читают их из переданного буфера при первом обращении.
+    pub fn from_cf(data: Vec<u8>) -> Result<CF, ConfError> {
+        let (blocks, container) = structure::reader::from_cf(data)?;
+        return Ok(CF::with_container(blocks, container));
     }
 
-    /// Получить объект на основании ранее распакованных данных конфигурационного файла
-    pub fn from_file(path_to_dir: &String) -> CF {
-        return CF::new(structure::reader::from_file(path_to_dir));
+    /// Получить объект на основании потока с данными конфигурационного файла.
+    /// Из потока сразу читаются только оглавление и атрибуты блоков, данные блока
+    /// читаются при первом обращении к ним.
+    pub fn from_reader<R: Read + Seek + Send + 'static>(reader: R) -> Result<CF, ConfError> {
+        let source = match StreamSource::new(reader) {
+            Ok(v) => v,
+            Err(e) => return Err(io_error("", e)),
+        };
+
+        let (blocks, container) = structure::reader::from_source(&source::shared(source))?;
+        return Ok(CF::with_container(blocks, container));
+    }
+
+    /// Получить объект на основании конфигурационного файла без загрузки файла в память
+    pub fn from_path(path_to_cf: &String) -> Result<CF, ConfError> {
+        let file = match File::open(path_to_cf) {
+            Ok(v) => v,
+            Err(e) => return Err(io_error(path_to_cf, format!("Couldn't open: {}", e))),
+        };
+
+        match CF::from_reader(file) {
+            Err(ConfError::Io { message, .. }) => Err(io_error(path_to_cf, message)),
+            v => v,
+        }
+    }
+
+    /// Получить объект на основании ранее распакованных данных конфигурационного файла.
+    /// Если каталог содержит описание, сохраненное при распаковке, то неизмененная конфигурация
+    /// собирается в файл, побайтово совпадающий с исходным.
+    pub fn from_file(path_to_dir: &String) -> Result<CF, ConfError> {
+        let (blocks, container) = structure::reader::from_file(path_to_dir)?;
+        return Ok(CF::with_container(blocks, container));
+    }
+
+    /// Получить объект на основании конфигурации, ранее выгруженной в виде дерева объектов
+    /// метаданных. Файлы сопоставляются блокам по индексу выгрузки.
+    pub fn from_export(path_to_dir: &String) -> Result<CF, ConfError> {
+        let (blocks, container) = meta_data::import::import(path_to_dir)?;
+        return Ok(CF::with_container(blocks, container));
     }
 
     /// Получить данные для конфигурационного файла
-    pub fn for_cf(&self) -> Vec<u8> {
-        return structure::writer::inflate_cf(&self.blocks);
+    pub fn for_cf(&self) -> Result<Vec<u8>, ConfError> {
+        return structure::writer::inflate_cf(&self.blocks, &self.container, self.workers);
+    }
+
+    /// Распаковать блоки и записать их файлы вместе с описанием конфигурации
+    pub fn deflate_to_files(&self, path_to_dir: &String) -> Result<(), ConfError> {
+        structure::writer::deflate_to_files(&self.blocks, &self.container, path_to_dir, self.workers)
+    }
+
+    /// Записать конфигурацию в каталог в виде дерева объектов метаданных с индексом,
+    /// в котором именам объектов и файлам сопоставлены идентификаторы блоков
+    pub fn export_to_dir(&self, path_to_dir: &String) -> Result<(), ConfError> {
+        meta_data::export::export(&self.blocks, &self.container, path_to_dir, self.workers)
     }
 
-    /// Распаковать блоки и записать их файлы
-    pub fn deflate_to_files(&self, path_to_dir: &String) {
-        structure::writer::deflate_to_files(&self.blocks, path_to_dir);
+    /// Записать тексты модулей объектов метаданных в файлы <Тип>.<Имя объекта>.<Вид модуля>.bsl.
+    /// Возвращает количество записанных модулей.
+    pub fn extract_modules(&self, path_to_dir: &String) -> Result<usize, ConfError> {
+        meta_data::modules::extract_modules(&self.blocks, path_to_dir, self.workers)
     }
 
-    pub fn filter(&mut self, settings_xml: &String) {
-        let settings = Settings::new(&settings_xml);
-        meta_data::writer::filter(&mut self.blocks, &settings);
+    /// Сравнить конфигурацию с новой версией: возвращает добавленные, удаленные и измененные
+    /// объекты метаданных и различия текстов измененных модулей
+    pub fn diff(&self, other: &CF) -> Result<Diff, ConfError> {
+        meta_data::diff::diff(&self.blocks, &other.blocks)
+    }
+
+    /// Трехстороннее объединение: в нашу конфигурацию переносятся изменения их конфигурации
+    /// относительно общей исходной. Возвращает объединенную конфигурацию и конфликты.
+    /// Тексты модулей с конфликтами содержат маркеры конфликтов.
+    pub fn merge(base: &CF, ours: &CF, theirs: &CF) -> Result<(CF, Vec<Conflict>), ConfError> {
+        let result = meta_data::merge::merge(&base.blocks, &ours.blocks, &theirs.blocks)?;
+        let mut cf = CF::with_container(result.blocks, ours.container.clone());
+        cf.workers = ours.workers;
+        return Ok((cf, result.conflicts));
+    }
+
+    /// Оставить в конфигурации только объекты метаданных, указанные в настройках.
+    /// Возвращает объекты, оставленные как зависимости отобранных объектов.
+    pub fn filter(&mut self, settings_xml: &String) -> Result<Vec<Dependency>, ConfError> {
+        self.check_configuration()?;
+        let settings = match Settings::new(&settings_xml) {
+            Ok(v) => v,
+            Err(e) => return Err(settings_error(format!("Failed settings: {}", e))),
+        };
+        meta_data::writer::filter(&mut self.blocks, &settings)
+    }
+
+    /// Проверить настройки фильтрации: возвращает отчет об объектах, которые будут оставлены
+    /// и удалены, и о ссылках, которые будут заменены. Конфигурация не изменяется.
+    pub fn filter_report(&self, settings_xml: &String) -> Result<FilterReport, ConfError> {
+        self.check_configuration()?;
+        let settings = match Settings::new(&settings_xml) {
+            Ok(v) => v,
+            Err(e) => return Err(settings_error(format!("Failed settings: {}", e))),
+        };
+        meta_data::writer::filter_report(&self.blocks, &settings)
+    }
+
+    /// Возвращает вид файла: конфигурация, внешняя обработка или внешний отчет
+    pub fn kind(&self) -> Result<ContainerKind, ConfError> {
+        meta_data::external::container_kind(&self.blocks)
+    }
+
+    /// Возвращает описание внешней обработки или отчета: имя, формы, макеты и модуль объекта
+    pub fn external_object(&self) -> Result<ExternalObject, ConfError> {
+        meta_data::external::read(&self.blocks)
+    }
+
+    /// Возвращает описание расширения конфигурации: собственные и заимствованные объекты
+    pub fn extension(&self) -> Result<Extension, ConfError> {
+        meta_data::extension::read(&self.blocks)
+    }
+
+    /// Извлечь обработку или отчет конфигурации с указанным именем во внешнюю обработку
+    /// (внешний отчет). Ссылки на другие объекты конфигурации заменяются на "Любая ссылка".
+    pub fn extract_external(&self, name: &str) -> Result<CF, ConfError> {
+        let (_, blocks) = meta_data::external::extract(&self.blocks, name)?;
+        let mut cf = CF::new(blocks);
+        cf.workers = self.workers;
+        return Ok(cf);
+    }
+
+    /// Встроить внешнюю обработку (внешний отчет) в конфигурацию как новую обработку (отчет).
+    /// Возвращает идентификатор добавленного объекта.
+    pub fn embed_external(&mut self, external: &CF) -> Result<String, ConfError> {
+        meta_data::external::embed(&mut self.blocks, &external.blocks)
+    }
+
+    // Фильтрация по настройкам выполняется только для конфигурации и расширения
+    fn check_configuration(&self) -> Result<(), ConfError> {
+        match self.kind()? {
+            ContainerKind::Configuration | ContainerKind::Extension => Ok(()),
+            kind => {
+                Err(metadata_error("root",
+                                   format!("Filtering by settings is not supported for *.{} files",
+                                           kind.extension())))
+            }
+        }
+    }
+}
+
+#[test]
+fn test_filter_settings_error() {
+    use error::Stage;
+    use meta_data::types;
+
+    let conf_id = "be22b29f-2db7-4fcb-8772-eeb5500d2170";
+    let conf = format!("{{2,{{{{0,0,{}}},\"Конфигурация\"}},{{{},0}}}}", conf_id, types::CATALOG);
+    let mut cf = CF::new(vec![Block::new("root", &format!("{{2,{},}}", conf_id).into_bytes()),
+                              Block::new(conf_id, &conf.into_bytes())]);
+
+    // Некорректные настройки возвращают ошибку, а не прерывают выполнение программы
+    for settings_xml in &["<project><sourceIB>",
+                          "<project><sourceIB><objects><Справочники/></objects></sourceIB></project>"] {
+        let e = cf.filter(&String::from(*settings_xml)).err().unwrap();
+        assert_eq!(Stage::Metadata, e.stage());
+        assert_eq!(None, e.block_id());
+
+        assert!(cf.filter_report(&String::from(*settings_xml)).is_err());
     }
 }
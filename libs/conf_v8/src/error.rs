@@ -0,0 +1,274 @@
+use std::fmt;
+use std::error::Error;
+
+/// Этап обработки конфигурационного файла, на котором возникла ошибка
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Stage {
+    Header, // чтение заголовка области
+    Toc, // чтение оглавления
+    Attributes, // чтение атрибутов блока
+    Inflate, // распаковка или упаковка данных блока
+    Metadata, // разбор описания объектов метаданных
+    Io, // чтение или запись файлов
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            Stage::Header => "header",
+            Stage::Toc => "table of contents",
+            Stage::Attributes => "attributes",
+            Stage::Inflate => "inflate",
+            Stage::Metadata => "metadata",
+            Stage::Io => "io",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+/// Ошибка обработки конфигурационного файла
+///
+/// Позиция указывается относительно начала данных, в которых выполнялся поиск:
+/// для блоков верхнего уровня - это начало конфигурационного файла,
+/// для вложенных блоков - начало распакованных данных родительского блока.
+/// Для вложенных блоков указывается идентификатор родительского блока.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfError {
+    Header {
+        block_id: Option<String>,
+        position: Option<i64>,
+        message: String,
+    },
+    Toc {
+        block_id: Option<String>,
+        position: Option<i64>,
+        message: String,
+    },
+    Attributes {
+        block_id: Option<String>,
+        position: Option<i64>,
+        message: String,
+    },
+    Inflate {
+        block_id: Option<String>,
+        position: Option<i64>,
+        message: String,
+    },
+    Metadata {
+        block_id: Option<String>,
+        position: Option<i64>,
+        message: String,
+    },
+    Io {
+        path: String,
+        block_id: Option<String>,
+        position: Option<i64>,
+        message: String,
+    },
+}
+
+impl ConfError {
+    /// Возвращает этап, на котором возникла ошибка
+    pub fn stage(&self) -> Stage {
+        match *self {
+            ConfError::Header { .. } => Stage::Header,
+            ConfError::Toc { .. } => Stage::Toc,
+            ConfError::Attributes { .. } => Stage::Attributes,
+            ConfError::Inflate { .. } => Stage::Inflate,
+            ConfError::Metadata { .. } => Stage::Metadata,
+            ConfError::Io { .. } => Stage::Io,
+        }
+    }
+
+    /// Возвращает позицию в данных, на которой возникла ошибка
+    pub fn position(&self) -> Option<i64> {
+        match *self {
+            ConfError::Header { position, .. } |
+            ConfError::Toc { position, .. } |
+            ConfError::Attributes { position, .. } |
+            ConfError::Inflate { position, .. } |
+            ConfError::Metadata { position, .. } |
+            ConfError::Io { position, .. } => position,
+        }
+    }
+
+    /// Возвращает идентификатор блока, при обработке которого возникла ошибка
+    pub fn block_id<'a>(&'a self) -> Option<&'a str> {
+        match *self {
+            ConfError::Header { ref block_id, .. } |
+            ConfError::Toc { ref block_id, .. } |
+            ConfError::Attributes { ref block_id, .. } |
+            ConfError::Inflate { ref block_id, .. } |
+            ConfError::Metadata { ref block_id, .. } |
+            ConfError::Io { ref block_id, .. } => block_id.as_ref().map(|x| &**x),
+        }
+    }
+
+    /// Возвращает текст ошибки
+    pub fn message<'a>(&'a self) -> &'a str {
+        match *self {
+            ConfError::Header { ref message, .. } |
+            ConfError::Toc { ref message, .. } |
+            ConfError::Attributes { ref message, .. } |
+            ConfError::Inflate { ref message, .. } |
+            ConfError::Metadata { ref message, .. } |
+            ConfError::Io { ref message, .. } => &*message,
+        }
+    }
+
+    /// Указать идентификатор блока, если он еще не указан
+    pub fn at_block(mut self, id: &str) -> ConfError {
+        {
+            let block_id = match self {
+                ConfError::Header { ref mut block_id, .. } |
+                ConfError::Toc { ref mut block_id, .. } |
+                ConfError::Attributes { ref mut block_id, .. } |
+                ConfError::Inflate { ref mut block_id, .. } |
+                ConfError::Metadata { ref mut block_id, .. } |
+                ConfError::Io { ref mut block_id, .. } => block_id,
+            };
+
+            if block_id.is_none() {
+                *block_id = Some(id.to_string());
+            }
+        }
+
+        return self;
+    }
+
+    /// Указать позицию в данных, если она еще не указана
+    pub fn at_position(mut self, value: i64) -> ConfError {
+        {
+            let position = match self {
+                ConfError::Header { ref mut position, .. } |
+                ConfError::Toc { ref mut position, .. } |
+                ConfError::Attributes { ref mut position, .. } |
+                ConfError::Inflate { ref mut position, .. } |
+                ConfError::Metadata { ref mut position, .. } |
+                ConfError::Io { ref mut position, .. } => position,
+            };
+
+            if position.is_none() {
+                *position = Some(value);
+            }
+        }
+
+        return self;
+    }
+}
+
+impl fmt::Display for ConfError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} error: ", self.stage())?;
+
+        if let ConfError::Io { ref path, .. } = *self {
+            write!(f, "path='{}': ", path)?;
+        }
+        if let Some(block_id) = self.block_id() {
+            write!(f, "block='{}': ", block_id)?;
+        }
+        if let Some(position) = self.position() {
+            write!(f, "position={}: ", position)?;
+        }
+
+        write!(f, "{}", self.message())
+    }
+}
+
+impl Error for ConfError {
+    fn description(&self) -> &str {
+        self.message()
+    }
+}
+
+/// Создает ошибку чтения заголовка области
+pub fn header_error<T: Into<String>>(position: i64, message: T) -> ConfError {
+    ConfError::Header {
+        block_id: None,
+        position: Some(position),
+        message: message.into(),
+    }
+}
+
+/// Создает ошибку чтения оглавления
+pub fn toc_error<T: Into<String>>(position: i64, message: T) -> ConfError {
+    ConfError::Toc {
+        block_id: None,
+        position: Some(position),
+        message: message.into(),
+    }
+}
+
+/// Создает ошибку чтения атрибутов блока
+pub fn attributes_error<T: Into<String>>(position: i64, message: T) -> ConfError {
+    ConfError::Attributes {
+        block_id: None,
+        position: Some(position),
+        message: message.into(),
+    }
+}
+
+/// Создает ошибку распаковки (упаковки) данных блока
+pub fn inflate_error<T: Into<String>>(block_id: &str, message: T) -> ConfError {
+    ConfError::Inflate {
+        block_id: Some(block_id.to_string()),
+        position: None,
+        message: message.into(),
+    }
+}
+
+/// Создает ошибку разбора описания объекта метаданных
+pub fn metadata_error<T: Into<String>>(block_id: &str, message: T) -> ConfError {
+    ConfError::Metadata {
+        block_id: Some(block_id.to_string()),
+        position: None,
+        message: message.into(),
+    }
+}
+
+/// Создает ошибку разбора настроек отбора объектов метаданных
+pub fn settings_error<T: Into<String>>(message: T) -> ConfError {
+    ConfError::Metadata {
+        block_id: None,
+        position: None,
+        message: message.into(),
+    }
+}
+
+/// Создает ошибку чтения (записи) файла
+pub fn io_error<T: Into<String>>(path: &str, message: T) -> ConfError {
+    ConfError::Io {
+        path: path.to_string(),
+        block_id: None,
+        position: None,
+        message: message.into(),
+    }
+}
+
+#[test]
+fn test_conf_error() {
+    let e = header_error(31, "Bad format of the header.");
+    assert_eq!(Stage::Header, e.stage());
+    assert_eq!(Some(31), e.position());
+    assert_eq!(None, e.block_id());
+    assert_eq!("header error: position=31: Bad format of the header.",
+               format!("{}", e));
+
+    let e = inflate_error("root", "Unable to decompress the data.");
+    assert_eq!(Stage::Inflate, e.stage());
+    assert_eq!(None, e.position());
+    assert_eq!(Some("root"), e.block_id());
+
+    // позиция и блок, указанные при создании ошибки, не заменяются
+    let e = attributes_error(40, "Bad attributes.").at_block("root").at_position(0);
+    assert_eq!(Stage::Attributes, e.stage());
+    assert_eq!(Some(40), e.position());
+    assert_eq!(Some("root"), e.block_id());
+    assert_eq!("attributes error: block='root': position=40: Bad attributes.",
+               format!("{}", e));
+    assert_eq!(Some("root"), e.at_block("other").block_id());
+
+    let e = io_error("/tmp/1.cf", "Couldn't open.").at_block("root");
+    assert_eq!("io error: path='/tmp/1.cf': block='root': Couldn't open.", format!("{}", e));
+}
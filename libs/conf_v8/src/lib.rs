@@ -2,22 +2,30 @@
 #[macro_use]
 extern crate log;
 extern crate time;
-extern crate regex;
 extern crate aho_corasick;
-#[macro_use]
-extern crate lazy_static;
 
 extern crate conv;
 extern crate settings;
+extern crate xml;
 extern crate file_system;
 extern crate zlib_wrapper;
 
-#[macro_use]
+mod error;
 mod meta_data;
 mod structure;
 mod configuration;
 
 pub use configuration::CF;
+pub use error::{ConfError, Stage};
+pub use meta_data::format::{Format, format_dir};
+pub use meta_data::diff::Diff;
+pub use meta_data::merge::{Conflict, ConflictKind};
+pub use meta_data::external::{ContainerKind, ExternalObject};
+pub use meta_data::extension::{Extension, ExtensionObject};
+pub use meta_data::writer::{Dependency, FilterReport, ReplacedReference};
+pub use structure::layout::ContainerFormat;
+pub use meta_data::types::{MetadataType, TYPES, Version, find_type, find_type_by_name, types_for_version};
 
 pub static DEFAULT_BLOCK_SIZE: i32 = 512; // Размер блока данных по умолчанию
 pub static GROUP_BLOKS_FLAG: [u8; 4] = [0xFF, 0xFF, 0xFF, 0x7F]; // маркер группы &conv::int32_to_bytes(i32::max_value())
+pub static GROUP_BLOKS_FLAG_64: [u8; 8] = [0xFF; 8]; // маркер группы 64-разрядного контейнера &conv::int64_to_bytes(u64::max_value())
@@ -0,0 +1,740 @@
+// Разбор и запись текста в формате списков 1С:
+//   {2,{0,0,9d1a9f27-cd9f-488b-b5e1-c8b410fb7856},"Имя ""в кавычках""",{#base64:AAEC},-1.5}
+//
+// Текст разбирается в дерево значений. Вместе со значениями сохраняются пробелы и переводы
+// строк между ними, поэтому записанный обратно текст побайтово совпадает с исходным.
+
+use meta_data::types::EMPTY_REF;
+use error::{ConfError, metadata_error};
+
+use std::slice;
+use std::str;
+
+// Маркер UTF-8, с которого начинаются данные блоков и тексты модулей
+pub const UTF8_BOM: [u8; 3] = [0xEF, 0xBB, 0xBF];
+
+// Префикс данных в кодировке base64
+const BASE64_PREFIX: &'static str = "#base64:";
+
+// Способ записи текста
+#[derive(Clone, Copy, PartialEq)]
+enum Style {
+    Original, // с исходными пробелами и переводами строк
+    Pretty, // в читаемом виде с отступами
+    Minified, // без пробелов и переводов строк
+}
+
+// Значение
+#[derive(Clone, PartialEq, Debug)]
+pub enum Value {
+    List(List), // список: {значение,значение,...}
+    Number(String), // число в исходной записи
+    Text(String), // строка без кавычек (двойные кавычки заменены на одинарные)
+    Guid(String), // идентификатор
+    Base64(String), // данные в кодировке base64 без префикса
+    Raw(String), // прочие значения (в том числе пустое значение между запятыми)
+}
+
+// Элемент списка
+#[derive(Clone, PartialEq, Debug)]
+pub struct Node {
+    pub before: String, // пробелы и переводы строк перед значением
+    pub value: Value,
+    pub after: String, // пробелы и переводы строк после значения
+}
+
+// Список значений
+#[derive(Clone, PartialEq, Debug)]
+pub struct List {
+    pub items: Vec<Node>,
+    pub space: String, // пробелы внутри пустого списка
+}
+
+// Разобранный текст: списки верхнего уровня
+#[derive(Clone, PartialEq, Debug)]
+pub struct Document {
+    pub bom: bool, // текст начинается с маркера UTF-8
+    pub items: Vec<Node>, // списки верхнего уровня
+    pub tail: String, // текст после последнего списка, который не является списком
+}
+
+impl Node {
+    pub fn new(value: Value) -> Node {
+        return Node {
+            before: String::new(),
+            value: value,
+            after: String::new(),
+        };
+    }
+}
+
+impl List {
+    pub fn new(values: Vec<Value>) -> List {
+        return List {
+            items: values.into_iter().map(Node::new).collect(),
+            space: String::new(),
+        };
+    }
+
+    // Возвращает значение элемента списка
+    pub fn item(&self, i: usize) -> Option<&Value> {
+        self.items.get(i).map(|v| &v.value)
+    }
+}
+
+impl Value {
+    pub fn list(&self) -> Option<&List> {
+        match *self {
+            Value::List(ref v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn guid(&self) -> Option<&str> {
+        match *self {
+            Value::Guid(ref v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn text(&self) -> Option<&str> {
+        match *self {
+            Value::Text(ref v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn number(&self) -> Option<&str> {
+        match *self {
+            Value::Number(ref v) => Some(v),
+            _ => None,
+        }
+    }
+
+    // Записать значение в текст
+    fn write(&self, text: &mut String, style: Style, level: usize) {
+        match *self {
+            Value::List(ref list) => {
+                // В читаемом виде с новой строки пишутся только элементы списков, содержащих списки
+                let is_multiline = style == Style::Pretty &&
+                                   list.items.iter().any(|v| v.value.list().is_some());
+
+                text.push('{');
+                if list.items.is_empty() && style == Style::Original {
+                    text.push_str(&list.space);
+                }
+                for (i, node) in list.items.iter().enumerate() {
+                    if i > 0 {
+                        text.push(',');
+                    }
+                    // Первое значение, если оно не список, пишется в строке с открывающей скобкой
+                    if is_multiline && (i > 0 || node.value.list().is_some()) {
+                        new_line(text, level + 1);
+                    }
+                    node.write(text, style, level + 1);
+                }
+                if is_multiline {
+                    new_line(text, level);
+                }
+                text.push('}');
+            }
+            Value::Text(ref v) => {
+                text.push('"');
+                text.push_str(&v.replace("\"", "\"\""));
+                text.push('"');
+            }
+            Value::Base64(ref v) => {
+                text.push_str(BASE64_PREFIX);
+                text.push_str(v);
+            }
+            Value::Number(ref v) |
+            Value::Guid(ref v) |
+            Value::Raw(ref v) => text.push_str(v),
+        }
+    }
+}
+
+impl Node {
+    fn write(&self, text: &mut String, style: Style, level: usize) {
+        if style == Style::Original {
+            text.push_str(&self.before);
+        }
+        self.value.write(text, style, level);
+        if style == Style::Original {
+            text.push_str(&self.after);
+        }
+    }
+}
+
+impl Document {
+    // Разобрать текст
+    pub fn parse(data: &[u8]) -> Result<Document, String> {
+
+        let bom = data.starts_with(&UTF8_BOM);
+        let data = without_bom(data);
+
+        let text = match str::from_utf8(data) {
+            Ok(v) => v,
+            Err(e) => return Err(format!("The text is not in UTF-8: {}", e)),
+        };
+
+        let mut parser = Parser {
+            text: text,
+            pos: 0,
+        };
+
+        let mut items: Vec<Node> = Vec::new();
+        let tail: String;
+
+        loop {
+            let start = parser.pos;
+            let before = parser.whitespace();
+
+            // Текст, который не является списком, сохраняется без разбора
+            if parser.peek() != Some(b'{') {
+                tail = String::from(&text[start..]);
+                break;
+            }
+
+            let value = parser.list()?;
+            items.push(Node {
+                before: before,
+                value: value,
+                after: String::new(),
+            });
+        }
+
+        return Ok(Document {
+            bom: bom,
+            items: items,
+            tail: tail,
+        });
+    }
+
+    // Возвращает первый список верхнего уровня
+    pub fn root(&self) -> Option<&List> {
+        self.items.first().and_then(|v| v.value.list())
+    }
+
+    // Записать разобранный текст
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.write(Style::Original)
+    }
+
+    // Записать текст в читаемом виде: элементы списков, содержащих вложенные списки,
+    // пишутся с новой строки с отступом. Строки и данные base64 не изменяются.
+    pub fn to_pretty_bytes(&self) -> Vec<u8> {
+        self.write(Style::Pretty)
+    }
+
+    // Записать текст без пробелов и переводов строк между значениями
+    pub fn to_minified_bytes(&self) -> Vec<u8> {
+        self.write(Style::Minified)
+    }
+
+    fn write(&self, style: Style) -> Vec<u8> {
+
+        let mut text = String::new();
+        for (i, node) in self.items.iter().enumerate() {
+            if style == Style::Pretty && i > 0 {
+                text.push('\n');
+            }
+            node.write(&mut text, style, 0);
+        }
+
+        match style {
+            Style::Original => text.push_str(&self.tail),
+            Style::Pretty => {
+                text.push_str(self.tail.trim());
+                text.push('\n');
+            }
+            Style::Minified => text.push_str(self.tail.trim()),
+        }
+
+        let mut retval: Vec<u8> = Vec::with_capacity(text.len() + UTF8_BOM.len());
+        if self.bom {
+            retval.extend_from_slice(&UTF8_BOM);
+        }
+        retval.extend_from_slice(text.as_bytes());
+
+        return retval;
+    }
+}
+
+// Разобрать описание блока. Ошибка разбора возвращается как ошибка метаданных блока.
+pub fn parse_data(block_id: &str, data: &[u8]) -> Result<Document, ConfError> {
+    match Document::parse(data) {
+        Ok(v) => Ok(v),
+        Err(e) => Err(metadata_error(block_id, e)),
+    }
+}
+
+// Возвращает данные без маркера UTF-8
+pub fn without_bom(data: &[u8]) -> &[u8] {
+    match data.starts_with(&UTF8_BOM) {
+        true => &data[UTF8_BOM.len()..],
+        false => data,
+    }
+}
+
+// Возвращает все идентификаторы в порядке следования в тексте
+pub fn guids(items: &[Node]) -> Vec<String> {
+
+    let mut retval: Vec<String> = Vec::new();
+    walk(items, &mut |value| if let Some(id) = value.guid() {
+        retval.push(String::from(id));
+    });
+
+    return retval;
+}
+
+// Возвращает идентификаторы списков, которые не являются первыми элементами своих списков.
+// Пример: {fdf816d2-1ead-11d5-b975-0050bae0a95d,2,2605a1e0-...,3305a1e0-...}
+// Результат: 2605a1e0-..., 3305a1e0-...
+pub fn elements_ids(items: &[Node]) -> Vec<String> {
+
+    let mut retval: Vec<String> = Vec::new();
+
+    for (i, node) in items.iter().enumerate() {
+        match node.value {
+            Value::Guid(ref id) if i > 0 => retval.push(id.clone()),
+            Value::List(ref list) => retval.extend(elements_ids(&list.items)),
+            _ => (),
+        }
+    }
+
+    return retval;
+}
+
+// Возвращает идентификаторы и имена объектов.
+// Имя объекта следует за списком, последний элемент которого - идентификатор объекта.
+// Пример: {0,0,b8c42329-e3ee-47c0-9d9e-0fae37b7eefa},"Команда1"
+pub fn ids_and_names(items: &[Node]) -> Vec<(String, String)> {
+
+    let mut retval: Vec<(String, String)> = Vec::new();
+
+    push_ids_and_names(items, &mut retval);
+    walk_lists(items, &mut |list| push_ids_and_names(&list.items, &mut retval));
+
+    return retval;
+}
+
+// Возвращает идентификатор и имя элемента списка с описанием типа.
+// Элемент - идентификатор объекта или описание объекта, в котором первым указаны идентификатор и имя.
+// Пример: 2605a1e0-a034-4fd1-885b-7a2fdf618144 или {{{0,0,b8c42329-e3ee-47c0-9d9e-0fae37b7eefa},"Реквизит1"},...}
+pub fn element_id_and_name(node: &Node) -> Option<(String, Option<String>)> {
+    match node.value {
+        Value::Guid(ref id) => Some((id.clone(), None)),
+        Value::List(_) => {
+            ids_and_names(slice::from_ref(node))
+                .into_iter()
+                .next()
+                .map(|(id, name)| (id, Some(name)))
+        }
+        _ => None,
+    }
+}
+
+// Возвращает идентификатор объекта основной конфигурации, который заимствует объект расширения.
+// В описании заимствованного объекта после имени, синонима и комментария указываются
+// признак принадлежности (1 - заимствованный) и идентификатор заимствованного объекта.
+// Пример: {3,{0,0,b8c42329-e3ee-47c0-9d9e-0fae37b7eefa},"Товары",{0},"",1,5a7b1f8e-...}
+pub fn borrowed_id(items: &[Node], id: &str) -> Option<String> {
+
+    let mut retval = borrowed_id_in_list(items, id);
+    walk_lists(items, &mut |list| if retval.is_none() {
+        retval = borrowed_id_in_list(&list.items, id);
+    });
+
+    return retval;
+}
+
+// Возвращает список с описанием типа: первый список, первый элемент которого - идентификатор типа.
+// Пример: {fdf816d2-1ead-11d5-b975-0050bae0a95d,1,2605a1e0-a034-4fd1-885b-7a2fdf618144}
+pub fn find_type_list<'a>(items: &'a [Node], type_id: &str) -> Option<&'a List> {
+
+    for node in items {
+        if let Value::List(ref list) = node.value {
+            if is_type_list(list, type_id) {
+                return Some(list);
+            }
+            if let Some(v) = find_type_list(&list.items, type_id) {
+                return Some(v);
+            }
+        }
+    }
+
+    return None;
+}
+
+// Возвращает изменяемый список с описанием типа
+pub fn find_type_list_mut<'a>(items: &'a mut [Node], type_id: &str) -> Option<&'a mut List> {
+
+    for node in items.iter_mut() {
+        if let Value::List(ref mut list) = node.value {
+            if is_type_list(list, type_id) {
+                return Some(list);
+            }
+            if let Some(v) = find_type_list_mut(&mut list.items, type_id) {
+                return Some(v);
+            }
+        }
+    }
+
+    return None;
+}
+
+// Заменить идентификаторы
+pub fn replace_guids(items: &mut [Node], ids: &[String], new_id: &str) {
+    for node in items.iter_mut() {
+        match node.value {
+            Value::Guid(ref mut id) => {
+                if ids.iter().any(|v| v.eq(id)) {
+                    *id = String::from(new_id);
+                }
+            }
+            Value::List(ref mut list) => replace_guids(&mut list.items, ids, new_id),
+            _ => (),
+        }
+    }
+}
+
+// Добавить идентификаторы и имена объектов из элементов одного списка
+fn push_ids_and_names(items: &[Node], retval: &mut Vec<(String, String)>) {
+    for pair in items.windows(2) {
+        let id = match pair[0].value.list() {
+            Some(v) if v.items.len() == 3 && v.item(0).and_then(Value::number).is_some() &&
+                       v.item(1).and_then(Value::number).is_some() => v.item(2).and_then(Value::guid),
+            _ => None,
+        };
+
+        if let (Some(id), Some(name)) = (id, pair[1].value.text()) {
+            retval.push((String::from(id), String::from(name)));
+        }
+    }
+}
+
+// Ищет признак заимствования после идентификатора и имени объекта в элементах одного списка
+fn borrowed_id_in_list(items: &[Node], id: &str) -> Option<String> {
+    let mut names: Vec<(String, String)> = Vec::new();
+
+    for (i, pair) in items.windows(2).enumerate() {
+        names.clear();
+        push_ids_and_names(pair, &mut names);
+        match names.first() {
+            Some(v) if v.0 == id => (),
+            _ => continue,
+        }
+
+        for flag in items[i + 2..].windows(2) {
+            match (flag[0].value.number(), flag[1].value.guid()) {
+                (Some("1"), Some(v)) if v != EMPTY_REF => return Some(String::from(v)),
+                _ => (),
+            }
+        }
+        return None;
+    }
+
+    return None;
+}
+
+// Перевод строки с отступом
+fn new_line(text: &mut String, level: usize) {
+    text.push('\n');
+    for _ in 0..level {
+        text.push_str("    ");
+    }
+}
+
+fn is_type_list(list: &List, type_id: &str) -> bool {
+    list.items.len() > 1 && list.item(0).and_then(Value::guid) == Some(type_id)
+}
+
+// Обойти все значения в порядке следования в тексте
+fn walk<F: FnMut(&Value)>(items: &[Node], f: &mut F) {
+    for node in items {
+        f(&node.value);
+        if let Value::List(ref list) = node.value {
+            walk(&list.items, f);
+        }
+    }
+}
+
+// Обойти все списки в порядке следования в тексте
+fn walk_lists<F: FnMut(&List)>(items: &[Node], f: &mut F) {
+    for node in items {
+        if let Value::List(ref list) = node.value {
+            f(list);
+            walk_lists(&list.items, f);
+        }
+    }
+}
+
+// Возвращает признак идентификатора: 8-4-4-4-12 латинских букв и цифр
+fn is_guid(value: &str) -> bool {
+
+    if value.len() != 36 {
+        return false;
+    }
+
+    value.bytes().enumerate().all(|(i, b)| match i {
+        8 | 13 | 18 | 23 => b == b'-',
+        _ => b.is_ascii_alphanumeric(),
+    })
+}
+
+// Возвращает признак числа
+fn is_number(value: &str) -> bool {
+
+    let digits = match value.starts_with('-') {
+        true => &value[1..],
+        false => value,
+    };
+
+    match digits.chars().next() {
+        Some(c) if c.is_ascii_digit() => (),
+        _ => return false,
+    }
+
+    digits.chars().all(|c| c.is_ascii_digit() || c == '.' || c == 'e' || c == 'E' || c == '-' || c == '+')
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.text.as_bytes().get(self.pos).cloned()
+    }
+
+    // Пропустить пробелы и переводы строк
+    fn whitespace(&mut self) -> String {
+
+        let start = self.pos;
+        while let Some(b) = self.peek() {
+            match b {
+                b' ' | b'\t' | b'\r' | b'\n' => self.pos += 1,
+                _ => break,
+            }
+        }
+
+        return String::from(&self.text[start..self.pos]);
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        match self.peek() {
+            Some(b'{') => self.list(),
+            Some(b'"') => self.quoted_text(),
+            _ => Ok(self.token()),
+        }
+    }
+
+    fn list(&mut self) -> Result<Value, String> {
+
+        let start = self.pos;
+        self.pos += 1; // {
+
+        let mut items: Vec<Node> = Vec::new();
+
+        let space = self.whitespace();
+        if self.peek() == Some(b'}') {
+            self.pos += 1;
+            return Ok(Value::List(List {
+                items: items,
+                space: space,
+            }));
+        }
+
+        let mut before = space;
+
+        loop {
+            let value = self.value()?;
+            let after = self.whitespace();
+
+            items.push(Node {
+                before: before,
+                value: value,
+                after: after,
+            });
+
+            match self.peek() {
+                Some(b',') => {
+                    self.pos += 1;
+                    before = self.whitespace();
+                }
+                Some(b'}') => {
+                    self.pos += 1;
+                    break;
+                }
+                Some(b'{') => {
+                    return Err(format!("Missing comma before the list at the position {}.",
+                                       self.pos))
+                }
+                Some(b) => {
+                    return Err(format!("Unexpected character '{}' at the position {}.",
+                                       b as char,
+                                       self.pos))
+                }
+                None => return Err(format!("The list at the position {} is not closed.", start)),
+            }
+        }
+
+        return Ok(Value::List(List {
+            items: items,
+            space: String::new(),
+        }));
+    }
+
+    fn quoted_text(&mut self) -> Result<Value, String> {
+
+        let start = self.pos;
+        self.pos += 1; // "
+
+        let mut retval = String::new();
+
+        loop {
+            let end = match self.text[self.pos..].find('"') {
+                Some(v) => self.pos + v,
+                None => return Err(format!("The string at the position {} is not closed.", start)),
+            };
+
+            retval.push_str(&self.text[self.pos..end]);
+            self.pos = end + 1;
+
+            // Две кавычки подряд - кавычка внутри строки
+            if self.peek() == Some(b'"') {
+                retval.push('"');
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+
+        return Ok(Value::Text(retval));
+    }
+
+    // Значение без кавычек до запятой, конца или начала списка. Пробелы после значения
+    // к значению не относятся.
+    fn token(&mut self) -> Value {
+
+        let start = self.pos;
+        let end = match self.text[start..].find(&[',', '}', '{'][..]) {
+            Some(v) => start + v,
+            None => self.text.len(),
+        };
+
+        let token = self.text[start..end].trim_end();
+        self.pos = start + token.len();
+
+        if token.starts_with(BASE64_PREFIX) {
+            return Value::Base64(String::from(&token[BASE64_PREFIX.len()..]));
+        }
+        if is_guid(token) {
+            return Value::Guid(String::from(token));
+        }
+        if is_number(token) {
+            return Value::Number(String::from(token));
+        }
+
+        return Value::Raw(String::from(token));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Document, Value, List, guids, elements_ids, ids_and_names, find_type_list,
+                find_type_list_mut, replace_guids, borrowed_id};
+
+    #[test]
+    fn test_parse() {
+
+        let text = "\u{feff}{2,\r\n{0,0,b8c42329-e3ee-47c0-9d9e-0fae37b7eefa},\"Имя \"\"{,}\"\"\",\
+                    {#base64:AAEC\r\nAwQ=},-1.5,, {} ,{ }}\r\n";
+
+        let doc = Document::parse(text.as_bytes()).unwrap();
+        assert!(doc.bom);
+        assert_eq!("\r\n", doc.tail);
+
+        let root = doc.root().unwrap();
+        assert_eq!(8, root.items.len());
+        assert_eq!(Some("2"), root.item(0).and_then(Value::number));
+        assert_eq!("\r\n", root.items[1].before);
+        assert_eq!(Some("Имя \"{,}\""), root.item(2).and_then(Value::text));
+        assert_eq!(Some(&Value::Base64(String::from("AAEC\r\nAwQ="))),
+                   root.item(3).and_then(Value::list).and_then(|v| v.item(0)));
+        assert_eq!(Some("-1.5"), root.item(4).and_then(Value::number));
+        assert_eq!(Some(&Value::Raw(String::new())), root.item(5));
+        assert_eq!(Some(&Value::List(List::new(Vec::new()))), root.item(6));
+        assert_eq!(" ", root.items[6].after);
+        assert_eq!(" ", root.item(7).and_then(Value::list).unwrap().space);
+
+        assert_eq!(text.as_bytes().to_vec(), doc.to_bytes());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(Document::parse(b"{1,{2}").is_err());
+        assert!(Document::parse(b"{\"text}").is_err());
+        assert!(Document::parse(b"{1{2}}").is_err());
+        assert!(Document::parse(&[b'{', 0xFF, b'}']).is_err());
+
+        // Текст, который не является списком, сохраняется без изменений
+        let doc = Document::parse(b"{1}text{2}").unwrap();
+        assert_eq!(1, doc.items.len());
+        assert_eq!("text{2}", doc.tail);
+        assert_eq!(b"{1}text{2}".to_vec(), doc.to_bytes());
+    }
+
+    #[test]
+    fn test_search() {
+
+        let text = "{1,{4fe87c89-9ad4-43f6-9fdb-9dc83b3879c6,1,{0,{2,\
+                    b8c42329-e3ee-47c0-9d9e-0fae37b7eefa,078a6af8-d22c-4248-9c33-7e90075a3d2c},\
+                    {0,{0,0,b8c42329-e3ee-47c0-9d9e-0fae37b7eefa},\"Команда1\",\"ru\"}}},\
+                    {37f2fa9a-b276-11d4-9435-004095e12fc7,2,d1e22a47-ab34-43b8-9b0e-a27113e944ad,\
+                    39bddf6a-0c3c-452b-921c-d99cfa1c2f1a}}";
+
+        let mut doc = Document::parse(text.as_bytes()).unwrap();
+
+        assert_eq!(7, guids(&doc.items).len());
+        assert_eq!(vec![(String::from("b8c42329-e3ee-47c0-9d9e-0fae37b7eefa"),
+                         String::from("Команда1"))],
+                   ids_and_names(&doc.items));
+
+        let list = find_type_list(&doc.items, "37f2fa9a-b276-11d4-9435-004095e12fc7").unwrap();
+        assert_eq!(vec!["d1e22a47-ab34-43b8-9b0e-a27113e944ad",
+                        "39bddf6a-0c3c-452b-921c-d99cfa1c2f1a"],
+                   elements_ids(&list.items));
+        assert!(find_type_list(&doc.items, "d1e22a47-ab34-43b8-9b0e-a27113e944ad").is_none());
+
+        {
+            let list = find_type_list_mut(&mut doc.items, "37f2fa9a-b276-11d4-9435-004095e12fc7")
+                .unwrap();
+            *list = List::new(vec![Value::Guid(String::from("37f2fa9a-b276-11d4-9435-004095e12fc7")),
+                                   Value::Number(String::from("0"))]);
+        }
+        replace_guids(&mut doc.items,
+                      &[String::from("b8c42329-e3ee-47c0-9d9e-0fae37b7eefa")],
+                      "00000000-0000-0000-0000-000000000000");
+
+        let text = String::from_utf8(doc.to_bytes()).unwrap();
+        assert!(text.ends_with(",{37f2fa9a-b276-11d4-9435-004095e12fc7,0}}"));
+        assert!(text.contains("{0,0,00000000-0000-0000-0000-000000000000},\"Команда1\""));
+    }
+
+    #[test]
+    fn test_borrowed_id() {
+
+        let text = "{1,{3,{0,0,b8c42329-e3ee-47c0-9d9e-0fae37b7eefa},\"Товары\",{0},\"\",1,\
+                    5a7b1f8e-1111-2222-3333-444455556666},{0,{0,{0,0,d1e22a47-ab34-43b8-9b0e-a27113e944ad},\
+                    \"Реквизит\",{0},\"\",0,00000000-0000-0000-0000-000000000000}}}";
+        let doc = Document::parse(text.as_bytes()).unwrap();
+
+        assert_eq!(Some(String::from("5a7b1f8e-1111-2222-3333-444455556666")),
+                   borrowed_id(&doc.items, "b8c42329-e3ee-47c0-9d9e-0fae37b7eefa"));
+        assert_eq!(None, borrowed_id(&doc.items, "d1e22a47-ab34-43b8-9b0e-a27113e944ad"));
+        assert_eq!(None, borrowed_id(&doc.items, "39bddf6a-0c3c-452b-921c-d99cfa1c2f1a"));
+    }
+}
@@ -1,14 +1,14 @@
 
 use meta_data::types::{FORMS_ID_DOC, FORMS_ID_CATALOG, PROPS_ID, LAYOUTS_ID, COMMANDS_ID,
                        TABULAR_SELECTIONS_ID};
-use meta_data::reader::{RegexTypes, find_ids, find_ids_and_names, find_type_coordinates};
-use meta_data::substr::find_text;
+use meta_data::brace::{Value, guids, ids_and_names, element_id_and_name, find_type_list,
+                       borrowed_id, parse_data};
+use error::{ConfError, metadata_error};
 
 use std::collections::HashMap;
 
 /// Описание объекта метаданных конфигурации(может содержать описание объекта метаданных, формы, модуля и т.д.)
 pub struct Description {
-    #[allow(dead_code)]
     block_id: String, // идентификатор блока
     internal_id: String, /* внутренний идентификатор объекта ( для всех типов соответствует идентификатору блока,
                           * но для описания конфигурации будет не соотвествовать ) */
@@ -25,20 +25,31 @@ pub struct Description {
                                                        * - щаблоны;
                                                        * - формы;
                                                        * - свойства. */
+    internal_names: HashMap<String, String>, /* Имена вложенных объектов, описание которых находится
+                                              * в описании объекта (реквизиты, табличные части) */
+    borrowed_id: Option<String>, /* Идентификатор объекта основной конфигурации, если это
+                                  * заимствованный объект расширения */
 }
 
 impl Description {
-    pub fn new(block_id: &String, data: &Vec<u8>) -> Description {
+    pub fn new(block_id: &String, data: &Vec<u8>) -> Result<Description, ConfError> {
+
+        let doc = parse_data(block_id, data)?;
+
+        let root = match doc.root() {
+            Some(v) => v,
+            None => return Err(metadata_error(block_id, "The description is empty.")),
+        };
+
+        // Описание конфигурации начинается с {2,
+        let is_configuration_data = root.item(0).and_then(Value::number) == Some("2");
 
         // Поиск наименования объекта
         let mut object_name: Option<String> = None;
 
-        // Первые три байта это маркер utf-8
-        let is_configuration_data = data.len() > 2 && "{2,".as_bytes().to_vec().eq(&&data[3..6]);
-
         let mut internal_id = block_id.clone();
 
-        for (id, name) in find_ids_and_names(data) {
+        for (id, name) in ids_and_names(&root.items) {
             if is_configuration_data {
                 internal_id = id;
                 object_name = Some(name);
@@ -51,21 +62,20 @@ impl Description {
         }
 
         if object_name.is_none() {
-            error!("Not found name of object for the block: {}", block_id);
-            panic!("Not found name of object for the block: {}", block_id);
+            return Err(metadata_error(block_id, "Not found name of object for the block."));
         }
 
         // Поиск идентификаторов, которые использются другими объектами для указания ссылки на этот объект
-        let end_position = find_text(data, block_id).unwrap();
-        let text_with_reference_ids = part_bytes!(data, 0, end_position);
-        let reference_ids = find_ids(&text_with_reference_ids, RegexTypes::All)
-            .iter()
-            .map(|x| x.clone())
-            .filter(|x| block_id.ne(&*x))
-            .collect();
+        let all_ids = guids(&root.items);
+        let end_position = match all_ids.iter().position(|x| block_id.eq(x)) {
+            Some(v) => v,
+            None => return Err(metadata_error(block_id, "Not found id of the block in the data.")),
+        };
+        let reference_ids = all_ids[..end_position].to_vec();
 
         // Поиск идентификаторов подчиненных объектов
         let mut internal_ids: HashMap<&'static str, Vec<String>> = HashMap::new();
+        let mut internal_names: HashMap<String, String> = HashMap::new();
         for type_id in &[FORMS_ID_DOC,
                          FORMS_ID_CATALOG,
                          PROPS_ID,
@@ -73,41 +83,37 @@ impl Description {
                          COMMANDS_ID,
                          TABULAR_SELECTIONS_ID] {
 
-            let substr = match find_type_coordinates(type_id, data) {
+            let type_list = match find_type_list(&root.items, type_id) {
                 None => continue,
                 Some(v) => v,
             };
 
-            let buf = part_bytes!(data, substr);
-
-            let mut ids: Vec<_> = find_ids_and_names(&buf)
-                .iter()
-                .map(|x| {
-                    let (id, _) = x.clone();
-                    id
-                })
-                .filter(|x| (*x).ne(type_id))
-                .collect();
-            if ids.is_empty() {
-                ids = find_ids(&buf, RegexTypes::All)
-                    .iter()
-                    .map(|x| x.clone())
-                    .filter(|x| (*x).ne(type_id))
-                    .collect();
+            // Элементы списка: {<идентификатор типа>,<количество>,<элементы>}
+            let mut ids: Vec<String> = Vec::new();
+            for node in type_list.items.iter().skip(2) {
+                if let Some((id, name)) = element_id_and_name(node) {
+                    if let Some(name) = name {
+                        internal_names.insert(id.clone(), name);
+                    }
+                    ids.push(id);
+                }
             }
             internal_ids.insert(type_id, ids);
         }
 
-        return Description {
+        let borrowed_id = borrowed_id(&root.items, &internal_id);
+
+        return Ok(Description {
             block_id: block_id.clone(),
             internal_id: internal_id,
             name: object_name.unwrap(),
             reference_ids: reference_ids,
             internal_ids: internal_ids,
-        };
+            internal_names: internal_names,
+            borrowed_id: borrowed_id,
+        });
     }
 
-    #[allow(dead_code)]
     pub fn block_id<'a>(&'a self) -> &'a str {
         &*self.block_id
     }
@@ -124,9 +130,20 @@ impl Description {
         self.internal_ids.get(type_id)
     }
 
+    /// Возвращает имя вложенного объекта, если оно указано в описании объекта
+    pub fn internal_name(&self, id: &str) -> Option<&str> {
+        self.internal_names.get(id).map(|x| &**x)
+    }
+
     pub fn reference_ids<'a>(&'a self) -> &'a Vec<String> {
         &self.reference_ids
     }
+
+    /// Возвращает идентификатор объекта основной конфигурации, который заимствует объект
+    /// расширения. Для собственных объектов возвращается None.
+    pub fn borrowed_id(&self) -> Option<&str> {
+        self.borrowed_id.as_deref()
+    }
 }
 
 #[cfg(test)]
@@ -156,7 +173,7 @@ mod tests {
                                  1708fdaa-cbce-4289-b373-07a5a74bee91},\
                                  {45e46cbc-3e24-4165-8b7b-cc98a6f80211,1,\
                                  53d90c1c-07d1-432b-985c-f808bc26d83b,\
-                                 }{4fe87c89-9ad4-43f6-9fdb-9dc83b3879c6,1,{{0,{0,0,0,{0,{2,\
+                                 },{4fe87c89-9ad4-43f6-9fdb-9dc83b3879c6,1,{{0,{0,0,0,{0,{2,\
                                  bf6f6886-73c1-4515-92ae-697f46bed2ac,\
                                  078a6af8-d22c-4248-9c33-7e90075a3d2c},{7,{3,0,{0},\"\",-1,-1,1,\
                                  0},3,{0},1,{0,0,0},0,{1,1af6d528-0b86-4fba-ab95-bd7475db03ba},\
@@ -164,11 +181,11 @@ mod tests {
                                  \"ДокументКоманда1\",{1,\"ru\",\"Документ \
                                  команда1\"},\"\"},0,0}}}},0}},\
                                  {d5b0e5ed-256d-401c-9c36-f630cafd8a62,1,\
-                                 31529189-9dfa-4dd5-ad66-1c93d70d83e1}}".as_bytes().to_vec();
+                                 31529189-9dfa-4dd5-ad66-1c93d70d83e1}}}".as_bytes().to_vec();
 
 
         let id = String::from("9d1a9f27-cd9f-488b-b5e1-c8b410fb7856");
-        let desc = Description::new(&id, &text);
+        let desc = Description::new(&id, &text).unwrap();
         assert_eq!(id, desc.block_id());
         assert_eq!("Документ", desc.name());
 
@@ -189,6 +206,59 @@ mod tests {
                     "e13bf249-363d-4761-b6d7-420b068d915b"]
                        .to_vec(),
                    *desc.reference_ids());
+        assert_eq!(None, desc.borrowed_id());
+    }
+
+    #[test]
+    fn test_new_borrowed() {
+        let text = "{1,{3,fdb1bf03-6e62-4b6b-8fd1-6ce57d9175fb,{0,{0,0,\
+                    9d1a9f27-cd9f-488b-b5e1-c8b410fb7856},\"Товары\",{0},\"\",1,\
+                    5a7b1f8e-1111-2222-3333-444455556666}}}"
+            .as_bytes()
+            .to_vec();
+
+        let id = String::from("9d1a9f27-cd9f-488b-b5e1-c8b410fb7856");
+        let desc = Description::new(&id, &text).unwrap();
+        assert_eq!("Товары", desc.name());
+        assert_eq!(Some("5a7b1f8e-1111-2222-3333-444455556666"), desc.borrowed_id());
+    }
+
+    #[test]
+    fn test_new_without_comma() {
+        // Списки внутри списка всегда разделяются запятой, "}{" встречается только между
+        // списками верхнего уровня
+        let text = "{1,{37,fdb1bf03-6e62-4b6b-8fd1-6ce57d9175fb,{0,{0,{0,0,\
+                    9d1a9f27-cd9f-488b-b5e1-c8b410fb7856},\"Документ\",{0},\"\"}},\
+                    {45e46cbc-3e24-4165-8b7b-cc98a6f80211,1,53d90c1c-07d1-432b-985c-f808bc26d83b,\
+                    }{4fe87c89-9ad4-43f6-9fdb-9dc83b3879c6,0}}}"
+            .as_bytes()
+            .to_vec();
+        let id = String::from("9d1a9f27-cd9f-488b-b5e1-c8b410fb7856");
+
+        let err = Description::new(&id, &text).err().unwrap();
+        assert_eq!(Some(&*id), err.block_id());
+        assert!(err.message().starts_with("Missing comma before the list"),
+                "{}",
+                err.message());
+
+        let text = "{1,{37,fdb1bf03-6e62-4b6b-8fd1-6ce57d9175fb,{0,{0,{0,0,\
+                    9d1a9f27-cd9f-488b-b5e1-c8b410fb7856},\"Документ\",{0},\"\"}}}}\
+                    {2,53d90c1c-07d1-432b-985c-f808bc26d83b}"
+            .as_bytes()
+            .to_vec();
+
+        let desc = Description::new(&id, &text).unwrap();
+        assert_eq!("Документ", desc.name());
+    }
+
+    #[test]
+    fn test_new_without_name() {
+        let text = "{1,{37,fdb1bf03-6e62-4b6b-8fd1-6ce57d9175fb}}".as_bytes().to_vec();
+        let id = String::from("9d1a9f27-cd9f-488b-b5e1-c8b410fb7856");
+
+        let desc = Description::new(&id, &text);
+        assert!(desc.is_err());
+        assert_eq!(Some(&*id), desc.err().unwrap().block_id());
     }
 
     fn get_internal_ids(val: Option<&Vec<String>>) -> Vec<String> {
@@ -0,0 +1,785 @@
+
+use meta_data::objects::{MetadataObject, metadata_tree};
+use meta_data::modules::{module_kind, normalize_text};
+use structure::block::Block;
+use structure::nested_block::NestedBlock;
+use error::ConfError;
+
+use std::collections::HashMap;
+
+// Количество неизмененных строк вокруг изменений в тексте модуля
+const CONTEXT_LINES: usize = 3;
+
+// Вид и имя объекта с описанием конфигурации
+const CONFIGURATION: &'static str = "Configuration";
+
+/// Вид изменения объекта метаданных
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Status {
+    Added, // объект есть только в новой конфигурации
+    Removed, // объект есть только в исходной конфигурации
+    Changed, // изменены данные объекта
+}
+
+/// Вид изменения строки текста модуля
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum LineChange {
+    Equal,
+    Added,
+    Removed,
+}
+
+/// Фрагмент изменений текста модуля вместе с неизмененными строками вокруг них
+pub struct Hunk {
+    pub old_start: usize, // номер первой строки фрагмента в исходном тексте
+    pub old_lines: usize, // количество строк фрагмента в исходном тексте
+    pub new_start: usize, // номер первой строки фрагмента в новом тексте
+    pub new_lines: usize, // количество строк фрагмента в новом тексте
+    pub lines: Vec<(LineChange, String)>,
+}
+
+/// Изменения текста модуля
+pub struct ModuleDiff {
+    pub name: String, // вид модуля (ObjectModule, ManagerModule, Module, ...)
+    pub hunks: Vec<Hunk>,
+}
+
+/// Изменения объекта метаданных
+pub struct ObjectDiff {
+    pub status: Status,
+    pub kind: &'static str, // каталог типа объекта (Catalogs, Forms, ...)
+    pub name: String, // имя объекта
+    pub full_name: String, // полное имя: <Тип>.<Имя>[.<Тип>.<Имя>]
+    pub old_name: Option<String>, // имя объекта в исходной конфигурации, если объект переименован
+    pub id: String, // идентификатор блока с описанием объекта
+    pub description: bool, // изменено описание объекта
+    pub data: bool, // изменены прочие данные объекта (формы, макеты, ...)
+    pub modules: Vec<ModuleDiff>, // изменения текстов модулей
+}
+
+/// Различия двух конфигураций по объектам метаданных
+pub struct Diff {
+    pub objects: Vec<ObjectDiff>, // измененные объекты, упорядоченные по полному имени
+}
+
+/// Сравнить конфигурации. Блоки сопоставляются по идентификаторам, объекты метаданных
+/// сопоставляются по идентификаторам блоков с их описанием, поэтому переименованный объект
+/// считается измененным. Для измененных модулей возвращаются различия текстов.
+pub fn diff(old_blocks: &Vec<Block>, new_blocks: &Vec<Block>) -> Result<Diff, ConfError> {
+
+    info!("Comparison of configurations");
+
+    let old_tree = metadata_tree(old_blocks)?;
+    let new_tree = metadata_tree(new_blocks)?;
+
+    let comparer = Comparer {
+        old_groups: groups(old_blocks),
+        new_groups: groups(new_blocks),
+    };
+
+    let mut objects: Vec<ObjectDiff> = Vec::new();
+
+    // Описание и модули конфигурации
+    let changes = comparer.compare(&[(&old_tree.conf_id, &new_tree.conf_id),
+                                     (&old_tree.conf_internal_id, &new_tree.conf_internal_id)],
+                                   "")?;
+    if let Some((description, data, modules)) = changes {
+        objects.push(ObjectDiff {
+            status: Status::Changed,
+            kind: CONFIGURATION,
+            name: String::from(CONFIGURATION),
+            full_name: String::from(CONFIGURATION),
+            old_name: None,
+            id: new_tree.conf_id.clone(),
+            description: description,
+            data: data,
+            modules: modules,
+        });
+    }
+
+    comparer.compare_objects(&old_tree.objects, &new_tree.objects, "", &mut objects)?;
+
+    objects.sort_by(|a, b| a.full_name.cmp(&b.full_name));
+
+    info!("-Comparison of configurations: {}", objects.len());
+
+    return Ok(Diff { objects: objects });
+}
+
+impl Status {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Status::Added => "added",
+            Status::Removed => "removed",
+            Status::Changed => "changed",
+        }
+    }
+}
+
+impl LineChange {
+    fn prefix(&self) -> char {
+        match *self {
+            LineChange::Equal => ' ',
+            LineChange::Added => '+',
+            LineChange::Removed => '-',
+        }
+    }
+}
+
+impl Hunk {
+    // Заголовок фрагмента в формате unified diff
+    fn header(&self) -> String {
+        format!("@@ -{},{} +{},{} @@",
+                self.old_start,
+                self.old_lines,
+                self.new_start,
+                self.new_lines)
+    }
+}
+
+impl Diff {
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty()
+    }
+
+    // Возвращает количество объектов с указанным видом изменения
+    pub fn count(&self, status: Status) -> usize {
+        self.objects.iter().filter(|v| v.status == status).count()
+    }
+
+    /// Возвращает описание различий в виде текста.
+    /// Пример:
+    ///   changed Catalogs.Товары
+    ///       description
+    ///       module ObjectModule
+    ///           @@ -1,2 +1,2 @@
+    ///            Процедура Тест()
+    ///           -    А = 1;
+    ///           +    А = 2;
+    pub fn to_text(&self) -> String {
+
+        let mut text = String::new();
+
+        for object in &self.objects {
+            text.push_str(&format!("{} {}", object.status.name(), object.full_name));
+            if let Some(ref old_name) = object.old_name {
+                text.push_str(&format!(" (old name: {})", old_name));
+            }
+            text.push('\n');
+
+            if object.description {
+                text.push_str("    description\n");
+            }
+            if object.data {
+                text.push_str("    data\n");
+            }
+
+            for module in &object.modules {
+                text.push_str(&format!("    module {}\n", module.name));
+                for hunk in &module.hunks {
+                    text.push_str(&format!("        {}\n", hunk.header()));
+                    for &(change, ref line) in &hunk.lines {
+                        text.push_str(&format!("        {}{}\n", change.prefix(), line));
+                    }
+                }
+            }
+        }
+
+        text.push_str(&format!("Added: {}, removed: {}, changed: {}\n",
+                               self.count(Status::Added),
+                               self.count(Status::Removed),
+                               self.count(Status::Changed)));
+
+        return text;
+    }
+
+    /// Возвращает описание различий в формате JSON
+    pub fn to_json(&self) -> String {
+
+        let objects: Vec<String> = self.objects.iter().map(object_to_json).collect();
+
+        return format!("{{\"added\":{},\"removed\":{},\"changed\":{},\"objects\":[{}]}}\n",
+                       self.count(Status::Added),
+                       self.count(Status::Removed),
+                       self.count(Status::Changed),
+                       objects.join(","));
+    }
+}
+
+struct Comparer<'a> {
+    old_groups: HashMap<&'a str, Vec<&'a Block>>, // блоки исходной конфигурации по идентификатору объекта
+    new_groups: HashMap<&'a str, Vec<&'a Block>>, // блоки новой конфигурации по идентификатору объекта
+}
+
+impl<'a> Comparer<'a> {
+    // Сравнить списки объектов метаданных и их подчиненных объектов
+    fn compare_objects(&self,
+                       old_objects: &[MetadataObject],
+                       new_objects: &[MetadataObject],
+                       prefix: &str,
+                       retval: &mut Vec<ObjectDiff>)
+                       -> Result<(), ConfError> {
+
+        for new_object in new_objects {
+            let old_object = old_objects.iter().find(|v| v.id.eq(&new_object.id));
+            let full_name = full_name(prefix, new_object);
+
+            let old_object = match old_object {
+                Some(v) => v,
+                None => {
+                    retval.push(object_diff(Status::Added, new_object, full_name));
+                    continue;
+                }
+            };
+
+            let changes = self.compare(&[(&old_object.id, &new_object.id)], new_object.type_id)?;
+            if let Some((description, data, modules)) = changes {
+                let mut object = object_diff(Status::Changed, new_object, full_name.clone());
+                if old_object.name.ne(&new_object.name) {
+                    object.old_name = Some(old_object.name.clone());
+                }
+                object.description = description;
+                object.data = data;
+                object.modules = modules;
+                retval.push(object);
+            }
+
+            self.compare_objects(&old_object.objects, &new_object.objects, &full_name, retval)?;
+        }
+
+        for old_object in old_objects {
+            if new_objects.iter().all(|v| v.id.ne(&old_object.id)) {
+                retval.push(object_diff(Status::Removed, old_object, full_name(prefix, old_object)));
+            }
+        }
+
+        Ok(())
+    }
+
+    // Сравнить блоки объекта (<идентификатор> и <идентификатор>.<номер>).
+    // Возвращает признаки изменения описания и прочих данных и изменения модулей,
+    // если блоки объекта различаются.
+    fn compare(&self,
+               ids: &[(&String, &String)],
+               type_id: &str)
+               -> Result<Option<(bool, bool, Vec<ModuleDiff>)>, ConfError> {
+
+        let mut description = false;
+        let mut data = false;
+        let mut modules: Vec<ModuleDiff> = Vec::new();
+
+        for &(old_id, new_id) in ids {
+            let old_blocks = blocks_by_suffix(&self.old_groups, old_id);
+            let new_blocks = blocks_by_suffix(&self.new_groups, new_id);
+
+            let mut suffixes: Vec<&str> = new_blocks.iter().map(|v| v.0).collect();
+            for &(suffix, _) in &old_blocks {
+                if !suffixes.contains(&suffix) {
+                    suffixes.push(suffix);
+                }
+            }
+
+            for suffix in suffixes {
+                let old_data = nested_blocks(&old_blocks, suffix)?;
+                let new_data = nested_blocks(&new_blocks, suffix)?;
+
+                let mut nested_ids: Vec<&String> = new_data.iter().map(|v| v.attrs.id()).collect();
+                for nested_block in &old_data {
+                    if !nested_ids.contains(&nested_block.attrs.id()) {
+                        nested_ids.push(nested_block.attrs.id());
+                    }
+                }
+
+                for nested_id in nested_ids {
+                    let old_text = nested_data(&old_data, nested_id);
+                    let new_text = nested_data(&new_data, nested_id);
+                    if old_text == new_text {
+                        continue;
+                    }
+
+                    // Блок без номера - описание объекта
+                    if suffix.is_empty() {
+                        description = true;
+                        continue;
+                    }
+
+                    match module_kind(type_id, suffix, nested_id) {
+                        Some(name) => {
+                            let hunks = text_diff(&module_lines(old_text), &module_lines(new_text));
+                            if !hunks.is_empty() {
+                                modules.push(ModuleDiff {
+                                    name: name,
+                                    hunks: hunks,
+                                });
+                            }
+                        }
+                        None => data = true,
+                    }
+                }
+            }
+        }
+
+        if !description && !data && modules.is_empty() {
+            return Ok(None);
+        }
+
+        return Ok(Some((description, data, modules)));
+    }
+}
+
+// Возвращает блоки по идентификатору объекта (имени блока до точки)
+fn groups<'a>(blocks: &'a Vec<Block>) -> HashMap<&'a str, Vec<&'a Block>> {
+
+    let mut retval: HashMap<&str, Vec<&Block>> = HashMap::new();
+    for block in blocks.iter() {
+        let object_id = block.id().split('.').next().unwrap_or("");
+        retval.entry(object_id).or_insert(Vec::new()).push(block);
+    }
+
+    return retval;
+}
+
+// Возвращает блоки объекта с номерами блоков (для описания объекта - пустая строка)
+fn blocks_by_suffix<'a>(groups: &HashMap<&str, Vec<&'a Block>>,
+                        object_id: &str)
+                        -> Vec<(&'a str, &'a Block)> {
+
+    let blocks = match groups.get(object_id) {
+        Some(v) => v,
+        None => return Vec::new(),
+    };
+
+    blocks.iter()
+        .map(|block| {
+            let suffix = match block.id().len() > object_id.len() {
+                true => &block.id()[object_id.len() + 1..],
+                false => "",
+            };
+            (suffix, *block)
+        })
+        .collect()
+}
+
+// Возвращает вложенные блоки блока с указанным номером
+fn nested_blocks(blocks: &Vec<(&str, &Block)>, suffix: &str) -> Result<Vec<NestedBlock>, ConfError> {
+    match blocks.iter().find(|v| v.0.eq(suffix)) {
+        Some(&(_, block)) => block.get_data(),
+        None => Ok(Vec::new()),
+    }
+}
+
+fn nested_data<'a>(nested_blocks: &'a Vec<NestedBlock>, nested_id: &String) -> Option<&'a Vec<u8>> {
+    nested_blocks.iter().find(|v| v.attrs.id().eq(nested_id)).map(|v| &v.data)
+}
+
+// Возвращает строки текста модуля
+fn module_lines(data: Option<&Vec<u8>>) -> Vec<String> {
+    match data {
+        Some(v) => {
+            String::from_utf8_lossy(&normalize_text(v))
+                .lines()
+                .map(String::from)
+                .collect()
+        }
+        None => Vec::new(),
+    }
+}
+
+fn full_name(prefix: &str, object: &MetadataObject) -> String {
+    match prefix.is_empty() {
+        true => format!("{}.{}", object.kind, object.name),
+        false => format!("{}.{}.{}", prefix, object.kind, object.name),
+    }
+}
+
+fn object_diff(status: Status, object: &MetadataObject, full_name: String) -> ObjectDiff {
+    ObjectDiff {
+        status: status,
+        kind: object.kind,
+        name: object.name.clone(),
+        full_name: full_name,
+        old_name: None,
+        id: object.id.clone(),
+        description: false,
+        data: false,
+        modules: Vec::new(),
+    }
+}
+
+/// Сравнить тексты построчно (алгоритм Майерса). Возвращает фрагменты изменений
+/// с CONTEXT_LINES неизмененными строками вокруг них.
+pub fn text_diff(old_lines: &[String], new_lines: &[String]) -> Vec<Hunk> {
+
+    let changes = line_changes(old_lines, new_lines);
+
+    // Номера строк исходного и нового текста перед каждым изменением
+    let mut positions: Vec<(usize, usize)> = Vec::with_capacity(changes.len());
+    let (mut old_pos, mut new_pos) = (0, 0);
+    for &(change, _) in &changes {
+        positions.push((old_pos, new_pos));
+        match change {
+            LineChange::Equal => {
+                old_pos += 1;
+                new_pos += 1;
+            }
+            LineChange::Added => new_pos += 1,
+            LineChange::Removed => old_pos += 1,
+        }
+    }
+
+    // Группируем изменения, между которыми не больше 2 * CONTEXT_LINES неизмененных строк
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for (i, &(change, _)) in changes.iter().enumerate() {
+        if change == LineChange::Equal {
+            continue;
+        }
+
+        let is_near = match ranges.last() {
+            Some(&(_, end)) => i - end <= 2 * CONTEXT_LINES + 1,
+            None => false,
+        };
+        match is_near {
+            true => ranges.last_mut().unwrap().1 = i,
+            false => ranges.push((i, i)),
+        }
+    }
+
+    let mut retval: Vec<Hunk> = Vec::new();
+
+    for (start, end) in ranges {
+        let from = start.saturating_sub(CONTEXT_LINES);
+        let to = (end + CONTEXT_LINES + 1).min(changes.len());
+
+        let lines: Vec<(LineChange, String)> = changes[from..to]
+            .iter()
+            .map(|&(change, line)| (change, line.clone()))
+            .collect();
+
+        let old_count = lines.iter().filter(|v| v.0 != LineChange::Added).count();
+        let new_count = lines.iter().filter(|v| v.0 != LineChange::Removed).count();
+        let (old_pos, new_pos) = positions[from];
+
+        retval.push(Hunk {
+            old_start: if old_count == 0 { old_pos } else { old_pos + 1 },
+            old_lines: old_count,
+            new_start: if new_count == 0 { new_pos } else { new_pos + 1 },
+            new_lines: new_count,
+            lines: lines,
+        });
+    }
+
+    return retval;
+}
+
+/// Возвращает для каждой строки исходного текста номер совпадающей с ней строки нового текста
+/// или None, если строка удалена или изменена
+pub fn line_matches(old_lines: &[String], new_lines: &[String]) -> Vec<Option<usize>> {
+
+    let mut retval: Vec<Option<usize>> = Vec::with_capacity(old_lines.len());
+    let mut new_pos: usize = 0;
+
+    for (change, _) in line_changes(old_lines, new_lines) {
+        match change {
+            LineChange::Equal => {
+                retval.push(Some(new_pos));
+                new_pos += 1;
+            }
+            LineChange::Added => new_pos += 1,
+            LineChange::Removed => retval.push(None),
+        }
+    }
+
+    return retval;
+}
+
+// Возвращает кратчайшую последовательность изменений, преобразующую исходный текст в новый
+fn line_changes<'a>(old_lines: &'a [String],
+                    new_lines: &'a [String])
+                    -> Vec<(LineChange, &'a String)> {
+
+    // Общие начало и конец текстов не участвуют в поиске
+    let prefix = old_lines.iter().zip(new_lines.iter()).take_while(|v| v.0 == v.1).count();
+    let suffix = old_lines[prefix..]
+        .iter()
+        .rev()
+        .zip(new_lines[prefix..].iter().rev())
+        .take_while(|v| v.0 == v.1)
+        .count();
+
+    let old = &old_lines[prefix..old_lines.len() - suffix];
+    let new = &new_lines[prefix..new_lines.len() - suffix];
+
+    let mut retval: Vec<(LineChange, &String)> = Vec::new();
+    retval.extend(old_lines[..prefix].iter().map(|v| (LineChange::Equal, v)));
+    retval.extend(myers(old, new));
+    retval.extend(old_lines[old_lines.len() - suffix..].iter().map(|v| (LineChange::Equal, v)));
+
+    return retval;
+}
+
+fn myers<'a>(old: &'a [String], new: &'a [String]) -> Vec<(LineChange, &'a String)> {
+
+    let (n, m) = (old.len() as isize, new.len() as isize);
+    let max = n + m;
+    let offset = max + 1;
+
+    // v[k] - самая дальняя позиция в исходном тексте на диагонали k.
+    // Для восстановления пути сохраняются значения v перед каждым шагом d (диагонали -d..d).
+    let mut v: Vec<isize> = vec![0; (2 * max + 3) as usize];
+    let mut trace: Vec<Vec<isize>> = Vec::new();
+
+    'search: for d in 0..max + 1 {
+        trace.push(v[(offset - d) as usize..(offset + d + 1) as usize].to_vec());
+
+        let mut k = -d;
+        while k <= d {
+            let i = (offset + k) as usize;
+            let mut x = match k == -d || (k != d && v[i - 1] < v[i + 1]) {
+                true => v[i + 1],
+                false => v[i - 1] + 1,
+            };
+            let mut y = x - k;
+
+            while x < n && y < m && old[x as usize] == new[y as usize] {
+                x += 1;
+                y += 1;
+            }
+
+            v[i] = x;
+
+            if x >= n && y >= m {
+                break 'search;
+            }
+
+            k += 2;
+        }
+    }
+
+    let mut retval: Vec<(LineChange, &String)> = Vec::new();
+    let (mut x, mut y) = (n, m);
+
+    for d in (0..trace.len() as isize).rev() {
+        let v = &trace[d as usize];
+        let get = |k: isize| v[(k + d) as usize];
+
+        let k = x - y;
+        let prev_k = match k == -d || (k != d && get(k - 1) < get(k + 1)) {
+            true => k + 1,
+            false => k - 1,
+        };
+        let prev_x = match d {
+            0 => 0,
+            _ => get(prev_k),
+        };
+        let prev_y = prev_x - prev_k;
+
+        while x > prev_x && y > prev_y {
+            retval.push((LineChange::Equal, &old[(x - 1) as usize]));
+            x -= 1;
+            y -= 1;
+        }
+
+        if d > 0 {
+            match x == prev_x {
+                true => retval.push((LineChange::Added, &new[(y - 1) as usize])),
+                false => retval.push((LineChange::Removed, &old[(x - 1) as usize])),
+            }
+        }
+
+        x = prev_x;
+        y = prev_y;
+    }
+
+    retval.reverse();
+
+    return retval;
+}
+
+fn object_to_json(object: &ObjectDiff) -> String {
+
+    let modules: Vec<String> = object.modules
+        .iter()
+        .map(|module| {
+            let hunks: Vec<String> = module.hunks
+                .iter()
+                .map(|hunk| {
+                    let lines: Vec<String> = hunk.lines
+                        .iter()
+                        .map(|&(change, ref line)| json_string(&format!("{}{}", change.prefix(), line)))
+                        .collect();
+
+                    format!("{{\"old_start\":{},\"old_lines\":{},\"new_start\":{},\"new_lines\":{},\
+                             \"lines\":[{}]}}",
+                            hunk.old_start,
+                            hunk.old_lines,
+                            hunk.new_start,
+                            hunk.new_lines,
+                            lines.join(","))
+                })
+                .collect();
+
+            format!("{{\"name\":{},\"hunks\":[{}]}}",
+                    json_string(&module.name),
+                    hunks.join(","))
+        })
+        .collect();
+
+    let old_name = match object.old_name {
+        Some(ref v) => json_string(v),
+        None => String::from("null"),
+    };
+
+    format!("{{\"status\":{},\"type\":{},\"name\":{},\"full_name\":{},\"old_name\":{},\"id\":{},\
+             \"description\":{},\"data\":{},\"modules\":[{}]}}",
+            json_string(object.status.name()),
+            json_string(object.kind),
+            json_string(&object.name),
+            json_string(&object.full_name),
+            old_name,
+            json_string(&object.id),
+            object.description,
+            object.data,
+            modules.join(","))
+}
+
+/// Строка в формате JSON
+pub fn json_string(value: &str) -> String {
+
+    let mut retval = String::with_capacity(value.len() + 2);
+    retval.push('"');
+
+    for c in value.chars() {
+        match c {
+            '"' => retval.push_str("\\\""),
+            '\\' => retval.push_str("\\\\"),
+            '\n' => retval.push_str("\\n"),
+            '\r' => retval.push_str("\\r"),
+            '\t' => retval.push_str("\\t"),
+            c if (c as u32) < 0x20 => retval.push_str(&format!("\\u{:04x}", c as u32)),
+            c => retval.push(c),
+        }
+    }
+
+    retval.push('"');
+
+    return retval;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LineChange, Status, diff, text_diff};
+    use meta_data::types;
+    use structure::block::Block;
+
+    fn lines(text: &str) -> Vec<String> {
+        text.lines().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_text_diff() {
+
+        let old = lines("1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n11\n12\n13\n14\n15");
+        let new = lines("1\n2\n3\n4\nfive\n6\n7\n8\n9\n10\n11\n12\n13\n14\n15\n16");
+
+        let hunks = text_diff(&old, &new);
+        assert_eq!(2, hunks.len());
+
+        assert_eq!((2, 7, 2, 7),
+                   (hunks[0].old_start, hunks[0].old_lines, hunks[0].new_start, hunks[0].new_lines));
+        assert_eq!((LineChange::Removed, String::from("5")), hunks[0].lines[3]);
+        assert_eq!((LineChange::Added, String::from("five")), hunks[0].lines[4]);
+
+        assert_eq!((13, 3, 13, 4),
+                   (hunks[1].old_start, hunks[1].old_lines, hunks[1].new_start, hunks[1].new_lines));
+        assert_eq!((LineChange::Added, String::from("16")), hunks[1].lines[3]);
+
+        assert!(text_diff(&old, &old).is_empty());
+
+        let hunks = text_diff(&Vec::new(), &lines("a\nb"));
+        assert_eq!((0, 0, 1, 2),
+                   (hunks[0].old_start, hunks[0].old_lines, hunks[0].new_start, hunks[0].new_lines));
+
+        let hunks = text_diff(&lines("a\nb\nc"), &lines("c\nb\na"));
+        let old_count = hunks[0].lines.iter().filter(|v| v.0 != LineChange::Added).count();
+        let new_count = hunks[0].lines.iter().filter(|v| v.0 != LineChange::Removed).count();
+        assert_eq!((3, 3), (old_count, new_count));
+    }
+
+    fn configuration(catalogs: &[(&str, &str, &str)]) -> Vec<Block> {
+
+        let conf_id = "be22b29f-2db7-4fcb-8772-eeb5500d2170";
+        let ids: Vec<&str> = catalogs.iter().map(|v| v.0).collect();
+
+        let root = format!("{{2,{},}}", conf_id);
+        let conf = format!("{{2,{{{{0,0,{}}},\"Конфигурация\"}},{{{},{},{}}}}}",
+                           conf_id,
+                           types::CATALOG,
+                           ids.len(),
+                           ids.join(","));
+
+        let mut blocks = vec![Block::new("root", &root.into_bytes()),
+                              Block::new(conf_id, &conf.into_bytes())];
+
+        for &(id, name, module) in catalogs {
+            let catalog = format!("{{1,{{{{0,0,{}}},\"{}\"}}}}", id, name);
+            blocks.push(Block::new(id, &catalog.into_bytes()));
+            blocks.push(Block::from_parts(&format!("{}.0", id),
+                                          true,
+                                          vec![(String::from("info"), b"{3,1,0}".to_vec()),
+                                               (String::from("text"), module.as_bytes().to_vec())],
+                                          None));
+        }
+
+        return blocks;
+    }
+
+    #[test]
+    fn test_diff() {
+
+        let goods = "9d1a9f27-cd9f-488b-b5e1-c8b410fb7856";
+        let clients = "0b1a9f27-cd9f-488b-b5e1-c8b410fb7856";
+        let orders = "1c1a9f27-cd9f-488b-b5e1-c8b410fb7856";
+
+        let old = configuration(&[(goods, "Товары", "А = 1;\r\nБ = 2;\r\n"),
+                                  (clients, "Клиенты", "// clients")]);
+        let new = configuration(&[(goods, "Номенклатура", "А = 1;\r\nБ = 3;\r\n"),
+                                  (orders, "Заказы", "// orders")]);
+
+        let test = diff(&old, &new).unwrap();
+        assert_eq!(4, test.objects.len());
+
+        let names: Vec<(&str, Status)> = test.objects
+            .iter()
+            .map(|v| (&*v.full_name, v.status))
+            .collect();
+        assert_eq!(vec![("Catalogs.Заказы", Status::Added),
+                        ("Catalogs.Клиенты", Status::Removed),
+                        ("Catalogs.Номенклатура", Status::Changed),
+                        ("Configuration", Status::Changed)],
+                   names);
+
+        let changed = &test.objects[2];
+        assert_eq!(Some(String::from("Товары")), changed.old_name);
+        assert!(changed.description);
+        assert!(!changed.data);
+        assert_eq!(1, changed.modules.len());
+        assert_eq!("ObjectModule", changed.modules[0].name);
+        assert_eq!(vec![(LineChange::Equal, String::from("А = 1;")),
+                        (LineChange::Removed, String::from("Б = 2;")),
+                        (LineChange::Added, String::from("Б = 3;"))],
+                   changed.modules[0].hunks[0].lines);
+
+        let text = test.to_text();
+        assert!(text.contains("changed Catalogs.Номенклатура (old name: Товары)\n    description\n    \
+                               module ObjectModule\n        @@ -1,2 +1,2 @@\n         А = 1;\n        \
+                               -Б = 2;\n        +Б = 3;\n"));
+        assert!(text.ends_with("Added: 1, removed: 1, changed: 2\n"));
+
+        let json = test.to_json();
+        assert!(json.starts_with("{\"added\":1,\"removed\":1,\"changed\":2,\"objects\":[{\"status\":\
+                                  \"added\",\"type\":\"Catalogs\",\"name\":\"Заказы\""));
+        assert!(json.contains("\"lines\":[\" А = 1;\",\"-Б = 2;\",\"+Б = 3;\"]"));
+
+        assert!(diff(&new, &new).unwrap().is_empty());
+    }
+}
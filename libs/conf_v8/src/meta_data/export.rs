@@ -0,0 +1,365 @@
+
+use file_system;
+
+use meta_data::types;
+use meta_data::objects::{MetadataObject, metadata_tree};
+use meta_data::index::{Index, IndexObject, IndexFile};
+use structure::block::Block;
+use structure::layout::ContainerLayout;
+use structure::manifest::{BlockLayout, Manifest};
+use structure::writer::process_blocks;
+use error::{ConfError, io_error};
+
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+
+const CONFIGURATION_DIR: &'static str = "Configuration"; // описание и модули конфигурации
+const OTHER_DIR: &'static str = "Other"; // блоки, которые не относятся к объектам метаданных
+const METADATA_FILE: &'static str = "Metadata"; // описание объекта метаданных
+
+/// Записать блоки конфигурации в каталог в виде дерева объектов метаданных:
+///   Catalogs/<Имя>/Metadata.txt - описание объекта,
+///   Catalogs/<Имя>/ObjectModule.bsl - модуль объекта,
+///   Catalogs/<Имя>/Forms/<Имя формы>/Module.bsl - модуль формы и т.д.
+/// Вместе с файлами записываются индекс, в котором файлам сопоставлены идентификаторы блоков,
+/// и описание конфигурации для восстановления исходного конфигурационного файла.
+pub fn export(blocks: &Vec<Block>,
+              container: &ContainerLayout,
+              path_to_dir: &String,
+              workers: usize)
+              -> Result<(), ConfError> {
+
+    info!("Export of metadata");
+
+    // Распаковываем данные блоков в нескольких потоках
+    let layouts = process_blocks(blocks, workers, |block| block.layout())?;
+
+    let tree = metadata_tree(blocks)?;
+
+    let mut exporter = Exporter::new(blocks, &layouts, path_to_dir, &tree.conf_id);
+
+    exporter.export_blocks(&tree.conf_id, "", CONFIGURATION_DIR)?;
+    exporter.export_blocks(&tree.conf_internal_id, "", CONFIGURATION_DIR)?;
+
+    for object in &tree.objects {
+        let object = exporter.export_object(object, "")?;
+        exporter.index.objects.push(object);
+    }
+
+    exporter.export_other_blocks()?;
+    exporter.index.write(path_to_dir)?;
+
+    let manifest = Manifest {
+        container: container.clone(),
+        blocks: layouts.clone(),
+    };
+    manifest.write(path_to_dir)?;
+
+    info!("-Export of metadata");
+
+    Ok(())
+}
+
+// Возвращает имя файла для блока с данными объекта (<идентификатор объекта>.<номер>)
+pub fn block_file_name(type_id: &str, suffix: &str, is_module: bool) -> String {
+
+    // Модули объектов по номеру блока из описания типа
+    if is_module {
+        if let Some(kind) = types::find_type(type_id).and_then(|x| x.module_kind(suffix)) {
+            return String::from(kind);
+        }
+    }
+
+    let name = match (type_id, suffix) {
+        (types::COMMON_FORMS, "0") |
+        (types::FORMS_ID_DOC, "0") |
+        (types::FORMS_ID_CATALOG, "0") => "Form",
+        (types::COMMON_TEMPLATES, "0") |
+        (types::LAYOUTS_ID, "0") => "Template",
+        _ if is_module => return format!("Module.{}", suffix),
+        _ => return format!("Data.{}", suffix),
+    };
+
+    return String::from(name);
+}
+
+struct Exporter<'a> {
+    blocks: &'a Vec<Block>,
+    layouts: &'a Vec<BlockLayout>,
+    path_to_dir: &'a String,
+    groups: HashMap<String, Vec<usize>>, // блоки по идентификатору объекта (имя блока до точки)
+    exported: Vec<bool>, // признаки выгруженных блоков
+    paths: HashSet<String>, // занятые пути (без учета регистра)
+    index: Index,
+}
+
+impl<'a> Exporter<'a> {
+    fn new(blocks: &'a Vec<Block>,
+           layouts: &'a Vec<BlockLayout>,
+           path_to_dir: &'a String,
+           conf_id: &str)
+           -> Exporter<'a> {
+
+        let mut groups: HashMap<String, Vec<usize>> = HashMap::new();
+        for (i, block) in blocks.iter().enumerate() {
+            let object_id = block.id().split('.').next().unwrap_or("");
+            groups.entry(String::from(object_id)).or_insert(Vec::new()).push(i);
+        }
+
+        return Exporter {
+            blocks: blocks,
+            layouts: layouts,
+            path_to_dir: path_to_dir,
+            groups: groups,
+            exported: vec![false; blocks.len()],
+            paths: HashSet::new(),
+            index: Index::new(conf_id),
+        };
+    }
+
+    // Выгрузить объект метаданных вместе с подчиненными объектами (формами, макетами, командами)
+    fn export_object(&mut self,
+                     object: &MetadataObject,
+                     parent_dir: &str)
+                     -> Result<IndexObject, ConfError> {
+
+        let path = match parent_dir.is_empty() {
+            true => format!("{}/{}", object.kind, object.name),
+            false => format!("{}/{}/{}", parent_dir, object.kind, object.name),
+        };
+        let path = self.unique_path(path, &object.id);
+
+        self.export_blocks(&object.id, object.type_id, &path)?;
+
+        let mut retval = IndexObject {
+            kind: String::from(object.kind),
+            name: object.name.clone(),
+            id: object.id.clone(),
+            path: path.clone(),
+            objects: Vec::new(),
+        };
+
+        for nested_object in &object.objects {
+            retval.objects.push(self.export_object(nested_object, &path)?);
+        }
+
+        return Ok(retval);
+    }
+
+    // Выгрузить блоки объекта: блок описания и блоки с данными объекта (<идентификатор>.<номер>)
+    fn export_blocks(&mut self, object_id: &str, type_id: &str, dir: &str) -> Result<(), ConfError> {
+
+        let ids = match self.groups.get(object_id) {
+            Some(v) => v.clone(),
+            None => return Ok(()),
+        };
+
+        for i in ids {
+            if self.exported[i] {
+                continue;
+            }
+
+            let block_id = self.blocks[i].id().clone();
+            let file_name = match block_id.eq(object_id) {
+                true => String::from(METADATA_FILE),
+                false => {
+                    let is_module = self.layouts[i]
+                        .nested
+                        .iter()
+                        .any(|v| v.attrs.id().eq("text"));
+                    block_file_name(type_id, &block_id[object_id.len() + 1..], is_module)
+                }
+            };
+
+            self.export_block(i, dir, &file_name)?;
+        }
+
+        Ok(())
+    }
+
+    // Выгрузить данные блока. Данные простого блока записываются в файл <имя>.txt,
+    // текст модуля - в файл <имя>.bsl, остальные вложенные блоки - в файлы <имя>.<вложенный блок>
+    fn export_block(&mut self, i: usize, dir: &str, file_name: &str) -> Result<(), ConfError> {
+
+        let block_id = self.blocks[i].id().clone();
+        let is_group = self.layouts[i].container.is_some();
+
+        for nested_block in self.blocks[i].get_data()? {
+            let nested_id = nested_block.attrs.id().clone();
+
+            let (path, nested) = match is_group {
+                false => (format!("{}/{}.txt", dir, file_name), None),
+                true => {
+                    let path = match &*nested_id {
+                        "text" => format!("{}/{}.bsl", dir, file_name),
+                        "module" => format!("{}/Module.bsl", dir),
+                        _ => format!("{}/{}.{}", dir, file_name, nested_id),
+                    };
+                    (path, Some(nested_id))
+                }
+            };
+
+            self.write_file(path, &block_id, nested, &nested_block.data)?;
+        }
+
+        self.exported[i] = true;
+
+        Ok(())
+    }
+
+    // Выгрузить блоки, которые не относятся к объектам метаданных, в том же виде,
+    // в котором они записываются при распаковке
+    fn export_other_blocks(&mut self) -> Result<(), ConfError> {
+
+        for i in 0..self.blocks.len() {
+            if self.exported[i] {
+                continue;
+            }
+
+            let block_id = self.blocks[i].id().clone();
+            let is_group = self.layouts[i].container.is_some();
+
+            for nested_block in self.blocks[i].get_data()? {
+                let (path, nested) = match is_group {
+                    false => (format!("{}/{}", OTHER_DIR, block_id), None),
+                    true => {
+                        let nested_id = nested_block.attrs.id().clone();
+                        (format!("{}/{}/{}", OTHER_DIR, block_id, nested_id), Some(nested_id))
+                    }
+                };
+
+                self.write_file(path, &block_id, nested, &nested_block.data)?;
+            }
+
+            self.exported[i] = true;
+        }
+
+        Ok(())
+    }
+
+    // Записать файл и добавить его в индекс
+    fn write_file(&mut self,
+                  path: String,
+                  block_id: &String,
+                  nested: Option<String>,
+                  data: &Vec<u8>)
+                  -> Result<(), ConfError> {
+
+        let path = self.unique_path(path, block_id);
+
+        let mut full_path = PathBuf::from(self.path_to_dir);
+        for part in path.split('/') {
+            full_path.push(part);
+        }
+        if let Some(parent) = full_path.parent() {
+            file_system::create_dir(&file_system::path_to_str(parent));
+        }
+
+        let full_path = file_system::path_to_str(full_path.as_path());
+        match file_system::write_file(&full_path, data) {
+            Ok(_) => (),
+            Err(e) => return Err(io_error(&full_path, e)),
+        }
+
+        self.index.files.push(IndexFile {
+            path: path,
+            block: block_id.clone(),
+            nested: nested,
+        });
+
+        Ok(())
+    }
+
+    // Возвращает свободный путь. Если путь уже занят (например, у объектов одинаковые имена),
+    // то к нему добавляется идентификатор блока.
+    fn unique_path(&mut self, path: String, id: &str) -> String {
+
+        let path = match self.paths.contains(&path.to_lowercase()) {
+            true => format!("{}.{}", path, id),
+            false => path,
+        };
+
+        self.paths.insert(path.to_lowercase());
+
+        return path;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{export, block_file_name};
+    use meta_data::types;
+    use structure::block::Block;
+    use structure::layout::ContainerLayout;
+
+    use file_system;
+    use std::env;
+    use std::path::Path;
+
+    #[test]
+    fn test_block_file_name() {
+        assert_eq!("ObjectModule", block_file_name(types::CATALOG, "0", true));
+        assert_eq!("ManagerModule", block_file_name(types::DOCUMENT, "2", true));
+        assert_eq!("Form", block_file_name(types::FORMS_ID_DOC, "0", false));
+        assert_eq!("Module.1", block_file_name(types::CATALOG, "1", true));
+        assert_eq!("RecordSetModule", block_file_name(types::INFORMATION_REGISTER, "1", true));
+        assert_eq!("Data.4", block_file_name("", "4", false));
+    }
+
+    #[test]
+    fn test_export() {
+
+        let conf_id = "be22b29f-2db7-4fcb-8772-eeb5500d2170";
+        let catalog_id = "9d1a9f27-cd9f-488b-b5e1-c8b410fb7856";
+        let form_id = "31529189-9dfa-4dd5-ad66-1c93d70d83e1";
+
+        let root = format!("{{2,{},}}", conf_id);
+        let conf = format!("{{2,{{{{0,0,{}}},\"Конфигурация\"}},{{{},1,{}}}}}",
+                           conf_id,
+                           types::CATALOG,
+                           catalog_id);
+        let catalog = format!("{{1,{{{{0,0,{}}},\"Товары\"}},{{{},1,{}}}}}",
+                              catalog_id,
+                              types::FORMS_ID_CATALOG,
+                              form_id);
+        let form = format!("{{1,{{{{0,0,{}}},\"ФормаЭлемента\"}}}}", form_id);
+
+        let blocks = vec![Block::new("root", &root.into_bytes()),
+                          Block::new(conf_id, &conf.into_bytes()),
+                          Block::new(catalog_id, &catalog.into_bytes()),
+                          Block::new(&format!("{}.0", form_id), &b"form".to_vec()),
+                          Block::new(form_id, &form.into_bytes()),
+                          Block::new("version", &b"{}".to_vec())];
+
+        let path_to_dir = env::temp_dir().join("conf_robber_test_export");
+        let path_to_dir = file_system::path_to_str(path_to_dir.as_path());
+        if file_system::exist(&path_to_dir) {
+            file_system::remove(&path_to_dir).unwrap();
+        }
+
+        export(&blocks, &ContainerLayout::new(), &path_to_dir, 2).unwrap();
+
+        for path in &["Configuration/Metadata.txt",
+                      "Catalogs/Товары/Metadata.txt",
+                      "Catalogs/Товары/Forms/ФормаЭлемента/Metadata.txt",
+                      "Catalogs/Товары/Forms/ФормаЭлемента/Form.txt",
+                      "Other/root",
+                      "Other/version",
+                      "index.xml",
+                      ".manifest"] {
+            let path = file_system::path_to_str(Path::new(&path_to_dir).join(path).as_path());
+            assert!(file_system::exist(&path), "{}", path);
+        }
+
+        let index = file_system::read_file(&file_system::path_to_str(Path::new(&path_to_dir)
+                .join("index.xml")
+                .as_path()))
+            .unwrap();
+        let index = String::from_utf8(index).unwrap();
+        assert!(index.contains(&format!("<file path=\"Catalogs/Товары/Forms/ФормаЭлемента/Form.txt\" \
+                                         block=\"{}.0\"/>",
+                                        form_id)));
+
+        file_system::remove(&path_to_dir).unwrap();
+    }
+}
@@ -0,0 +1,200 @@
+use meta_data::reader;
+use meta_data::types;
+use meta_data::description::Description;
+use meta_data::brace::{elements_ids, find_type_list, parse_data};
+use structure::block::Block;
+use error::{ConfError, metadata_error};
+
+/// Объект метаданных расширения конфигурации
+pub struct ExtensionObject {
+    type_name: &'static str, // имя типа объектов метаданных
+    name: String, // имя объекта
+    id: String, // идентификатор блока с описанием объекта
+    borrowed_id: Option<String>, // идентификатор заимствованного объекта основной конфигурации
+}
+
+impl ExtensionObject {
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+
+    pub fn name<'a>(&'a self) -> &'a String {
+        &self.name
+    }
+
+    pub fn id<'a>(&'a self) -> &'a String {
+        &self.id
+    }
+
+    /// Возвращает идентификатор объекта основной конфигурации, который заимствует объект.
+    /// Для собственных объектов расширения возвращается None.
+    pub fn borrowed_id(&self) -> Option<&String> {
+        self.borrowed_id.as_ref()
+    }
+
+    /// Возвращает полное имя объекта: <Тип>.<Имя>
+    pub fn full_name(&self) -> String {
+        format!("{}.{}", self.type_name, self.name)
+    }
+}
+
+/// Расширение конфигурации: собственные и заимствованные объекты метаданных
+pub struct Extension {
+    name: String, // имя расширения
+    objects: Vec<ExtensionObject>, // объекты расширения в порядке типов метаданных
+}
+
+impl Extension {
+    pub fn name<'a>(&'a self) -> &'a String {
+        &self.name
+    }
+
+    pub fn objects<'a>(&'a self) -> &'a Vec<ExtensionObject> {
+        &self.objects
+    }
+
+    /// Возвращает собственные объекты расширения
+    pub fn own(&self) -> Vec<&ExtensionObject> {
+        self.objects.iter().filter(|x| x.borrowed_id.is_none()).collect()
+    }
+
+    /// Возвращает заимствованные объекты расширения
+    pub fn borrowed(&self) -> Vec<&ExtensionObject> {
+        self.objects.iter().filter(|x| x.borrowed_id.is_some()).collect()
+    }
+
+    /// Возвращает список объектов в текстовом виде: собственные объекты, затем заимствованные
+    /// с идентификаторами объектов основной конфигурации
+    pub fn to_text(&self) -> String {
+
+        let own = self.own();
+        let borrowed = self.borrowed();
+
+        let mut text = format!("extension: {}\n", self.name);
+
+        text.push_str(&format!("own: {}\n", own.len()));
+        for item in own {
+            text.push_str(&format!("    {}\n", item.full_name()));
+        }
+
+        text.push_str(&format!("borrowed: {}\n", borrowed.len()));
+        for item in borrowed {
+            text.push_str(&format!("    {} <- {}\n",
+                                   item.full_name(),
+                                   item.borrowed_id.as_ref().map_or("", |x| &**x)));
+        }
+
+        return text;
+    }
+}
+
+/// Читает описание расширения конфигурации: имя и объекты метаданных всех типов
+pub fn read(blocks: &Vec<Block>) -> Result<Extension, ConfError> {
+
+    let conf_id = reader::main_conf_block_id(blocks)?;
+    let conf_data = reader::main_block_data(&conf_id, blocks)?;
+    let conf_desc = Description::new(&conf_id, &conf_data)?;
+
+    if conf_desc.borrowed_id().is_none() {
+        return Err(metadata_error(&conf_id, "The file is not an extension of the configuration."));
+    }
+
+    let conf_doc = parse_data(&conf_id, &conf_data)?;
+
+    let mut objects: Vec<ExtensionObject> = Vec::new();
+
+    for metadata_type in types::TYPES {
+        let type_list = match find_type_list(&conf_doc.items, metadata_type.id) {
+            Some(v) => v,
+            None => continue,
+        };
+
+        for id in elements_ids(&type_list.items) {
+            let block = match reader::block_by_name(blocks, &id) {
+                Some(v) => v,
+                None => continue,
+            };
+
+            let desc = Description::new(&id, &reader::simply_block_data(block)?)?;
+            objects.push(ExtensionObject {
+                type_name: metadata_type.name,
+                name: desc.name().to_string(),
+                borrowed_id: desc.borrowed_id().map(String::from),
+                id: id,
+            });
+        }
+    }
+
+    Ok(Extension {
+        name: conf_desc.name().to_string(),
+        objects: objects,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::read;
+    use meta_data::types;
+    use meta_data::external::{ContainerKind, container_kind};
+    use structure::block::Block;
+
+    const CONF_ID: &'static str = "be22b29f-2db7-4fcb-8772-eeb5500d2170";
+    const OWN_ID: &'static str = "c0c0c0c0-0000-0000-0000-000000000001";
+    const BORROWED_ID: &'static str = "c0c0c0c0-0000-0000-0000-000000000002";
+    const ADOPTED_ID: &'static str = "a0a0a0a0-0000-0000-0000-000000000003";
+
+    // Настоящих файлов *.cfe в тестовых данных нет, поэтому описание расширения предполагаемое:
+    // это описание конфигурации (root и основной блок как в конфигурации из тестового архива,
+    // см. README), в котором у самой конфигурации и у заимствованных объектов после имени
+    // указан признак заимствования 1 и идентификатор объекта основной конфигурации
+    // (см. brace::borrowed_id). При появлении настоящего файла разметку нужно сверить с ним.
+    #[test]
+    fn test_read() {
+        let conf = format!("{{2,{{3,{{0,0,{}}},\"Расширение\",{{0}},\"\",1,\
+                            9cd510cd-abfc-11d4-9434-004095e12fc7}},{{{},2,{},{}}}}}",
+                           CONF_ID,
+                           types::CATALOG,
+                           OWN_ID,
+                           BORROWED_ID);
+        let own = format!("{{1,{{3,{{0,0,{}}},\"ок_Настройки\",{{0}},\"\",0,\
+                           00000000-0000-0000-0000-000000000000}}}}",
+                          OWN_ID);
+        let borrowed = format!("{{1,{{3,{{0,0,{}}},\"Товары\",{{0}},\"\",1,{}}}}}",
+                               BORROWED_ID,
+                               ADOPTED_ID);
+
+        let blocks = vec![Block::new("root", &format!("{{2,{},}}", CONF_ID).into_bytes()),
+                          Block::new(CONF_ID, &conf.into_bytes()),
+                          Block::new(OWN_ID, &own.into_bytes()),
+                          Block::new(BORROWED_ID, &borrowed.into_bytes())];
+
+        assert_eq!(ContainerKind::Extension, container_kind(&blocks).unwrap());
+
+        let extension = read(&blocks).unwrap();
+        assert_eq!("Расширение", extension.name());
+        assert_eq!(2, extension.objects().len());
+
+        let own = extension.own();
+        assert_eq!(1, own.len());
+        assert_eq!("Справочники.ок_Настройки", own[0].full_name());
+        assert_eq!(None, own[0].borrowed_id());
+
+        let borrowed = extension.borrowed();
+        assert_eq!(1, borrowed.len());
+        assert_eq!(BORROWED_ID, borrowed[0].id());
+        assert_eq!(Some(&String::from(ADOPTED_ID)), borrowed[0].borrowed_id());
+
+        assert_eq!(format!("extension: Расширение\nown: 1\n    Справочники.ок_Настройки\n\
+                            borrowed: 1\n    Справочники.Товары <- {}\n",
+                           ADOPTED_ID),
+                   extension.to_text());
+
+        let conf = format!("{{2,{{3,{{0,0,{}}},\"Конфигурация\",{{0}},\"\",0}},{{{},0}}}}",
+                           CONF_ID,
+                           types::CATALOG);
+        let blocks = vec![Block::new("root", &format!("{{2,{},}}", CONF_ID).into_bytes()),
+                          Block::new(CONF_ID, &conf.into_bytes())];
+        assert_eq!(ContainerKind::Configuration, container_kind(&blocks).unwrap());
+        assert!(read(&blocks).is_err());
+    }
+}
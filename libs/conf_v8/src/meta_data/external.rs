@@ -0,0 +1,888 @@
+use meta_data::reader;
+use meta_data::types;
+use meta_data::writer::{remove_references_deleted_blocks, set_type_desc};
+use meta_data::description::Description;
+use meta_data::brace::{Document, borrowed_id, elements_ids, find_type_list, guids, ids_and_names,
+                       parse_data};
+use meta_data::modules::{is_module_block, normalize_text};
+use structure::block::Block;
+use aho_corasick::{Automaton, AcAutomaton};
+use error::{ConfError, metadata_error};
+
+use std::collections::HashSet;
+
+/// Служебные блоки файла-контейнера, которые не относятся к объекту метаданных
+const SERVICE_BLOCKS: [&'static str; 4] = ["root", "version", "versions", "copyinfo"];
+
+/// Идентификатор класса внешней обработки
+pub const EXTERNAL_DATA_PROCESSOR: &'static str = "c3831ec8-d8d5-4f93-8a22-f9bfae07327f";
+
+/// Идентификатор класса внешнего отчета
+pub const EXTERNAL_REPORT: &'static str = "e41aff26-25cf-4bb6-b6c1-3f478a75f374";
+
+/// Вид файла-контейнера
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ContainerKind {
+    Configuration, // конфигурация (*.cf)
+    Extension, // расширение конфигурации (*.cfe)
+    DataProcessor, // внешняя обработка (*.epf)
+    Report, // внешний отчет (*.erf)
+}
+
+impl ContainerKind {
+    /// Возвращает расширение файла
+    pub fn extension(&self) -> &'static str {
+        match *self {
+            ContainerKind::Configuration => "cf",
+            ContainerKind::Extension => "cfe",
+            ContainerKind::DataProcessor => "epf",
+            ContainerKind::Report => "erf",
+        }
+    }
+
+    /// Возвращает идентификатор типа объектов метаданных, к которому относится внешний объект
+    pub fn type_id(&self) -> Option<&'static str> {
+        match *self {
+            ContainerKind::Configuration | ContainerKind::Extension => None,
+            ContainerKind::DataProcessor => Some(types::DATA_PROCESSORS),
+            ContainerKind::Report => Some(types::REPORTS),
+        }
+    }
+}
+
+/// Внешняя обработка или отчет
+pub struct ExternalObject {
+    kind: ContainerKind, // вид файла
+    id: String, // идентификатор объекта
+    name: String, // имя объекта
+    forms: Vec<(String, String)>, // идентификаторы и имена форм
+    templates: Vec<(String, String)>, // идентификаторы и имена макетов
+    module: Option<Vec<u8>>, // текст модуля объекта
+}
+
+impl ExternalObject {
+    pub fn kind(&self) -> ContainerKind {
+        self.kind
+    }
+
+    pub fn id<'a>(&'a self) -> &'a String {
+        &self.id
+    }
+
+    pub fn name<'a>(&'a self) -> &'a String {
+        &self.name
+    }
+
+    /// Возвращает идентификаторы и имена форм
+    pub fn forms<'a>(&'a self) -> &'a Vec<(String, String)> {
+        &self.forms
+    }
+
+    /// Возвращает идентификаторы и имена макетов
+    pub fn templates<'a>(&'a self) -> &'a Vec<(String, String)> {
+        &self.templates
+    }
+
+    /// Возвращает текст модуля объекта в кодировке UTF-8 без маркера и с переводами строк LF
+    pub fn module<'a>(&'a self) -> Option<&'a Vec<u8>> {
+        self.module.as_ref()
+    }
+}
+
+/// Определяет вид файла по основному блоку, на который указывает блок root
+pub fn container_kind(blocks: &Vec<Block>) -> Result<ContainerKind, ConfError> {
+    let main_id = reader::main_conf_block_id(blocks)?;
+    let main_data = reader::main_block_data(&main_id, blocks)?;
+
+    Ok(kind_by_data(&main_data))
+}
+
+/// Определяет вид файла по тексту основного блока. Описание конфигурации содержит списки
+/// объектов метаданных, описание внешнего объекта - идентификатор класса обработки или отчета.
+/// Расширение отличается от конфигурации тем, что сама конфигурация в нем заимствованная.
+pub fn kind_by_data(data: &Vec<u8>) -> ContainerKind {
+
+    let doc = match Document::parse(data) {
+        Ok(v) => v,
+        Err(_) => return ContainerKind::Configuration,
+    };
+
+    if find_type_list(&doc.items, types::CATALOG).is_some() {
+        let conf_id = ids_and_names(&doc.items).into_iter().next().map(|x| x.0);
+        return match conf_id.and_then(|x| borrowed_id(&doc.items, &x)) {
+            Some(_) => ContainerKind::Extension,
+            None => ContainerKind::Configuration,
+        };
+    }
+
+    for id in guids(&doc.items) {
+        match &*id {
+            EXTERNAL_DATA_PROCESSOR => return ContainerKind::DataProcessor,
+            EXTERNAL_REPORT => return ContainerKind::Report,
+            _ => (),
+        }
+    }
+
+    ContainerKind::Configuration
+}
+
+/// Читает описание внешней обработки или отчета: имя, формы, макеты и модуль объекта
+pub fn read(blocks: &Vec<Block>) -> Result<ExternalObject, ConfError> {
+
+    let main_id = reader::main_conf_block_id(blocks)?;
+    let main_data = reader::main_block_data(&main_id, blocks)?;
+
+    let kind = kind_by_data(&main_data);
+    if kind.type_id().is_none() {
+        return Err(metadata_error(&main_id,
+                                  "The file is not an external data processor or report."));
+    }
+
+    let (doc, id, name) = object_description(blocks, &main_id, &main_data)?;
+
+    let forms = nested_objects(blocks, &doc, &[types::FORMS_ID_DOC, types::FORMS_ID_CATALOG])?;
+    let templates = nested_objects(blocks, &doc, &[types::LAYOUTS_ID])?;
+
+    // Модуль объекта хранится в блоке <идентификатор объекта>.0
+    let mut module: Option<Vec<u8>> = None;
+    for module_id in &[format!("{}.0", id), format!("{}.0", main_id)] {
+        let block = match reader::block_by_name(blocks, module_id) {
+            Some(v) => v,
+            None => continue,
+        };
+
+        let nested_blocks = match block.get_data() {
+            Ok(v) => v,
+            Err(_) => continue,
+        };
+
+        if let Some(v) = nested_blocks.iter().find(|x| is_module_block(x.attrs.id())) {
+            module = Some(normalize_text(&v.data));
+            break;
+        }
+    }
+
+    Ok(ExternalObject {
+        kind: kind,
+        id: id,
+        name: name,
+        forms: forms,
+        templates: templates,
+        module: module,
+    })
+}
+
+/// Извлекает обработку или отчет конфигурации с указанным именем в блоки внешней обработки
+/// (отчета): описание объекта, модули, формы и макеты. Ссылки на другие объекты конфигурации,
+/// которых нет вне конфигурации, заменяются на тип "Любая ссылка".
+pub fn extract(blocks: &Vec<Block>, name: &str) -> Result<(ContainerKind, Vec<Block>), ConfError> {
+
+    info!("Extracting of the object {}", name);
+
+    let conf_id = reader::main_conf_block_id(blocks)?;
+    let conf_data = reader::main_block_data(&conf_id, blocks)?;
+    let conf_doc = parse_data(&conf_id, &conf_data)?;
+
+    if find_type_list(&conf_doc.items, types::CATALOG).is_none() {
+        return Err(metadata_error(&conf_id, "The file is not a configuration."));
+    }
+
+    // Поиск объекта среди обработок и отчетов, остальные объекты конфигурации
+    // собираются для замены ссылок на них
+    let mut object: Option<(ContainerKind, Description)> = None;
+    let mut reference_ids: Vec<String> = Vec::new();
+
+    for metadata_type in types::TYPES {
+        let type_list = match find_type_list(&conf_doc.items, metadata_type.id) {
+            Some(v) => v,
+            None => continue,
+        };
+
+        let kind = match metadata_type.id {
+            types::DATA_PROCESSORS => Some(ContainerKind::DataProcessor),
+            types::REPORTS => Some(ContainerKind::Report),
+            _ => None,
+        };
+
+        for id in elements_ids(&type_list.items) {
+            let block = match reader::block_by_name(blocks, &id) {
+                Some(v) => v,
+                None => continue,
+            };
+
+            let data = reader::simply_block_data(block)?;
+            let desc = Description::new(&id, &data)?;
+
+            match kind {
+                Some(kind) if object.is_none() && desc.name() == name => {
+                    object = Some((kind, desc));
+                }
+                _ => reference_ids.extend_from_slice(desc.reference_ids()),
+            }
+        }
+    }
+
+    let (kind, desc) = match object {
+        Some(v) => v,
+        None => {
+            return Err(metadata_error(&conf_id,
+                                      format!("Not found data processor or report {}", name)))
+        }
+    };
+
+    // Блоки объекта: описание, модули (<идентификатор>.<номер>), формы и макеты
+    let mut objects_ids: Vec<String> = vec![desc.block_id().to_string()];
+    for type_id in &[types::FORMS_ID_DOC, types::FORMS_ID_CATALOG, types::LAYOUTS_ID] {
+        if let Some(ids) = desc.internal_types_ids(type_id) {
+            objects_ids.extend_from_slice(ids);
+        }
+    }
+
+    let object_id = desc.block_id().to_string();
+    let main_id = external_main_id(&object_id);
+
+    let mut retval: Vec<Block> = Vec::new();
+    retval.push(Block::new("root", &format!("{{2,{},}}", main_id).into_bytes()));
+    if let Some(block) = reader::block_by_name(blocks, &String::from("version")) {
+        retval.push(block.clone());
+    }
+
+    let class_id = match kind {
+        ContainerKind::Report => EXTERNAL_REPORT,
+        _ => EXTERNAL_DATA_PROCESSOR,
+    };
+    let main = format!("{{1,{{{},{},{{0,{}}}}}}}", main_id, object_id, class_id);
+    retval.push(Block::new(&main_id, &main.into_bytes()));
+
+    for block in blocks {
+        let id = block.id().split('.').next().unwrap_or("");
+        if objects_ids.iter().any(|x| x == id) {
+            retval.push(block.clone());
+        }
+    }
+
+    remove_references_deleted_blocks(&retval, reference_ids)?;
+
+    info!("-Extracting of the object {}", name);
+
+    Ok((kind, retval))
+}
+
+// Идентификатор основного блока внешнего объекта получается из идентификатора объекта
+// заменой шестнадцатеричных цифр на дополняющие до f, поэтому повторное извлечение
+// дает одинаковый файл
+fn external_main_id(object_id: &str) -> String {
+    object_id.chars()
+        .map(|c| match c.to_digit(16) {
+            Some(v) => ::std::char::from_digit(15 - v, 16).unwrap_or(c),
+            None => c,
+        })
+        .collect()
+}
+
+/// Встраивает внешнюю обработку (отчет) в конфигурацию как новый объект метаданных:
+/// блоки объекта добавляются в конфигурацию, а идентификатор объекта - в список объектов
+/// своего типа в описании конфигурации. Если описание объекта находится в основном блоке файла,
+/// то оно переносится в блок объекта. Если идентификаторы блоков, ссылок или вложенных объектов
+/// встраиваемого объекта уже используются в конфигурации, то идентификаторы объекта и его
+/// вложенных объектов заменяются на новые.
+/// Возвращает идентификатор добавленного объекта.
+pub fn embed(blocks: &mut Vec<Block>, external_blocks: &Vec<Block>) -> Result<String, ConfError> {
+
+    let object = read(external_blocks)?;
+    let type_id = match object.kind().type_id() {
+        Some(v) => v,
+        None => return Err(metadata_error("root", "The file is not an external data processor or report.")),
+    };
+
+    info!("Embedding of the object {}", object.name());
+
+    let conf_id = reader::main_conf_block_id(blocks)?;
+    let conf_data = reader::main_block_data(&conf_id, blocks)?;
+    let mut conf_doc = parse_data(&conf_id, &conf_data)?;
+
+    if find_type_list(&conf_doc.items, types::CATALOG).is_none() {
+        return Err(metadata_error(&conf_id, "The file is not a configuration."));
+    }
+
+    let mut objects_ids = match find_type_list(&conf_doc.items, type_id) {
+        Some(v) => elements_ids(&v.items),
+        None => return Err(metadata_error(&conf_id, format!("Not found list of objects {}", type_id))),
+    };
+
+    // Идентификаторы, которые используются в конфигурации: идентификаторы блоков, ссылок
+    // и вложенных объектов всех объектов метаданных. Имя встраиваемого объекта должно быть
+    // уникальным среди объектов того же типа.
+    let mut conf_ids: HashSet<String> = blocks.iter().map(|x| id_without_suffix(x.id())).collect();
+    for metadata_type in types::TYPES {
+        let type_list = match find_type_list(&conf_doc.items, metadata_type.id) {
+            Some(v) => v,
+            None => continue,
+        };
+
+        for id in elements_ids(&type_list.items) {
+            let block = match reader::block_by_name(blocks, &id) {
+                Some(v) => v,
+                None => continue,
+            };
+
+            let desc = Description::new(&id, &reader::simply_block_data(block)?)?;
+            if metadata_type.id == type_id && desc.name() == object.name() {
+                return Err(metadata_error(&id, format!("The object {} already exists", object.name())));
+            }
+
+            conf_ids.extend(desc.reference_ids().iter().cloned());
+            conf_ids.extend(internal_ids(&desc));
+        }
+    }
+
+    // Блоки объекта: все блоки файла, кроме служебных и основного. Если описание объекта
+    // находится в основном блоке, то оно переносится в блок объекта, а модули основного блока
+    // (<идентификатор основного блока>.<номер>) становятся модулями объекта.
+    let main_id = reader::main_conf_block_id(external_blocks)?;
+    let main_data = reader::main_block_data(&main_id, external_blocks)?;
+    let (object_doc, _, _) = object_description(external_blocks, &main_id, &main_data)?;
+    let object_data = object_doc.to_bytes();
+
+    let no_ids = AcAutomaton::new(Vec::<String>::new());
+    let main_prefix = format!("{}.", main_id);
+
+    let mut object_blocks: Vec<Block> = Vec::new();
+    for block in external_blocks {
+        if block.id().eq(&main_id) || SERVICE_BLOCKS.contains(&&**block.id()) {
+            continue;
+        }
+
+        match block.id().starts_with(&main_prefix) {
+            true => {
+                let block_id = format!("{}.{}", object.id(), &block.id()[main_prefix.len()..]);
+                object_blocks.push(copy_block(block, &block_id, &no_ids, &Vec::new())?);
+            }
+            false => object_blocks.push(block.clone()),
+        }
+    }
+
+    if reader::block_by_name(&object_blocks, object.id()).is_none() {
+        object_blocks.push(Block::new(object.id(), &object_data));
+    }
+    let desc = Description::new(object.id(), &object_data)?;
+
+    // Идентификаторы объекта: блоки без номера модуля (<идентификатор>.<номер>), ссылки
+    // и вложенные объекты. Идентификаторы классов внешних объектов не заменяются.
+    let mut object_ids: Vec<String> = object_blocks.iter().map(|x| id_without_suffix(x.id())).collect();
+    object_ids.extend_from_slice(desc.reference_ids());
+    object_ids.extend(internal_ids(&desc));
+    object_ids.retain(|x| x != EXTERNAL_DATA_PROCESSOR && x != EXTERNAL_REPORT);
+    object_ids.sort();
+    object_ids.dedup();
+
+    let is_conflict = object_ids.iter().any(|x| conf_ids.contains(x));
+
+    let mut old_ids: Vec<String> = Vec::new();
+    let mut new_ids: Vec<String> = Vec::new();
+    if is_conflict {
+        old_ids = object_ids;
+        for id in &old_ids {
+            new_ids.push(new_guid(&conf_ids, id, &conf_id));
+        }
+        debug!("Ids of the object {} are replaced: {}", object.name(), old_ids.len());
+    }
+
+    let aut = AcAutomaton::new(old_ids.clone());
+    for block in object_blocks {
+        if !is_conflict {
+            blocks.push(block);
+            continue;
+        }
+
+        let block_id = replace_ids(block.id().as_bytes(), &aut, &new_ids);
+        let block_id = String::from_utf8_lossy(&block_id).into_owned();
+        blocks.push(copy_block(&block, &block_id, &aut, &new_ids)?);
+    }
+
+    let object_id = match old_ids.iter().position(|x| x == object.id()) {
+        Some(i) => new_ids[i].clone(),
+        None => object.id().clone(),
+    };
+
+    // Добавляем объект в список объектов типа в описании конфигурации
+    objects_ids.push(object_id.clone());
+    set_type_desc(&mut conf_doc.items, type_id, &objects_ids);
+
+    let conf_block = match reader::block_by_name(blocks, &conf_id) {
+        Some(v) => v,
+        None => return Err(metadata_error(&conf_id, "Not found block of configuration.")),
+    };
+    conf_block.set_data(&conf_id, &conf_doc.to_bytes())?;
+
+    info!("-Embedding of the object {}", object.name());
+
+    Ok(object_id)
+}
+
+// Возвращает идентификатор блока без номера модуля (<идентификатор>.<номер>)
+fn id_without_suffix(block_id: &str) -> String {
+    block_id.split('.').next().unwrap_or("").to_string()
+}
+
+// Возвращает идентификаторы вложенных объектов: форм, реквизитов, макетов, команд и табличных частей
+fn internal_ids(desc: &Description) -> Vec<String> {
+    let mut retval: Vec<String> = Vec::new();
+    for internal_type_id in &[types::FORMS_ID_DOC,
+                              types::FORMS_ID_CATALOG,
+                              types::PROPS_ID,
+                              types::LAYOUTS_ID,
+                              types::COMMANDS_ID,
+                              types::TABULAR_SELECTIONS_ID] {
+        if let Some(ids) = desc.internal_types_ids(internal_type_id) {
+            retval.extend_from_slice(ids);
+        }
+    }
+
+    retval
+}
+
+// Возвращает копию блока с указанным идентификатором, в данных которой идентификаторы
+// заменены на новые
+fn copy_block(block: &Block,
+              block_id: &str,
+              aut: &AcAutomaton<String>,
+              new_ids: &Vec<String>)
+              -> Result<Block, ConfError> {
+
+    let is_group = block.layout()?.container.is_some();
+    let mut nested_data: Vec<(String, Vec<u8>)> = Vec::new();
+    for nested_block in block.get_data()? {
+        nested_data.push((nested_block.attrs.id().clone(),
+                          replace_ids(&nested_block.data, aut, new_ids)));
+    }
+
+    Ok(Block::from_parts(block_id, is_group, nested_data, None))
+}
+
+// Заменяет идентификаторы в данных. Длина нового идентификатора равна длине заменяемого.
+fn replace_ids(data: &[u8], aut: &AcAutomaton<String>, new_ids: &Vec<String>) -> Vec<u8> {
+    let mut retval = data.to_vec();
+    for m in aut.find(data) {
+        retval[m.start..m.end].copy_from_slice(new_ids[m.pati].as_bytes());
+    }
+
+    retval
+}
+
+// Возвращает новый идентификатор для встраиваемого объекта. Идентификатор вычисляется
+// по заменяемому идентификатору и идентификатору конфигурации, поэтому повторное встраивание
+// дает одинаковый результат. Идентификаторы, которые используются в конфигурации, пропускаются.
+fn new_guid(conf_ids: &HashSet<String>, id: &str, conf_id: &str) -> String {
+    let mut salt: u64 = 0;
+    loop {
+        let mut parts: Vec<u64> = Vec::new();
+        for part in 0..2u8 {
+            let mut data: Vec<u8> = vec![part];
+            data.extend_from_slice(id.as_bytes());
+            data.push(0);
+            data.extend_from_slice(conf_id.as_bytes());
+            data.push(0);
+            for i in 0..8 {
+                data.push((salt >> (8 * i)) as u8);
+            }
+            parts.push(fnv1a(&data));
+        }
+
+        let hex = format!("{:016x}{:016x}", parts[0], parts[1]);
+        let guid = format!("{}-{}-4{}-{}-{}", &hex[..8], &hex[8..12], &hex[13..16], &hex[16..20], &hex[20..]);
+
+        if !conf_ids.contains(&guid) {
+            return guid;
+        }
+        salt += 1;
+    }
+}
+
+// Хеш FNV-1a (64 бита). В отличие от хеша стандартной библиотеки, алгоритм которого может
+// меняться между версиями Rust, значение не зависит от версии компилятора.
+fn fnv1a(data: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for &byte in data {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+
+    hash
+}
+
+/// Возвращает разобранное описание внешнего объекта, его идентификатор и имя.
+/// Описание находится в основном блоке или в блоке, на который указывает основной блок.
+fn object_description(blocks: &Vec<Block>,
+                      main_id: &String,
+                      main_data: &Vec<u8>)
+                      -> Result<(Document, String, String), ConfError> {
+
+    let main_doc = parse_data(main_id, main_data)?;
+    if let Some((id, name)) = ids_and_names(&main_doc.items).into_iter().next() {
+        return Ok((main_doc, id, name));
+    }
+
+    for id in guids(&main_doc.items) {
+        let block = match reader::block_by_name(blocks, &id) {
+            Some(v) if v.id().ne(main_id) => v,
+            _ => continue,
+        };
+
+        let data = reader::simply_block_data(block)?;
+        let doc = parse_data(&id, &data)?;
+        let object = ids_and_names(&doc.items).into_iter().find(|x| x.0.eq(&id));
+        if let Some((id, name)) = object {
+            return Ok((doc, id, name));
+        }
+    }
+
+    Err(metadata_error(main_id, "Not found name of the external object."))
+}
+
+/// Возвращает идентификаторы и имена вложенных объектов, описание которых хранится в отдельных блоках
+fn nested_objects(blocks: &Vec<Block>,
+                  doc: &Document,
+                  types_ids: &[&'static str])
+                  -> Result<Vec<(String, String)>, ConfError> {
+
+    let mut retval: Vec<(String, String)> = Vec::new();
+
+    for type_id in types_ids {
+        let type_list = match find_type_list(&doc.items, type_id) {
+            Some(v) => v,
+            None => continue,
+        };
+
+        for id in elements_ids(&type_list.items) {
+            let name = match reader::block_by_name(blocks, &id) {
+                Some(block) => {
+                    let data = reader::simply_block_data(block)?;
+                    Description::new(&id, &data)?.name().to_string()
+                }
+                None => id.clone(),
+            };
+            retval.push((id, name));
+        }
+    }
+
+    Ok(retval)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ContainerKind, EXTERNAL_DATA_PROCESSOR, EXTERNAL_REPORT, container_kind, embed,
+                extract, fnv1a, new_guid, read};
+    use meta_data::brace::{Document, elements_ids, find_type_list};
+    use meta_data::reader;
+    use meta_data::types;
+    use structure::block::Block;
+
+    use std::collections::HashSet;
+
+    const MAIN_ID: &'static str = "5d1c4e6b-0000-0000-0000-000000000001";
+    const OBJECT_ID: &'static str = "0b0b0b0b-0000-0000-0000-000000000002";
+    const FORM_ID: &'static str = "f0f0f0f0-0000-0000-0000-000000000003";
+    const TEMPLATE_ID: &'static str = "a0a0a0a0-0000-0000-0000-000000000004";
+
+    // Блоки внешнего объекта: описание объекта находится в отдельном блоке.
+    //
+    // Настоящих файлов *.epf/*.erf в тестовых данных нет, поэтому разметка блоков предполагаемая:
+    //  - root: {2,<идентификатор основного блока>,} - как блок root конфигурации из тестового
+    //    архива (см. README), по нему же проверяется main_conf_block_id;
+    //  - основной блок: {1,{<идентификатор основного блока>,<идентификатор объекта>,
+    //    {0,<идентификатор класса>}}}, где класс - EXTERNAL_DATA_PROCESSOR или EXTERNAL_REPORT;
+    //  - описание объекта и его форм в формате описаний объектов конфигурации: имя после
+    //    {0,0,<идентификатор>} и списки {<идентификатор типа>,<количество>,<идентификаторы>}.
+    // При появлении настоящего файла в тестовых данных разметку нужно сверить с ним.
+    fn external_blocks(class_id: &str) -> Vec<Block> {
+        let root = format!("{{2,{},}}", MAIN_ID);
+        let main = format!("{{1,{{{},{},{{0,{}}}}}}}", MAIN_ID, OBJECT_ID, class_id);
+        let object = format!("{{1,{{{{0,0,{}}},\"ЗагрузкаДанных\",{{1,\"ru\",\"Загрузка\"}}}},\
+                              {{{},1,{}}},{{{},1,{}}}}}",
+                             OBJECT_ID,
+                             types::FORMS_ID_DOC,
+                             FORM_ID,
+                             types::LAYOUTS_ID,
+                             TEMPLATE_ID);
+        let form = format!("{{1,{{{{0,0,{}}},\"Форма\"}}}}", FORM_ID);
+        let template = format!("{{1,{{{{0,0,{}}},\"Макет\"}}}}", TEMPLATE_ID);
+
+        let module = Block::from_parts(&format!("{}.0", OBJECT_ID),
+                                       true,
+                                       vec![(String::from("info"), b"{3,1,0}".to_vec()),
+                                            (String::from("text"),
+                                             b"\xEF\xBB\xBF// module\r\n".to_vec())],
+                                       None);
+
+        vec![Block::new("root", &root.into_bytes()),
+             Block::new(MAIN_ID, &main.into_bytes()),
+             Block::new(OBJECT_ID, &object.into_bytes()),
+             module,
+             Block::new(FORM_ID, &form.into_bytes()),
+             Block::new(TEMPLATE_ID, &template.into_bytes())]
+    }
+
+    // Блоки внешнего объекта: описание объекта находится в основном блоке,
+    // модуль объекта - в блоке <идентификатор основного блока>.0
+    fn external_main_blocks() -> Vec<Block> {
+        let root = format!("{{2,{},}}", MAIN_ID);
+        let main = format!("{{1,{{{},{{0,{}}}}},{{{{0,0,{}}},\"ЗагрузкаДанных\",{{0}}}},\
+                            {{{},1,{}}}}}",
+                           MAIN_ID,
+                           EXTERNAL_DATA_PROCESSOR,
+                           OBJECT_ID,
+                           types::FORMS_ID_DOC,
+                           FORM_ID);
+        let form = format!("{{1,{{{{0,0,{}}},\"Форма\"}}}}", FORM_ID);
+
+        let module = Block::from_parts(&format!("{}.0", MAIN_ID),
+                                       true,
+                                       vec![(String::from("text"), b"// module".to_vec())],
+                                       None);
+
+        vec![Block::new("root", &root.into_bytes()),
+             Block::new(MAIN_ID, &main.into_bytes()),
+             module,
+             Block::new(FORM_ID, &form.into_bytes())]
+    }
+
+    #[test]
+    fn test_new_guid() {
+        assert_eq!(0xcbf29ce484222325, fnv1a(b""));
+        assert_eq!(0xaf63dc4c8601ec8c, fnv1a(b"a"));
+
+        let conf_id = "be22b29f-2db7-4fcb-8772-eeb5500d2170";
+        let guid = new_guid(&HashSet::new(), OBJECT_ID, conf_id);
+        assert_eq!("55ca31cb-4fbf-4207-a1d7-be2284feb448", guid);
+
+        // Идентификатор, который уже используется в конфигурации, пропускается
+        let conf_ids: HashSet<String> = vec![guid.clone()].into_iter().collect();
+        let other = new_guid(&conf_ids, OBJECT_ID, conf_id);
+        assert_eq!(36, other.len());
+        assert!(other != guid);
+    }
+
+    #[test]
+    fn test_container_kind() {
+        assert_eq!(ContainerKind::DataProcessor,
+                   container_kind(&external_blocks(EXTERNAL_DATA_PROCESSOR)).unwrap());
+        assert_eq!(ContainerKind::Report,
+                   container_kind(&external_blocks(EXTERNAL_REPORT)).unwrap());
+
+        let conf_id = "be22b29f-2db7-4fcb-8772-eeb5500d2170";
+        let conf = format!("{{2,{{{{0,0,{}}},\"Конфигурация\"}},{{{},0}}}}", conf_id, types::CATALOG);
+        let blocks = vec![Block::new("root", &format!("{{2,{},}}", conf_id).into_bytes()),
+                          Block::new(conf_id, &conf.into_bytes())];
+        assert_eq!(ContainerKind::Configuration, container_kind(&blocks).unwrap());
+        assert!(read(&blocks).is_err());
+
+        assert_eq!("epf", ContainerKind::DataProcessor.extension());
+        assert_eq!(Some(types::REPORTS), ContainerKind::Report.type_id());
+    }
+
+    #[test]
+    fn test_read() {
+        let object = read(&external_blocks(EXTERNAL_DATA_PROCESSOR)).unwrap();
+
+        assert_eq!(ContainerKind::DataProcessor, object.kind());
+        assert_eq!(OBJECT_ID, object.id());
+        assert_eq!("ЗагрузкаДанных", object.name());
+        assert_eq!(&vec![(String::from(FORM_ID), String::from("Форма"))], object.forms());
+        assert_eq!(&vec![(String::from(TEMPLATE_ID), String::from("Макет"))],
+                   object.templates());
+        assert_eq!(Some(&b"// module\n".to_vec()), object.module());
+    }
+
+    #[test]
+    fn test_extract() {
+        let conf_id = "be22b29f-2db7-4fcb-8772-eeb5500d2170";
+        let catalog_id = "c1c1c1c1-0000-0000-0000-000000000005";
+        let catalog_ref_id = "c2c2c2c2-0000-0000-0000-000000000006";
+
+        let conf = format!("{{2,{{{{0,0,{}}},\"Конфигурация\"}},{{{},1,{}}},{{{},1,{}}}}}",
+                           conf_id,
+                           types::CATALOG,
+                           catalog_id,
+                           types::DATA_PROCESSORS,
+                           OBJECT_ID);
+        let catalog = format!("{{1,{{{},{{{{0,0,{}}},\"Товары\"}}}}}}", catalog_ref_id, catalog_id);
+        let object = format!("{{1,{{{{0,0,{}}},\"ЗагрузкаДанных\"}},{{\"#\",{}}},{{{},1,{}}}}}",
+                             OBJECT_ID,
+                             catalog_ref_id,
+                             types::FORMS_ID_DOC,
+                             FORM_ID);
+        let form = format!("{{1,{{{{0,0,{}}},\"Форма\"}}}}", FORM_ID);
+        let module = Block::from_parts(&format!("{}.0", OBJECT_ID),
+                                       true,
+                                       vec![(String::from("text"), b"// module".to_vec())],
+                                       None);
+
+        let blocks = vec![Block::new("root", &format!("{{2,{},}}", conf_id).into_bytes()),
+                          Block::new("version", &b"{{216,0}}".to_vec()),
+                          Block::new(conf_id, &conf.into_bytes()),
+                          Block::new(catalog_id, &catalog.into_bytes()),
+                          Block::new(OBJECT_ID, &object.into_bytes()),
+                          module,
+                          Block::new(FORM_ID, &form.into_bytes())];
+
+        assert!(extract(&blocks, "Загрузка").is_err());
+        assert!(extract(&blocks, "Товары").is_err());
+
+        let (kind, external) = extract(&blocks, "ЗагрузкаДанных").unwrap();
+        assert_eq!(ContainerKind::DataProcessor, kind);
+        assert_eq!(ContainerKind::DataProcessor, container_kind(&external).unwrap());
+        assert!(reader::block_by_name(&external, &String::from(catalog_id)).is_none());
+        assert!(reader::block_by_name(&external, &String::from("version")).is_some());
+
+        let object = read(&external).unwrap();
+        assert_eq!("ЗагрузкаДанных", object.name());
+        assert_eq!(&vec![(String::from(FORM_ID), String::from("Форма"))], object.forms());
+        assert_eq!(Some(&b"// module".to_vec()), object.module());
+
+        // Ссылка на справочник заменена на "Любая ссылка"
+        let block = reader::block_by_name(&external, &String::from(OBJECT_ID)).unwrap();
+        let data = String::from_utf8(reader::simply_block_data(block).unwrap()).unwrap();
+        assert!(data.contains(types::ANY_REF));
+        assert!(!data.contains(catalog_ref_id));
+
+        // Исходные блоки не изменяются
+        let block = reader::block_by_name(&blocks, &String::from(OBJECT_ID)).unwrap();
+        let data = String::from_utf8(reader::simply_block_data(block).unwrap()).unwrap();
+        assert!(data.contains(catalog_ref_id));
+    }
+
+    #[test]
+    fn test_embed() {
+        let conf_id = "be22b29f-2db7-4fcb-8772-eeb5500d2170";
+        let conf = format!("{{2,{{{{0,0,{}}},\"Конфигурация\"}},{{{},0}},{{{},0}}}}",
+                           conf_id,
+                           types::CATALOG,
+                           types::DATA_PROCESSORS);
+        let mut blocks = vec![Block::new("root", &format!("{{2,{},}}", conf_id).into_bytes()),
+                              Block::new(conf_id, &conf.into_bytes())];
+
+        let external = external_blocks(EXTERNAL_DATA_PROCESSOR);
+        // Конфигурация не встраивается
+        let conf_blocks = blocks.clone();
+        assert!(embed(&mut blocks, &conf_blocks).is_err());
+
+        // Блоки объекта добавляются с исходными идентификаторами
+        let id = embed(&mut blocks, &external).unwrap();
+        assert_eq!(OBJECT_ID, id);
+        assert_eq!(vec![OBJECT_ID.to_string()], processors_ids(&blocks, conf_id));
+        assert!(reader::block_by_name(&blocks, &String::from(MAIN_ID)).is_none());
+        assert!(reader::block_by_name(&blocks, &format!("{}.0", OBJECT_ID)).is_some());
+        assert!(reader::block_by_name(&blocks, &String::from(FORM_ID)).is_some());
+
+        // Объект с таким же именем не добавляется
+        assert!(embed(&mut blocks, &external).is_err());
+
+        // При совпадении идентификаторов блоков идентификаторы объекта заменяются
+        let renamed: Vec<Block> = external.iter()
+            .map(|x| {
+                let data = reader::simply_block_data(x)
+                    .map(|v| String::from_utf8(v).unwrap().replace("ЗагрузкаДанных", "Выгрузка"));
+                match data {
+                    Ok(v) => Block::new(x.id(), &v.into_bytes()),
+                    Err(_) => x.clone(),
+                }
+            })
+            .collect();
+
+        let new_id = embed(&mut blocks, &renamed).unwrap();
+        assert!(new_id != OBJECT_ID);
+        assert_eq!(vec![OBJECT_ID.to_string(), new_id.clone()], processors_ids(&blocks, conf_id));
+
+        let block = reader::block_by_name(&blocks, &new_id).unwrap();
+        let data = String::from_utf8(reader::simply_block_data(block).unwrap()).unwrap();
+        assert!(data.contains("Выгрузка"));
+        assert!(!data.contains(FORM_ID));
+        assert!(!data.contains(OBJECT_ID));
+        assert!(reader::block_by_name(&blocks, &format!("{}.0", new_id)).is_some());
+        assert_eq!(1, blocks.iter().filter(|x| x.id().eq(FORM_ID)).count());
+    }
+
+    #[test]
+    fn test_embed_main_block() {
+        let conf_id = "be22b29f-2db7-4fcb-8772-eeb5500d2170";
+        let conf = format!("{{2,{{{{0,0,{}}},\"Конфигурация\"}},{{{},0}},{{{},0}}}}",
+                           conf_id,
+                           types::CATALOG,
+                           types::DATA_PROCESSORS);
+        let mut blocks = vec![Block::new("root", &format!("{{2,{},}}", conf_id).into_bytes()),
+                              Block::new(conf_id, &conf.into_bytes())];
+
+        let external = external_main_blocks();
+        let object = read(&external).unwrap();
+        assert_eq!(OBJECT_ID, object.id());
+        assert_eq!(Some(&b"// module".to_vec()), object.module());
+
+        // Описание объекта переносится из основного блока в блок объекта
+        let id = embed(&mut blocks, &external).unwrap();
+        assert_eq!(OBJECT_ID, id);
+        assert_eq!(vec![OBJECT_ID.to_string()], processors_ids(&blocks, conf_id));
+        assert!(reader::block_by_name(&blocks, &String::from(MAIN_ID)).is_none());
+        assert!(reader::block_by_name(&blocks, &format!("{}.0", MAIN_ID)).is_none());
+        assert!(reader::block_by_name(&blocks, &String::from(FORM_ID)).is_some());
+
+        let block = reader::block_by_name(&blocks, &String::from(OBJECT_ID)).unwrap();
+        let data = String::from_utf8(reader::simply_block_data(block).unwrap()).unwrap();
+        assert!(data.contains("ЗагрузкаДанных"));
+        assert!(data.contains(FORM_ID));
+
+        let block = reader::block_by_name(&blocks, &format!("{}.0", OBJECT_ID)).unwrap();
+        assert_eq!(b"// module".to_vec(), block.get_data().unwrap()[0].data);
+    }
+
+    #[test]
+    fn test_embed_reference_conflict() {
+        let conf_id = "be22b29f-2db7-4fcb-8772-eeb5500d2170";
+        let catalog_id = "c1c1c1c1-0000-0000-0000-000000000005";
+        let reference_id = "c2c2c2c2-0000-0000-0000-000000000006";
+        let command_id = "c3c3c3c3-0000-0000-0000-000000000007";
+
+        let conf = format!("{{2,{{{{0,0,{}}},\"Конфигурация\"}},{{{},1,{}}},{{{},0}}}}",
+                           conf_id,
+                           types::CATALOG,
+                           catalog_id,
+                           types::DATA_PROCESSORS);
+        let catalog = format!("{{1,{{{},{{{{0,0,{}}},\"Товары\"}}}},{{{},1,{}}}}}",
+                              reference_id,
+                              catalog_id,
+                              types::COMMANDS_ID,
+                              command_id);
+        let mut blocks = vec![Block::new("root", &format!("{{2,{},}}", conf_id).into_bytes()),
+                              Block::new(conf_id, &conf.into_bytes()),
+                              Block::new(catalog_id, &catalog.into_bytes())];
+
+        // Блоки объекта не совпадают с блоками конфигурации, но совпадают идентификатор ссылки
+        // и идентификатор команды
+        let object = format!("{{1,{{{},{{{{0,0,{}}},\"ЗагрузкаДанных\"}}}},{{{},1,{}}}}}",
+                             reference_id,
+                             OBJECT_ID,
+                             types::COMMANDS_ID,
+                             command_id);
+        let main = format!("{{1,{{{},{},{{0,{}}}}}}}", MAIN_ID, OBJECT_ID, EXTERNAL_DATA_PROCESSOR);
+        let external = vec![Block::new("root", &format!("{{2,{},}}", MAIN_ID).into_bytes()),
+                            Block::new(MAIN_ID, &main.into_bytes()),
+                            Block::new(OBJECT_ID, &object.into_bytes())];
+
+        let new_id = embed(&mut blocks, &external).unwrap();
+        assert!(new_id != OBJECT_ID);
+        assert_eq!(vec![new_id.clone()], processors_ids(&blocks, conf_id));
+
+        let block = reader::block_by_name(&blocks, &new_id).unwrap();
+        let data = String::from_utf8(reader::simply_block_data(block).unwrap()).unwrap();
+        assert!(data.contains("ЗагрузкаДанных"));
+        assert!(!data.contains(reference_id));
+        assert!(!data.contains(command_id));
+        assert!(data.contains(types::COMMANDS_ID));
+    }
+
+    fn processors_ids(blocks: &Vec<Block>, conf_id: &str) -> Vec<String> {
+        let data = reader::main_block_data(&String::from(conf_id), blocks).unwrap();
+        let doc = Document::parse(&data).unwrap();
+        elements_ids(&find_type_list(&doc.items, types::DATA_PROCESSORS).unwrap().items)
+    }
+}
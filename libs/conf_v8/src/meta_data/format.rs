@@ -0,0 +1,172 @@
+
+use file_system;
+
+use meta_data::brace::Document;
+use meta_data::modules::is_module_block;
+use structure::manifest::MANIFEST_FILE_NAME;
+use error::{ConfError, io_error};
+
+use std::path::Path;
+
+/// Способ форматирования текста блоков
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Format {
+    Pretty, // в читаемом виде с отступами
+    Minify, // без пробелов и переводов строк, для сборки конфигурационного файла
+}
+
+/// Отформатировать файлы распакованных блоков и записать их в каталог path_to_target.
+/// Форматируются только файлы с описаниями в формате списков 1С, тексты модулей,
+/// описание распакованной конфигурации и прочие файлы копируются без изменений.
+/// Возвращает количество отформатированных файлов.
+pub fn format_dir(path_to_dir: &String,
+                  path_to_target: &String,
+                  format: Format)
+                  -> Result<usize, ConfError> {
+
+    info!("Formatting of blocks: {:?}", format);
+
+    if !file_system::exist(path_to_dir) {
+        return Err(io_error(path_to_dir, "Directory not found."));
+    }
+
+    let count = format_files(path_to_dir, path_to_target, format)?;
+
+    info!("-Formatting of blocks: {}", count);
+
+    return Ok(count);
+}
+
+/// Отформатировать текст блока. Если данные не являются описанием в формате списков 1С,
+/// то возвращается None.
+pub fn format_data(data: &Vec<u8>, format: Format) -> Option<Vec<u8>> {
+
+    let doc = match Document::parse(data) {
+        Ok(v) => v,
+        Err(_) => return None,
+    };
+
+    if doc.items.is_empty() || !doc.tail.trim().is_empty() {
+        return None;
+    }
+
+    match format {
+        Format::Pretty => Some(doc.to_pretty_bytes()),
+        Format::Minify => Some(doc.to_minified_bytes()),
+    }
+}
+
+fn format_files(path_to_dir: &String,
+                path_to_target: &String,
+                format: Format)
+                -> Result<usize, ConfError> {
+
+    file_system::create_dir(path_to_target);
+
+    let mut count: usize = 0;
+
+    for (name, path) in &file_system::files_in_dir(path_to_dir) {
+        let target = file_system::path_to_str(Path::new(path_to_target).join(name).as_path());
+
+        if file_system::is_dir(path) {
+            count += format_files(path, &target, format)?;
+            continue;
+        }
+
+        let data = match file_system::read_file(path) {
+            Ok(v) => v,
+            Err(e) => return Err(io_error(path, e)),
+        };
+
+        let is_skipped = name.eq(MANIFEST_FILE_NAME) || is_module_block(name);
+        let formatted = match is_skipped {
+            true => None,
+            false => format_data(&data, format),
+        };
+
+        let data = match formatted {
+            Some(v) => {
+                count += 1;
+                v
+            }
+            None => data,
+        };
+
+        match file_system::write_file(&target, &data) {
+            Ok(_) => (),
+            Err(e) => return Err(io_error(&target, e)),
+        }
+    }
+
+    return Ok(count);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Format, format_data, format_dir};
+
+    use file_system;
+    use std::env;
+    use std::path::Path;
+
+    #[test]
+    fn test_format_data() {
+
+        let text = "\u{feff}{2,\r\n{0,0,b8c42329-e3ee-47c0-9d9e-0fae37b7eefa},\"{ \"\"a\"\" }\",\
+                    {1,{#base64:AAEC}},{}}\r\n";
+
+        let pretty = String::from_utf8(format_data(&text.as_bytes().to_vec(), Format::Pretty)
+                .unwrap())
+            .unwrap();
+        assert_eq!("\u{feff}{2,\n    {0,0,b8c42329-e3ee-47c0-9d9e-0fae37b7eefa},\n    \
+                    \"{ \"\"a\"\" }\",\n    {1,\n        {#base64:AAEC}\n    },\n    {}\n}\n",
+                   pretty);
+
+        let minified = format_data(&pretty.into_bytes(), Format::Minify).unwrap();
+        assert_eq!("\u{feff}{2,{0,0,b8c42329-e3ee-47c0-9d9e-0fae37b7eefa},\"{ \"\"a\"\" }\",\
+                    {1,{#base64:AAEC}},{}}",
+                   String::from_utf8(minified).unwrap());
+
+        assert_eq!(None, format_data(&b"// {text}".to_vec(), Format::Pretty));
+        assert_eq!(None, format_data(&b"{1}text".to_vec(), Format::Pretty));
+        assert_eq!(None, format_data(&b"{1,{2}".to_vec(), Format::Pretty));
+    }
+
+    #[test]
+    fn test_format_dir() {
+
+        let path_to_dir = env::temp_dir().join("conf_robber_test_format");
+        let path_to_dir = file_system::path_to_str(path_to_dir.as_path());
+        if file_system::exist(&path_to_dir) {
+            file_system::remove(&path_to_dir).unwrap();
+        }
+
+        let source = file_system::path_to_str(Path::new(&path_to_dir).join("source").as_path());
+        let target = file_system::path_to_str(Path::new(&path_to_dir).join("target").as_path());
+
+        let group = Path::new(&source).join("block.0");
+        file_system::create_dir(&file_system::path_to_str(group.as_path()));
+
+        let files = [("block", "{1,{2}}"), ("block.0/info", "{3,1,0}"), ("block.0/text", "{1,{2}}")];
+        for &(name, text) in &files {
+            let path = file_system::path_to_str(Path::new(&source).join(name).as_path());
+            file_system::write_file(&path, &text.as_bytes().to_vec()).unwrap();
+        }
+
+        assert_eq!(2, format_dir(&source, &target, Format::Pretty).unwrap());
+
+        let read = |name: &str| {
+            let path = file_system::path_to_str(Path::new(&target).join(name).as_path());
+            String::from_utf8(file_system::read_file(&path).unwrap()).unwrap()
+        };
+        assert_eq!("{1,\n    {2}\n}\n", read("block"));
+        assert_eq!("{3,1,0}\n", read("block.0/info"));
+        assert_eq!("{1,{2}}", read("block.0/text")); // текст модуля не изменяется
+
+        // Исходный каталог не изменяется
+        let path = file_system::path_to_str(Path::new(&source).join("block").as_path());
+        assert_eq!(b"{1,{2}}".to_vec(), file_system::read_file(&path).unwrap());
+
+        file_system::remove(&path_to_dir).unwrap();
+    }
+}
@@ -0,0 +1,177 @@
+
+use file_system;
+
+use meta_data::index::{Index, IndexFile};
+use structure::block::Block;
+use structure::layout::ContainerLayout;
+use structure::manifest::Manifest;
+use error::{ConfError, io_error};
+
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+
+/// Получить блоки конфигурации из каталога, в который конфигурация была выгружена в виде дерева
+/// объектов метаданных. Файлы сопоставляются блокам по индексу выгрузки, поэтому измененный
+/// текст модуля попадает во вложенный блок, из которого он был выгружен.
+/// Если в каталоге есть описание конфигурации, то блоки следуют в порядке оглавления исходного
+/// файла, а их атрибуты восстанавливаются.
+pub fn import(path_to_dir: &String) -> Result<(Vec<Block>, ContainerLayout), ConfError> {
+
+    info!("Import of metadata");
+
+    if !file_system::exist(path_to_dir) {
+        return Err(io_error(path_to_dir, "Directory not found."));
+    }
+
+    let index = Index::read(path_to_dir)?;
+    let manifest = Manifest::read(path_to_dir)?;
+
+    // Файлы блоков в порядке индекса
+    let mut block_ids: Vec<String> = Vec::new();
+    let mut block_files: HashMap<String, Vec<&IndexFile>> = HashMap::new();
+    for file in &index.files {
+        if !block_files.contains_key(&file.block) {
+            block_ids.push(file.block.clone());
+        }
+        block_files.entry(file.block.clone()).or_insert(Vec::new()).push(file);
+    }
+
+    // Сначала блоки из описания, затем блоки, которых нет в описании.
+    // Составные блоки без вложенных блоков не выгружаются в файлы, поэтому их берем из описания.
+    let mut ordered_ids: Vec<String> = Vec::new();
+    let mut container = ContainerLayout::new();
+
+    if let Some(ref manifest) = manifest {
+        container = manifest.container.clone();
+
+        for layout in &manifest.blocks {
+            let is_empty_group = layout.container.is_some() && layout.nested.is_empty();
+            if block_files.contains_key(layout.attrs.id()) || is_empty_group {
+                ordered_ids.push(layout.attrs.id().clone());
+            }
+        }
+    }
+
+    let described_ids: HashSet<String> = ordered_ids.iter().cloned().collect();
+    for block_id in block_ids {
+        if !described_ids.contains(&block_id) {
+            ordered_ids.push(block_id);
+        }
+    }
+
+    let mut retval: Vec<Block> = Vec::new();
+
+    for block_id in ordered_ids {
+        let layout = manifest.as_ref().and_then(|v| v.block(&block_id));
+        let files = block_files.get(&block_id).cloned().unwrap_or(Vec::new());
+
+        let is_group = match layout {
+            Some(v) => v.container.is_some(),
+            None => files.iter().any(|v| v.nested.is_some()),
+        };
+
+        let mut nested_data: Vec<(String, Vec<u8>)> = Vec::new();
+        for file in files {
+            let path = file_path(path_to_dir, &file.path);
+            let data = match file_system::read_file(&path) {
+                Ok(v) => v,
+                Err(e) => return Err(io_error(&path, e)),
+            };
+
+            let nested_block_id = match file.nested {
+                Some(ref v) => v.clone(),
+                None => block_id.clone(),
+            };
+            nested_data.push((nested_block_id, data));
+        }
+
+        retval.push(Block::from_parts(&block_id, is_group, nested_data, layout));
+    }
+
+    info!("-Import of metadata");
+
+    return Ok((retval, container));
+}
+
+// Возвращает полный путь к файлу по пути из индекса
+fn file_path(path_to_dir: &String, path: &String) -> String {
+
+    let mut retval = PathBuf::from(path_to_dir);
+    for part in path.split('/') {
+        retval.push(part);
+    }
+
+    return file_system::path_to_str(retval.as_path());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::import;
+    use meta_data::types;
+    use meta_data::export::export;
+    use structure;
+    use structure::block::Block;
+    use structure::layout::ContainerLayout;
+
+    use file_system;
+    use std::env;
+    use std::path::Path;
+
+    #[test]
+    fn test_import() {
+
+        let conf_id = "be22b29f-2db7-4fcb-8772-eeb5500d2170";
+        let catalog_id = "9d1a9f27-cd9f-488b-b5e1-c8b410fb7856";
+
+        let root = format!("{{2,{},}}", conf_id);
+        let conf = format!("{{2,{{{{0,0,{}}},\"Конфигурация\"}},{{{},1,{}}}}}",
+                           conf_id,
+                           types::CATALOG,
+                           catalog_id);
+        let catalog = format!("{{1,{{{{0,0,{}}},\"Товары\"}}}}", catalog_id);
+        let module = Block::from_parts(&format!("{}.0", catalog_id),
+                                       true,
+                                       vec![(String::from("info"), b"{3,1,0}".to_vec()),
+                                            (String::from("text"), b"// old".to_vec())],
+                                       None);
+
+        let blocks = vec![Block::new("root", &root.into_bytes()),
+                          Block::new(conf_id, &conf.into_bytes()),
+                          module,
+                          Block::new(catalog_id, &catalog.into_bytes())];
+
+        let cf = structure::writer::inflate_cf(&blocks, &ContainerLayout::new(), 1).unwrap();
+        let (blocks, container) = structure::reader::from_cf(cf.clone()).unwrap();
+
+        let path_to_dir = env::temp_dir().join("conf_robber_test_import");
+        let path_to_dir = file_system::path_to_str(path_to_dir.as_path());
+        if file_system::exist(&path_to_dir) {
+            file_system::remove(&path_to_dir).unwrap();
+        }
+
+        export(&blocks, &container, &path_to_dir, 2).unwrap();
+
+        // Без изменений собирается исходный файл
+        let (test, container) = import(&path_to_dir).unwrap();
+        assert_eq!(cf,
+                   structure::writer::inflate_cf(&test, &container, 1).unwrap());
+
+        // Измененный модуль попадает во вложенный блок text
+        let path_to_module = Path::new(&path_to_dir)
+            .join("Catalogs")
+            .join("Товары")
+            .join("ObjectModule.bsl");
+        let path_to_module = file_system::path_to_str(path_to_module.as_path());
+        file_system::write_file(&path_to_module, &b"// new".to_vec()).unwrap();
+
+        let (test, _) = import(&path_to_dir).unwrap();
+        let module = test.iter().find(|v| v.id().eq(&format!("{}.0", catalog_id))).unwrap();
+        let nested_blocks = module.get_data().unwrap();
+        assert_eq!(2, nested_blocks.len());
+        assert_eq!("info", nested_blocks[0].attrs.id());
+        assert_eq!("text", nested_blocks[1].attrs.id());
+        assert_eq!(b"// new".to_vec(), nested_blocks[1].data);
+
+        file_system::remove(&path_to_dir).unwrap();
+    }
+}
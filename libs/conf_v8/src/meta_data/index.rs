@@ -0,0 +1,300 @@
+
+use file_system;
+use xml::{XmlDOM, XmlElement};
+
+use error::{ConfError, io_error};
+
+use std::path::Path;
+
+// Имя файла индекса выгруженной конфигурации
+pub const INDEX_FILE_NAME: &'static str = "index.xml";
+
+// Объект метаданных выгруженной конфигурации
+pub struct IndexObject {
+    pub kind: String, // каталог типа объекта (Catalogs, Forms, ...)
+    pub name: String, // имя объекта
+    pub id: String, // идентификатор блока с описанием объекта
+    pub path: String, // каталог объекта относительно каталога выгрузки
+    pub objects: Vec<IndexObject>, // подчиненные объекты (формы, макеты, команды)
+}
+
+// Файл выгруженной конфигурации и блок, данные которого в нем записаны
+pub struct IndexFile {
+    pub path: String, // путь к файлу относительно каталога выгрузки
+    pub block: String, // идентификатор блока
+    pub nested: Option<String>, // идентификатор вложенного блока, если блок составной
+}
+
+// Индекс выгруженной конфигурации: соответствие имен объектов метаданных и файлов
+// идентификаторам блоков.
+//
+// Пример:
+//   <index configuration="be22b29f-2db7-4fcb-8772-eeb5500d2170">
+//       <objects>
+//           <object kind="Catalogs" name="Товары" id="..." path="Catalogs/Товары">
+//               <object kind="Forms" name="ФормаЭлемента" id="..." path="..."/>
+//           </object>
+//       </objects>
+//       <files>
+//           <file path="Catalogs/Товары/ObjectModule.bsl" block="....0" nested="text"/>
+//       </files>
+//   </index>
+pub struct Index {
+    pub configuration: String, // идентификатор блока с описанием конфигурации
+    pub objects: Vec<IndexObject>,
+    pub files: Vec<IndexFile>,
+}
+
+impl Index {
+    pub fn new(configuration: &str) -> Index {
+        return Index {
+            configuration: String::from(configuration),
+            objects: Vec::new(),
+            files: Vec::new(),
+        };
+    }
+
+    // Возвращает текст индекса
+    pub fn to_xml(&self) -> String {
+
+        let mut text = String::new();
+        text.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        text.push_str(&format!("<index configuration=\"{}\">\n",
+                               escape(&self.configuration)));
+
+        text.push_str("    <objects>\n");
+        for object in &self.objects {
+            object_to_xml(&mut text, object, 2);
+        }
+        text.push_str("    </objects>\n");
+
+        text.push_str("    <files>\n");
+        for file in &self.files {
+            text.push_str(&format!("        <file path=\"{}\" block=\"{}\"",
+                                   escape(&file.path),
+                                   escape(&file.block)));
+            if let Some(ref nested) = file.nested {
+                text.push_str(&format!(" nested=\"{}\"", escape(nested)));
+            }
+            text.push_str("/>\n");
+        }
+        text.push_str("    </files>\n");
+
+        text.push_str("</index>\n");
+
+        return text;
+    }
+
+    // Получить индекс из текста. Если текст поврежден (пустой или обрезанный), то возвращается ошибка.
+    pub fn from_xml(text: &String) -> Result<Index, String> {
+
+        let dom = XmlDOM::parse(text)?;
+        let root = dom.root();
+
+        if root.name.ne("index") {
+            return Err(String::from("Unknown format of the index."));
+        }
+
+        let mut retval = Index::new(&attribute(root, "configuration")?);
+
+        if let Some(objects) = root.first("objects") {
+            for object in objects.find("object") {
+                retval.objects.push(object_from_xml(object)?);
+            }
+        }
+
+        if let Some(files) = root.first("files") {
+            for file in files.find("file") {
+                retval.files.push(IndexFile {
+                    path: attribute(file, "path")?,
+                    block: attribute(file, "block")?,
+                    nested: file.attributes.get("nested").cloned(),
+                });
+            }
+        }
+
+        return Ok(retval);
+    }
+
+    // Прочитать индекс из каталога выгрузки
+    pub fn read(path_to_dir: &String) -> Result<Index, ConfError> {
+
+        let path = index_path(path_to_dir);
+        if !file_system::exist(&path) {
+            return Err(io_error(&path, "The index of the exported configuration is not found."));
+        }
+
+        let data = match file_system::read_file(&path) {
+            Ok(v) => v,
+            Err(e) => return Err(io_error(&path, e)),
+        };
+
+        let text = match String::from_utf8(data) {
+            Ok(v) => v,
+            Err(e) => return Err(io_error(&path, format!("{}", e))),
+        };
+
+        match Index::from_xml(&text) {
+            Ok(v) => Ok(v),
+            Err(e) => Err(io_error(&path, e)),
+        }
+    }
+
+    // Записать индекс в каталог выгрузки
+    pub fn write(&self, path_to_dir: &String) -> Result<(), ConfError> {
+
+        let path = index_path(path_to_dir);
+
+        match file_system::write_file(&path, &self.to_xml().into_bytes()) {
+            Ok(_) => Ok(()),
+            Err(e) => Err(io_error(&path, e)),
+        }
+    }
+}
+
+// Возвращает путь к файлу индекса
+fn index_path(path_to_dir: &String) -> String {
+    file_system::path_to_str(Path::new(path_to_dir).join(INDEX_FILE_NAME).as_path())
+}
+
+fn object_from_xml(element: &XmlElement) -> Result<IndexObject, String> {
+
+    let mut objects: Vec<IndexObject> = Vec::new();
+    for object in element.find("object") {
+        objects.push(object_from_xml(object)?);
+    }
+
+    Ok(IndexObject {
+        kind: attribute(element, "kind")?,
+        name: attribute(element, "name")?,
+        id: attribute(element, "id")?,
+        path: attribute(element, "path")?,
+        objects: objects,
+    })
+}
+
+// Возвращает значение обязательного атрибута элемента
+fn attribute(element: &XmlElement, name: &str) -> Result<String, String> {
+    match element.attributes.get(name) {
+        Some(v) => Ok(v.clone()),
+        None => Err(format!("Not found attribute '{}' of the element '{}'.", name, element.name)),
+    }
+}
+
+fn object_to_xml(text: &mut String, object: &IndexObject, level: usize) {
+
+    let indent = "    ".repeat(level);
+
+    text.push_str(&format!("{}<object kind=\"{}\" name=\"{}\" id=\"{}\" path=\"{}\"",
+                           indent,
+                           escape(&object.kind),
+                           escape(&object.name),
+                           escape(&object.id),
+                           escape(&object.path)));
+
+    if object.objects.is_empty() {
+        text.push_str("/>\n");
+        return;
+    }
+
+    text.push_str(">\n");
+    for nested_object in &object.objects {
+        object_to_xml(text, nested_object, level + 1);
+    }
+    text.push_str(&format!("{}</object>\n", indent));
+}
+
+// Экранирование значения атрибута
+fn escape(value: &str) -> String {
+
+    let mut retval = String::with_capacity(value.len());
+
+    for c in value.chars() {
+        match c {
+            '&' => retval.push_str("&amp;"),
+            '<' => retval.push_str("&lt;"),
+            '>' => retval.push_str("&gt;"),
+            '"' => retval.push_str("&quot;"),
+            _ => retval.push(c),
+        }
+    }
+
+    return retval;
+}
+
+#[test]
+fn test_index_xml() {
+
+    let mut index = Index::new("conf");
+    index.objects.push(IndexObject {
+        kind: String::from("Catalogs"),
+        name: String::from("Товары"),
+        id: String::from("id"),
+        path: String::from("Catalogs/Товары"),
+        objects: vec![IndexObject {
+                          kind: String::from("Forms"),
+                          name: String::from("Форма"),
+                          id: String::from("form"),
+                          path: String::from("Catalogs/Товары/Forms/Форма"),
+                          objects: Vec::new(),
+                      }],
+    });
+    index.files.push(IndexFile {
+        path: String::from("Catalogs/Товары/ObjectModule.bsl"),
+        block: String::from("id.0"),
+        nested: Some(String::from("text")),
+    });
+    index.files.push(IndexFile {
+        path: String::from("Other/a&b"),
+        block: String::from("a&b"),
+        nested: None,
+    });
+
+    let text = index.to_xml();
+    assert!(text.contains("<index configuration=\"conf\">"));
+    assert!(text.contains("        <object kind=\"Catalogs\" name=\"Товары\" id=\"id\" \
+                           path=\"Catalogs/Товары\">\n            <object kind=\"Forms\""));
+    assert!(text.contains("<file path=\"Catalogs/Товары/ObjectModule.bsl\" block=\"id.0\" \
+                           nested=\"text\"/>"));
+    assert!(text.contains("<file path=\"Other/a&amp;b\" block=\"a&amp;b\"/>"));
+
+    let test = Index::from_xml(&text).unwrap();
+    assert_eq!("conf", test.configuration);
+    assert_eq!(1, test.objects.len());
+    assert_eq!("Товары", test.objects[0].name);
+    assert_eq!("Catalogs/Товары/Forms/Форма", test.objects[0].objects[0].path);
+    assert_eq!(2, test.files.len());
+    assert_eq!("id.0", test.files[0].block);
+    assert_eq!(Some(String::from("text")), test.files[0].nested);
+    assert_eq!("a&b", test.files[1].block);
+    assert_eq!(None, test.files[1].nested);
+
+    assert!(Index::from_xml(&String::from("<manifest/>")).is_err());
+}
+
+#[test]
+fn test_index_read_corrupt() {
+    use std::env;
+    use error::Stage;
+
+    let path_to_dir = env::temp_dir().join("conf_robber_test_index");
+    let path_to_dir = file_system::path_to_str(path_to_dir.as_path());
+    if file_system::exist(&path_to_dir) {
+        file_system::remove(&path_to_dir).unwrap();
+    }
+    file_system::create_dir(&path_to_dir);
+
+    // Пустой и обрезанный индекс поврежденной выгрузки
+    let text = Index::new("conf").to_xml();
+    for data in &[String::new(), text[..text.len() / 2].to_string()] {
+        file_system::write_file(&index_path(&path_to_dir), &data.as_bytes().to_vec()).unwrap();
+
+        let e = Index::read(&path_to_dir).err().unwrap();
+        assert_eq!(Stage::Io, e.stage());
+    }
+
+    file_system::write_file(&index_path(&path_to_dir), &text.into_bytes()).unwrap();
+    assert_eq!("conf", Index::read(&path_to_dir).unwrap().configuration);
+
+    file_system::remove(&path_to_dir).unwrap();
+}
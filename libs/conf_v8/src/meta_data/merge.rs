@@ -0,0 +1,575 @@
+
+use meta_data::brace::{Document, List, Node, Value, UTF8_BOM, without_bom};
+use meta_data::diff::line_matches;
+use meta_data::modules::is_module_block;
+use meta_data::objects::{MetadataObject, metadata_tree};
+use structure::block::Block;
+use error::ConfError;
+
+use std::collections::HashMap;
+
+// Данные вложенных блоков: идентификатор и данные
+type NestedData = Vec<(String, Vec<u8>)>;
+
+// Маркеры конфликтов в тексте модуля
+const MARKER_OURS: &'static str = "<<<<<<< ours";
+const MARKER_BASE: &'static str = "||||||| base";
+const MARKER_SEPARATOR: &'static str = "=======";
+const MARKER_THEIRS: &'static str = ">>>>>>> theirs";
+
+/// Вид конфликта
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ConflictKind {
+    Text, // текст модуля изменен в обеих конфигурациях, в текст записаны маркеры конфликта
+    Data, // данные изменены в обеих конфигурациях, оставлены данные нашей конфигурации
+    Removed, // данные удалены в одной конфигурации и изменены в другой, оставлены измененные данные
+}
+
+/// Конфликт объединения
+pub struct Conflict {
+    pub kind: ConflictKind,
+    pub block_id: String, // идентификатор блока
+    pub nested_block_id: Option<String>, // идентификатор вложенного блока составного блока
+    pub object: Option<String>, // полное имя объекта метаданных, к которому относится блок
+}
+
+/// Результат объединения: блоки объединенной конфигурации и конфликты
+pub struct MergeResult {
+    pub blocks: Vec<Block>,
+    pub conflicts: Vec<Conflict>,
+}
+
+impl ConflictKind {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            ConflictKind::Text => "text",
+            ConflictKind::Data => "data",
+            ConflictKind::Removed => "removed",
+        }
+    }
+}
+
+impl Conflict {
+    /// Возвращает описание конфликта: <вид> <блок>[/<вложенный блок>] [(<объект>)]
+    pub fn description(&self) -> String {
+
+        let mut retval = format!("{} {}", self.kind.name(), self.block_id);
+        if let Some(ref nested_block_id) = self.nested_block_id {
+            retval.push('/');
+            retval.push_str(nested_block_id);
+        }
+        if let Some(ref object) = self.object {
+            retval.push_str(&format!(" ({})", object));
+        }
+
+        return retval;
+    }
+}
+
+/// Трехстороннее объединение конфигураций: изменения нашей (ours) и их (theirs) конфигураций
+/// относительно общей исходной (base) конфигурации.
+///
+/// Блоки, измененные только в одной конфигурации, берутся из нее. Если блок изменен в обеих,
+/// то объединяются его вложенные блоки: тексты модулей объединяются построчно, описания
+/// в формате списков 1С - по значениям (в списки объектов типов добавляются объекты обеих
+/// конфигураций). Если изменения объединить нельзя, то возвращается конфликт.
+pub fn merge(base: &Vec<Block>,
+             ours: &Vec<Block>,
+             theirs: &Vec<Block>)
+             -> Result<MergeResult, ConfError> {
+
+    info!("Merging of configurations");
+
+    let base_ids = blocks_ids(base);
+    let theirs_ids = blocks_ids(theirs);
+    let ours_ids = blocks_ids(ours);
+
+    // Сначала блоки нашей конфигурации, затем добавленные в их конфигурации
+    let mut ordered_ids: Vec<&str> = ours.iter().map(|v| &**v.id()).collect();
+    for block in theirs.iter() {
+        if !ours_ids.contains_key(&**block.id()) {
+            ordered_ids.push(block.id());
+        }
+    }
+
+    let mut retval = MergeResult {
+        blocks: Vec::new(),
+        conflicts: Vec::new(),
+    };
+
+    for block_id in ordered_ids {
+        let merged = merge_block(block_id,
+                                 base_ids.get(block_id).cloned(),
+                                 ours_ids.get(block_id).cloned(),
+                                 theirs_ids.get(block_id).cloned(),
+                                 &mut retval.conflicts)?;
+        if let Some(block) = merged {
+            retval.blocks.push(block);
+        }
+    }
+
+    // Имена объектов метаданных для описания конфликтов
+    if !retval.conflicts.is_empty() {
+        let names = objects_names(ours);
+        for conflict in retval.conflicts.iter_mut() {
+            let object_id = conflict.block_id.split('.').next().unwrap_or("");
+            conflict.object = names.get(object_id).cloned();
+        }
+    }
+
+    info!("-Merging of configurations: conflicts {}", retval.conflicts.len());
+
+    return Ok(retval);
+}
+
+/// Трехстороннее объединение текстов модулей.
+/// Возвращает объединенный текст и признак конфликта. В местах конфликтов в текст записываются
+/// изменения обеих конфигураций и исходный текст, разделенные маркерами.
+/// Переводы строк и маркер UTF-8 берутся из нашего текста.
+pub fn merge_text(base: &Vec<u8>, ours: &Vec<u8>, theirs: &Vec<u8>) -> (Vec<u8>, bool) {
+
+    let base_lines = text_lines(base);
+    let ours_lines = text_lines(ours);
+    let theirs_lines = text_lines(theirs);
+
+    let ours_matches = line_matches(&base_lines, &ours_lines);
+    let theirs_matches = line_matches(&base_lines, &theirs_lines);
+
+    let mut lines: Vec<&str> = Vec::new();
+    let mut is_conflict = false;
+
+    // Позиции в исходном, нашем и их текстах
+    let (mut i, mut a, mut c) = (0, 0, 0);
+
+    loop {
+        // Следующая строка исходного текста, которая не изменена в обеих конфигурациях
+        let stable = (i..base_lines.len()).find(|&k| {
+            ours_matches[k].is_some() && theirs_matches[k].is_some()
+        });
+
+        let (k, ak, ck) = match stable {
+            Some(k) => (k, ours_matches[k].unwrap(), theirs_matches[k].unwrap()),
+            None => (base_lines.len(), ours_lines.len(), theirs_lines.len()),
+        };
+
+        let base_chunk = &base_lines[i..k];
+        let ours_chunk = &ours_lines[a..ak];
+        let theirs_chunk = &theirs_lines[c..ck];
+
+        if ours_chunk == base_chunk || ours_chunk == theirs_chunk {
+            lines.extend(theirs_chunk.iter().map(|v| &**v));
+        } else if theirs_chunk == base_chunk {
+            lines.extend(ours_chunk.iter().map(|v| &**v));
+        } else {
+            is_conflict = true;
+            lines.push(MARKER_OURS);
+            lines.extend(ours_chunk.iter().map(|v| &**v));
+            lines.push(MARKER_BASE);
+            lines.extend(base_chunk.iter().map(|v| &**v));
+            lines.push(MARKER_SEPARATOR);
+            lines.extend(theirs_chunk.iter().map(|v| &**v));
+            lines.push(MARKER_THEIRS);
+        }
+
+        if stable.is_none() {
+            break;
+        }
+
+        lines.push(&base_lines[k]);
+        i = k + 1;
+        a = ak + 1;
+        c = ck + 1;
+    }
+
+    let line_break = match ours.windows(2).any(|v| v == b"\r\n") {
+        true => "\r\n",
+        false => "\n",
+    };
+
+    let mut retval: Vec<u8> = Vec::new();
+    if ours.starts_with(&UTF8_BOM) {
+        retval.extend_from_slice(&UTF8_BOM);
+    }
+    retval.extend_from_slice(lines.join(line_break).as_bytes());
+
+    return (retval, is_conflict);
+}
+
+/// Трехстороннее объединение описаний в формате списков 1С.
+/// Если изменения объединить нельзя, то возвращается None.
+pub fn merge_description(base: &Vec<u8>, ours: &Vec<u8>, theirs: &Vec<u8>) -> Option<Vec<u8>> {
+
+    let (base, ours, theirs) = match (Document::parse(base),
+                                      Document::parse(ours),
+                                      Document::parse(theirs)) {
+        (Ok(b), Ok(o), Ok(t)) => (b, o, t),
+        _ => return None,
+    };
+
+    if ours.bom != theirs.bom || ours.tail != theirs.tail ||
+       ours.items.len() != base.items.len() || theirs.items.len() != base.items.len() {
+        return None;
+    }
+
+    let mut items: Vec<Node> = Vec::new();
+    for ((b, o), t) in base.items.iter().zip(ours.items.iter()).zip(theirs.items.iter()) {
+        items.push(Node {
+            before: o.before.clone(),
+            value: merge_values(&b.value, &o.value, &t.value)?,
+            after: o.after.clone(),
+        });
+    }
+
+    let doc = Document {
+        bom: ours.bom,
+        items: items,
+        tail: ours.tail,
+    };
+
+    return Some(doc.to_bytes());
+}
+
+// Объединить значения
+fn merge_values(base: &Value, ours: &Value, theirs: &Value) -> Option<Value> {
+
+    if ours == theirs || theirs == base {
+        return Some(ours.clone());
+    }
+    if ours == base {
+        return Some(theirs.clone());
+    }
+
+    let (base, ours, theirs) = match (base.list(), ours.list(), theirs.list()) {
+        (Some(b), Some(o), Some(t)) => (b, o, t),
+        _ => return None,
+    };
+
+    // Список объектов типа: {<идентификатор типа>,<количество>,<идентификаторы объектов>}
+    if let (Some(b), Some(o), Some(t)) = (type_elements(base), type_elements(ours), type_elements(theirs)) {
+        if b.0 == o.0 && o.0 == t.0 {
+            return Some(Value::List(merge_type_elements(b, o, t)));
+        }
+    }
+
+    if ours.items.len() != base.items.len() || theirs.items.len() != base.items.len() {
+        return None;
+    }
+
+    let mut items: Vec<Node> = Vec::new();
+    for ((b, o), t) in base.items.iter().zip(ours.items.iter()).zip(theirs.items.iter()) {
+        items.push(Node {
+            before: o.before.clone(),
+            value: merge_values(&b.value, &o.value, &t.value)?,
+            after: o.after.clone(),
+        });
+    }
+
+    return Some(Value::List(List {
+        items: items,
+        space: ours.space.clone(),
+    }));
+}
+
+// Возвращает идентификатор типа и идентификаторы объектов, если список является списком объектов типа
+fn type_elements(list: &List) -> Option<(&str, Vec<&str>)> {
+
+    let type_id = list.item(0).and_then(Value::guid)?;
+    let count = list.item(1).and_then(Value::number)?;
+
+    let ids: Vec<&str> = list.items[2..].iter().filter_map(|v| v.value.guid()).collect();
+    if ids.len() != list.items.len() - 2 || count.ne(&ids.len().to_string()) {
+        return None;
+    }
+
+    return Some((type_id, ids));
+}
+
+// Объединить списки объектов типа: к нашим объектам добавляются объекты, добавленные
+// в их конфигурации, и удаляются объекты, удаленные в их конфигурации
+fn merge_type_elements(base: (&str, Vec<&str>),
+                       ours: (&str, Vec<&str>),
+                       theirs: (&str, Vec<&str>))
+                       -> List {
+
+    let (type_id, base_ids) = base;
+    let ours_ids = ours.1;
+    let theirs_ids = theirs.1;
+
+    let mut ids: Vec<&str> = ours_ids.iter()
+        .filter(|v| !base_ids.contains(v) || theirs_ids.contains(v))
+        .cloned()
+        .collect();
+    for id in theirs_ids {
+        if !base_ids.contains(&id) && !ids.contains(&id) {
+            ids.push(id);
+        }
+    }
+
+    let mut values = vec![Value::Guid(String::from(type_id)), Value::Number(ids.len().to_string())];
+    values.extend(ids.iter().map(|v| Value::Guid(String::from(*v))));
+
+    return List::new(values);
+}
+
+// Объединить блок. Если блок удален, то возвращается None.
+fn merge_block(block_id: &str,
+               base: Option<&Block>,
+               ours: Option<&Block>,
+               theirs: Option<&Block>,
+               conflicts: &mut Vec<Conflict>)
+               -> Result<Option<Block>, ConfError> {
+
+    let base_data = block_data(base)?;
+    let ours_data = block_data(ours)?;
+    let theirs_data = block_data(theirs)?;
+
+    if ours_data == theirs_data || theirs_data == base_data {
+        return Ok(ours.cloned());
+    }
+    if ours_data == base_data {
+        return Ok(theirs.cloned());
+    }
+
+    let (ours, ours_data, theirs_data) = match (ours, ours_data, theirs_data) {
+        (Some(o), Some(od), Some(td)) => (o, od, td),
+        (o, _, _) => {
+            // Блок удален в одной конфигурации и изменен в другой
+            conflicts.push(conflict(ConflictKind::Removed, block_id, None));
+            return Ok(o.or(theirs).cloned());
+        }
+    };
+    let base_data = base_data.unwrap_or(Vec::new());
+
+    let layout = ours.layout()?;
+    let is_group = layout.container.is_some();
+
+    // Сначала вложенные блоки нашей конфигурации, затем добавленные в их конфигурации
+    let mut nested_ids: Vec<&String> = ours_data.iter().map(|v| &v.0).collect();
+    for (id, _) in &theirs_data {
+        if !nested_ids.contains(&id) {
+            nested_ids.push(id);
+        }
+    }
+
+    let mut nested_data: NestedData = Vec::new();
+
+    for nested_id in nested_ids {
+        let b = nested(&base_data, nested_id);
+        let o = nested(&ours_data, nested_id);
+        let t = nested(&theirs_data, nested_id);
+
+        let nested_block_id = match is_group {
+            true => Some(nested_id.clone()),
+            false => None,
+        };
+
+        let data = if o == t || t == b {
+            o
+        } else if o == b {
+            t
+        } else {
+            match (o, t) {
+                (Some(o), Some(t)) => {
+                    let empty: Vec<u8> = Vec::new();
+                    let b = b.unwrap_or(&empty);
+
+                    if is_module_block(nested_id) {
+                        let (text, is_conflict) = merge_text(b, o, t);
+                        if is_conflict {
+                            conflicts.push(conflict(ConflictKind::Text, block_id, nested_block_id));
+                        }
+                        nested_data.push((nested_id.clone(), text));
+                        continue;
+                    }
+
+                    match merge_description(b, o, t) {
+                        Some(v) => {
+                            nested_data.push((nested_id.clone(), v));
+                            continue;
+                        }
+                        None => {
+                            conflicts.push(conflict(ConflictKind::Data, block_id, nested_block_id));
+                            Some(o)
+                        }
+                    }
+                }
+                (o, t) => {
+                    conflicts.push(conflict(ConflictKind::Removed, block_id, nested_block_id));
+                    o.or(t)
+                }
+            }
+        };
+
+        if let Some(data) = data {
+            nested_data.push((nested_id.clone(), data.clone()));
+        }
+    }
+
+    return Ok(Some(Block::from_parts(block_id, is_group, nested_data, Some(&layout))));
+}
+
+// Возвращает данные вложенных блоков
+fn block_data(block: Option<&Block>) -> Result<Option<NestedData>, ConfError> {
+
+    let block = match block {
+        Some(v) => v,
+        None => return Ok(None),
+    };
+
+    let retval = block.get_data()?
+        .into_iter()
+        .map(|v| (v.attrs.id().clone(), v.data))
+        .collect();
+
+    return Ok(Some(retval));
+}
+
+fn nested<'a>(data: &'a NestedData, nested_id: &String) -> Option<&'a Vec<u8>> {
+    data.iter().find(|v| v.0.eq(nested_id)).map(|v| &v.1)
+}
+
+fn blocks_ids(blocks: &Vec<Block>) -> HashMap<&str, &Block> {
+    blocks.iter().map(|v| (&**v.id(), v)).collect()
+}
+
+fn conflict(kind: ConflictKind, block_id: &str, nested_block_id: Option<String>) -> Conflict {
+    Conflict {
+        kind: kind,
+        block_id: String::from(block_id),
+        nested_block_id: nested_block_id,
+        object: None,
+    }
+}
+
+// Возвращает строки текста без маркера UTF-8. Последняя строка может быть пустой,
+// поэтому объединение строк восстанавливает текст.
+fn text_lines(data: &Vec<u8>) -> Vec<String> {
+
+    let data = without_bom(data);
+
+    String::from_utf8_lossy(data)
+        .replace("\r\n", "\n")
+        .split('\n')
+        .map(String::from)
+        .collect()
+}
+
+// Возвращает полные имена объектов метаданных по идентификаторам блоков
+fn objects_names(blocks: &Vec<Block>) -> HashMap<String, String> {
+
+    let mut retval: HashMap<String, String> = HashMap::new();
+
+    fn add(retval: &mut HashMap<String, String>, objects: &Vec<MetadataObject>, prefix: &str) {
+        for object in objects {
+            let name = match prefix.is_empty() {
+                true => format!("{}.{}", object.kind, object.name),
+                false => format!("{}.{}.{}", prefix, object.kind, object.name),
+            };
+            add(retval, &object.objects, &name);
+            retval.insert(object.id.clone(), name);
+        }
+    }
+
+    match metadata_tree(blocks) {
+        Ok(tree) => add(&mut retval, &tree.objects, ""),
+        Err(e) => warn!("{}", e),
+    }
+
+    return retval;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ConflictKind, merge, merge_text, merge_description};
+    use meta_data::types;
+    use structure::block::Block;
+
+    #[test]
+    fn test_merge_text() {
+
+        let base = b"\xEF\xBB\xBFa\r\nb\r\nc\r\nd\r\n".to_vec();
+        let ours = b"\xEF\xBB\xBFa\r\nB\r\nc\r\nd\r\n".to_vec();
+        let theirs = b"a\nb\nc\nD\ne\n".to_vec();
+
+        let (text, is_conflict) = merge_text(&base, &ours, &theirs);
+        assert!(!is_conflict);
+        assert_eq!(b"\xEF\xBB\xBFa\r\nB\r\nc\r\nD\r\ne\r\n".to_vec(), text);
+
+        let theirs = b"a\nb2\nc\nd\n".to_vec();
+        let (text, is_conflict) = merge_text(&base, &ours, &theirs);
+        assert!(is_conflict);
+        assert_eq!("\u{feff}a\r\n<<<<<<< ours\r\nB\r\n||||||| base\r\nb\r\n=======\r\nb2\r\n\
+                    >>>>>>> theirs\r\nc\r\nd\r\n",
+                   String::from_utf8(text).unwrap());
+
+        // Одинаковые изменения не являются конфликтом
+        let (text, is_conflict) = merge_text(&base, &ours, &ours);
+        assert!(!is_conflict);
+        assert_eq!(ours, text);
+    }
+
+    #[test]
+    fn test_merge_description() {
+
+        let base = format!("{{2,\"name\",{{{},1,a1a1a1a1-0000-0000-0000-000000000001}}}}",
+                           types::CATALOG);
+        let ours = format!("{{2,\"name\",{{{},2,a1a1a1a1-0000-0000-0000-000000000001,\
+                            b2b2b2b2-0000-0000-0000-000000000002}}}}",
+                           types::CATALOG);
+        let theirs = format!("{{2,\"new name\",{{{},2,a1a1a1a1-0000-0000-0000-000000000001,\
+                              c3c3c3c3-0000-0000-0000-000000000003}}}}",
+                             types::CATALOG);
+
+        let merged = merge_description(&base.into_bytes(), &ours.into_bytes(), &theirs.into_bytes())
+            .unwrap();
+        assert_eq!(format!("{{2,\"new name\",{{{},3,a1a1a1a1-0000-0000-0000-000000000001,\
+                            b2b2b2b2-0000-0000-0000-000000000002,\
+                            c3c3c3c3-0000-0000-0000-000000000003}}}}",
+                           types::CATALOG),
+                   String::from_utf8(merged).unwrap());
+
+        assert_eq!(None,
+                   merge_description(&b"{1,2}".to_vec(), &b"{1,3}".to_vec(), &b"{1,4}".to_vec()));
+        assert_eq!(Some(b"{3,4}".to_vec()),
+                   merge_description(&b"{1,2}".to_vec(), &b"{3,2}".to_vec(), &b"{1,4}".to_vec()));
+    }
+
+    fn module(id: &str, text: &str) -> Block {
+        Block::from_parts(id,
+                          true,
+                          vec![(String::from("info"), b"{3,1,0}".to_vec()),
+                               (String::from("text"), text.as_bytes().to_vec())],
+                          None)
+    }
+
+    #[test]
+    fn test_merge() {
+
+        let base = vec![Block::new("a", &b"{1}".to_vec()),
+                        Block::new("b", &b"{1}".to_vec()),
+                        module("c.0", "1\n2\n3\n"),
+                        Block::new("d", &b"{\"d\"}".to_vec())];
+        let ours = vec![Block::new("a", &b"{2}".to_vec()),
+                        Block::new("b", &b"{1}".to_vec()),
+                        module("c.0", "one\n2\n3\n"),
+                        Block::new("d", &b"{\"ours\"}".to_vec())];
+        let theirs = vec![Block::new("a", &b"{1}".to_vec()),
+                          module("c.0", "1\n2\nthree\n"),
+                          Block::new("d", &b"{\"theirs\"}".to_vec()),
+                          Block::new("e", &b"{5}".to_vec())];
+
+        let result = merge(&base, &ours, &theirs).unwrap();
+
+        let ids: Vec<&str> = result.blocks.iter().map(|v| &**v.id()).collect();
+        assert_eq!(vec!["a", "c.0", "d", "e"], ids);
+
+        let data = |i: usize, nested: usize| result.blocks[i].get_data().unwrap()[nested].data.clone();
+        assert_eq!(b"{2}".to_vec(), data(0, 0));
+        assert_eq!(b"one\n2\nthree\n".to_vec(), data(1, 1));
+        assert_eq!(b"{\"ours\"}".to_vec(), data(2, 0));
+
+        assert_eq!(1, result.conflicts.len());
+        assert_eq!(ConflictKind::Data, result.conflicts[0].kind);
+        assert_eq!("data d", result.conflicts[0].description());
+    }
+}
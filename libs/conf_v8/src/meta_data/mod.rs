@@ -1,6 +1,15 @@
-#[macro_use]
-pub mod substr;
+pub mod brace;
 pub mod types;
 pub mod reader;
 pub mod writer;
 pub mod description;
+pub mod index;
+pub mod export;
+pub mod import;
+pub mod objects;
+pub mod modules;
+pub mod format;
+pub mod diff;
+pub mod merge;
+pub mod external;
+pub mod extension;
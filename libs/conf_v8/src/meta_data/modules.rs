@@ -0,0 +1,246 @@
+
+use file_system;
+
+use meta_data::brace::without_bom;
+use meta_data::export::block_file_name;
+use meta_data::objects::{MetadataObject, metadata_tree};
+use structure::block::Block;
+use structure::manifest::BlockLayout;
+use structure::writer::process_blocks;
+use error::{ConfError, io_error};
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+const CONFIGURATION: &'static str = "Configuration"; // префикс модулей конфигурации
+
+/// Записать тексты модулей объектов метаданных (модулей объектов, менеджеров, форм и общих модулей)
+/// в файлы <Тип>.<Имя объекта>.<Вид модуля>.bsl. Текст записывается в кодировке UTF-8 без маркера
+/// и с переводами строк LF. Возвращает количество записанных модулей.
+pub fn extract_modules(blocks: &Vec<Block>,
+                       path_to_dir: &String,
+                       workers: usize)
+                       -> Result<usize, ConfError> {
+
+    info!("Extracting of modules");
+
+    // Распаковываем данные блоков в нескольких потоках
+    let layouts = process_blocks(blocks, workers, |block| block.layout())?;
+
+    let tree = metadata_tree(blocks)?;
+
+    let mut extractor = ModulesExtractor {
+        blocks: blocks,
+        layouts: &layouts,
+        path_to_dir: path_to_dir,
+        groups: HashMap::new(),
+        names: HashSet::new(),
+        count: 0,
+    };
+
+    for (i, block) in blocks.iter().enumerate() {
+        let object_id = block.id().split('.').next().unwrap_or("");
+        extractor.groups.entry(String::from(object_id)).or_insert(Vec::new()).push(i);
+    }
+
+    file_system::create_dir(path_to_dir);
+
+    extractor.extract_blocks(&tree.conf_id, "", CONFIGURATION)?;
+    extractor.extract_blocks(&tree.conf_internal_id, "", CONFIGURATION)?;
+
+    for object in &tree.objects {
+        let prefix = format!("{}.{}", object.kind, object.name);
+        extractor.extract_object(object, &prefix)?;
+    }
+
+    info!("-Extracting of modules: {}", extractor.count);
+
+    return Ok(extractor.count);
+}
+
+/// Возвращает вид модуля (ObjectModule, ManagerModule, Module, ...) по номеру блока с данными объекта
+/// и имени вложенного блока. Если вложенный блок не содержит текст модуля, то возвращается None.
+pub fn module_kind(type_id: &str, suffix: &str, nested_block_id: &str) -> Option<String> {
+    match nested_block_id {
+        "text" => Some(block_file_name(type_id, suffix, true)),
+        "module" => Some(String::from("Module")),
+        _ => None,
+    }
+}
+
+/// Возвращает признак вложенного блока с текстом модуля: text в составном блоке модуля
+/// или module в составном блоке обычной формы
+pub fn is_module_block(nested_block_id: &str) -> bool {
+    module_kind("", "", nested_block_id).is_some()
+}
+
+/// Возвращает текст модуля в кодировке UTF-8 без маркера и с переводами строк LF
+pub fn normalize_text(data: &Vec<u8>) -> Vec<u8> {
+
+    let data = without_bom(data);
+
+    let text = String::from_utf8_lossy(data).replace("\r\n", "\n");
+
+    return text.into_bytes();
+}
+
+struct ModulesExtractor<'a> {
+    blocks: &'a Vec<Block>,
+    layouts: &'a Vec<BlockLayout>,
+    path_to_dir: &'a String,
+    groups: HashMap<String, Vec<usize>>, // блоки по идентификатору объекта (имя блока до точки)
+    names: HashSet<String>, // имена записанных файлов (без учета регистра)
+    count: usize, // количество записанных модулей
+}
+
+impl<'a> ModulesExtractor<'a> {
+    // Записать модули объекта и его подчиненных объектов (форм, команд)
+    fn extract_object(&mut self, object: &MetadataObject, prefix: &str) -> Result<(), ConfError> {
+
+        self.extract_blocks(&object.id, object.type_id, prefix)?;
+
+        for nested_object in &object.objects {
+            let nested_prefix = format!("{}.{}.{}", prefix, nested_object.kind, nested_object.name);
+            self.extract_object(nested_object, &nested_prefix)?;
+        }
+
+        Ok(())
+    }
+
+    // Записать модули из блоков с данными объекта (<идентификатор>.<номер>).
+    // Текст модуля хранится во вложенном блоке text составного блока модуля
+    // или во вложенном блоке module составного блока обычной формы.
+    fn extract_blocks(&mut self, object_id: &str, type_id: &str, prefix: &str) -> Result<(), ConfError> {
+
+        let ids = match self.groups.get(object_id) {
+            Some(v) => v.clone(),
+            None => return Ok(()),
+        };
+
+        for i in ids {
+            let block_id = self.blocks[i].id().clone();
+            if block_id.eq(object_id) || self.layouts[i].container.is_none() {
+                continue;
+            }
+
+            for nested_block in self.blocks[i].get_data()? {
+                let kind = match module_kind(type_id,
+                                             &block_id[object_id.len() + 1..],
+                                             nested_block.attrs.id()) {
+                    Some(v) => v,
+                    None => continue,
+                };
+
+                self.write_module(format!("{}.{}", prefix, kind), &block_id, &nested_block.data)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // Записать текст модуля в файл. Если файл с таким именем уже записан,
+    // то к имени добавляется идентификатор блока.
+    fn write_module(&mut self,
+                    file_name: String,
+                    block_id: &str,
+                    data: &Vec<u8>)
+                    -> Result<(), ConfError> {
+
+        let file_name = match self.names.contains(&file_name.to_lowercase()) {
+            true => format!("{}.{}", file_name, block_id),
+            false => file_name,
+        };
+        self.names.insert(file_name.to_lowercase());
+
+        let path = Path::new(self.path_to_dir).join(format!("{}.bsl", file_name));
+        let path = file_system::path_to_str(path.as_path());
+
+        match file_system::write_file(&path, &normalize_text(data)) {
+            Ok(_) => (),
+            Err(e) => return Err(io_error(&path, e)),
+        }
+
+        self.count += 1;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{extract_modules, normalize_text};
+    use meta_data::types;
+    use structure::block::Block;
+
+    use file_system;
+    use std::env;
+    use std::path::Path;
+
+    #[test]
+    fn test_normalize_text() {
+        assert_eq!(b"a\nb\n".to_vec(),
+                   normalize_text(&b"\xEF\xBB\xBFa\r\nb\r\n".to_vec()));
+        assert_eq!(b"a\nb".to_vec(), normalize_text(&b"a\nb".to_vec()));
+    }
+
+    #[test]
+    fn test_extract_modules() {
+
+        let conf_id = "be22b29f-2db7-4fcb-8772-eeb5500d2170";
+        let catalog_id = "9d1a9f27-cd9f-488b-b5e1-c8b410fb7856";
+        let form_id = "31529189-9dfa-4dd5-ad66-1c93d70d83e1";
+
+        let root = format!("{{2,{},}}", conf_id);
+        let conf = format!("{{2,{{{{0,0,{}}},\"Конфигурация\"}},{{{},1,{}}}}}",
+                           conf_id,
+                           types::CATALOG,
+                           catalog_id);
+        let catalog = format!("{{1,{{{{0,0,{}}},\"Товары\"}},{{{},1,{}}}}}",
+                              catalog_id,
+                              types::FORMS_ID_CATALOG,
+                              form_id);
+        let form = format!("{{1,{{{{0,0,{}}},\"ФормаЭлемента\"}}}}", form_id);
+
+        let object_module = Block::from_parts(&format!("{}.0", catalog_id),
+                                              true,
+                                              vec![(String::from("info"), b"{3,1,0}".to_vec()),
+                                                   (String::from("text"),
+                                                    b"\xEF\xBB\xBF// object\r\n".to_vec())],
+                                              None);
+        let manager_module = Block::from_parts(&format!("{}.3", catalog_id),
+                                               true,
+                                               vec![(String::from("text"), b"// manager".to_vec())],
+                                               None);
+        let form_data = Block::from_parts(&format!("{}.0", form_id),
+                                          true,
+                                          vec![(String::from("form"), b"{}".to_vec()),
+                                               (String::from("module"), b"// form".to_vec())],
+                                          None);
+
+        let blocks = vec![Block::new("root", &root.into_bytes()),
+                          Block::new(conf_id, &conf.into_bytes()),
+                          Block::new(catalog_id, &catalog.into_bytes()),
+                          object_module,
+                          manager_module,
+                          Block::new(form_id, &form.into_bytes()),
+                          form_data];
+
+        let path_to_dir = env::temp_dir().join("conf_robber_test_extract_modules");
+        let path_to_dir = file_system::path_to_str(path_to_dir.as_path());
+        if file_system::exist(&path_to_dir) {
+            file_system::remove(&path_to_dir).unwrap();
+        }
+
+        assert_eq!(3, extract_modules(&blocks, &path_to_dir, 2).unwrap());
+
+        for &(file_name, text) in &[("Catalogs.Товары.ObjectModule.bsl", "// object\n"),
+                                    ("Catalogs.Товары.ManagerModule.bsl", "// manager"),
+                                    ("Catalogs.Товары.Forms.ФормаЭлемента.Module.bsl",
+                                     "// form")] {
+            let path = file_system::path_to_str(Path::new(&path_to_dir).join(file_name).as_path());
+            assert_eq!(text.as_bytes().to_vec(), file_system::read_file(&path).unwrap());
+        }
+
+        file_system::remove(&path_to_dir).unwrap();
+    }
+}
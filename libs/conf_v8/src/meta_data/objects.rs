@@ -0,0 +1,176 @@
+
+use meta_data::reader;
+use meta_data::external;
+use meta_data::types;
+use meta_data::description::Description;
+use meta_data::brace::{elements_ids, find_type_list, parse_data};
+use structure::block::Block;
+use error::ConfError;
+
+use std::collections::HashMap;
+
+// Внутренние типы, объекты которых хранятся в отдельных блоках
+const INTERNAL_TYPES_IDS: [&'static str; 4] = [types::FORMS_ID_DOC,
+                                               types::FORMS_ID_CATALOG,
+                                               types::LAYOUTS_ID,
+                                               types::COMMANDS_ID];
+
+// Объект метаданных конфигурации
+pub struct MetadataObject {
+    pub type_id: &'static str, // идентификатор типа
+    pub kind: &'static str, // имя каталога типа (Catalogs, Forms, ...)
+    pub name: String, // имя объекта (если имя не найдено - идентификатор блока)
+    pub id: String, // идентификатор блока с описанием объекта
+    pub objects: Vec<MetadataObject>, // подчиненные объекты (формы, макеты, команды)
+}
+
+// Дерево объектов метаданных конфигурации
+pub struct MetadataTree {
+    pub conf_id: String, // идентификатор блока с описанием конфигурации
+    pub conf_internal_id: String, // внутренний идентификатор конфигурации
+    pub objects: Vec<MetadataObject>, // объекты, упорядоченные по типам
+}
+
+/// Возвращает дерево объектов метаданных конфигурации.
+/// Типы упорядочиваются по имени каталога, объекты типа - в порядке описания конфигурации.
+pub fn metadata_tree(blocks: &Vec<Block>) -> Result<MetadataTree, ConfError> {
+
+    let conf_id = reader::main_conf_block_id(blocks)?;
+    let conf_data = reader::main_block_data(&conf_id, blocks)?;
+
+    let mut blocks_ids: HashMap<&str, &Block> = HashMap::new();
+    for block in blocks.iter() {
+        blocks_ids.insert(block.id(), block);
+    }
+
+    if let Some(type_id) = external::kind_by_data(&conf_data).type_id() {
+        return external_tree(blocks, &blocks_ids, conf_id, type_id);
+    }
+
+    let conf_desc = Description::new(&conf_id, &conf_data)?;
+    let conf_doc = parse_data(&conf_id, &conf_data)?;
+
+    let mut types_ids: Vec<(&'static str, &'static str)> = types::TYPES.iter()
+        .map(|x| (x.id, x.english_name))
+        .collect();
+    types_ids.sort_by(|a, b| a.1.cmp(b.1));
+
+    let mut objects: Vec<MetadataObject> = Vec::new();
+
+    for (type_id, type_dir) in types_ids {
+        let type_list = match find_type_list(&conf_doc.items, type_id) {
+            Some(v) => v,
+            None => {
+                warn!("Not found type '{}' in the description of the configuration.",
+                      type_dir);
+                continue;
+            }
+        };
+
+        for object_id in elements_ids(&type_list.items) {
+            if let Some(object) = metadata_object(&blocks_ids, &object_id, type_id, type_dir)? {
+                objects.push(object);
+            }
+        }
+    }
+
+    return Ok(MetadataTree {
+        conf_id: conf_id.clone(),
+        conf_internal_id: String::from(conf_desc.internal_id()),
+        objects: objects,
+    });
+}
+
+// Возвращает дерево внешней обработки или отчета: один объект с формами и макетами.
+// Основной блок выгружается как блок конфигурации, внутреннего идентификатора нет.
+fn external_tree(blocks: &Vec<Block>,
+                 blocks_ids: &HashMap<&str, &Block>,
+                 main_id: String,
+                 type_id: &'static str)
+                 -> Result<MetadataTree, ConfError> {
+
+    let external_object = external::read(blocks)?;
+    let type_dir = types::type_dir_name(type_id).unwrap_or("");
+
+    let mut objects: Vec<MetadataObject> = Vec::new();
+    for &(ids, internal_type_id) in &[(external_object.forms(), types::FORMS_ID_DOC),
+                                      (external_object.templates(), types::LAYOUTS_ID)] {
+        let internal_dir = types::type_dir_name(internal_type_id).unwrap_or("");
+        for id in ids.iter().map(|x| &x.0) {
+            if let Some(v) = metadata_object(blocks_ids, id, internal_type_id, internal_dir)? {
+                objects.push(v);
+            }
+        }
+    }
+
+    return Ok(MetadataTree {
+        conf_id: main_id,
+        conf_internal_id: String::new(),
+        objects: vec![MetadataObject {
+                          type_id: type_id,
+                          kind: type_dir,
+                          name: external_object.name().clone(),
+                          id: external_object.id().clone(),
+                          objects: objects,
+                      }],
+    });
+}
+
+// Возвращает объект метаданных вместе с подчиненными объектами.
+// Если блока с описанием объекта нет, то возвращается None.
+fn metadata_object(blocks_ids: &HashMap<&str, &Block>,
+                   object_id: &str,
+                   type_id: &'static str,
+                   type_dir: &'static str)
+                   -> Result<Option<MetadataObject>, ConfError> {
+
+    let block = match blocks_ids.get(object_id) {
+        Some(v) => *v,
+        None => return Ok(None),
+    };
+
+    let data = reader::simply_block_data(block)?;
+    let desc = match Description::new(block.id(), &data) {
+        Ok(v) => v,
+        Err(e) => {
+            warn!("{}", e);
+            return Ok(Some(MetadataObject {
+                type_id: type_id,
+                kind: type_dir,
+                name: String::from(object_id),
+                id: String::from(object_id),
+                objects: Vec::new(),
+            }));
+        }
+    };
+
+    let mut objects: Vec<MetadataObject> = Vec::new();
+
+    for internal_type_id in INTERNAL_TYPES_IDS.iter() {
+        let internal_ids = match desc.internal_types_ids(internal_type_id) {
+            Some(v) => v,
+            None => continue,
+        };
+        let internal_dir = match types::type_dir_name(internal_type_id) {
+            Some(v) => v,
+            None => continue,
+        };
+
+        for internal_id in internal_ids {
+            if let Some(v) = metadata_object(blocks_ids,
+                                             internal_id,
+                                             internal_type_id,
+                                             internal_dir)? {
+                objects.push(v);
+            }
+        }
+    }
+
+    return Ok(Some(MetadataObject {
+        type_id: type_id,
+        kind: type_dir,
+        name: String::from(desc.name()),
+        id: String::from(object_id),
+        objects: objects,
+    }));
+}
@@ -1,5 +1,3 @@
-use std::collections::HashMap;
-
 /// Идентификатор типа "Любая ссылка"
 pub const ANY_REF: &'static str = "280f5f0e-9c8a-49cc-bf6d-4d296cc17a63";
 
@@ -17,88 +15,237 @@ pub const TABULAR_SELECTIONS_ID: &'static str = "21c53e09-8950-4b5e-a6a0-1054f1b
 
 pub const DOCUMENT: &'static str = r"061d872a-5787-460e-95ac-ed74ea3a3e84";
 pub const CATALOG: &'static str = r"cf4abea6-37b2-11d4-940f-008048da11f9";
+pub const COMMON_MODULES: &'static str = r"0fe48980-252d-11d6-a3c7-0050bae0a776";
+pub const COMMON_FORMS: &'static str = r"07ee8426-87f1-11d5-b99c-0050bae0a95d";
+pub const COMMON_TEMPLATES: &'static str = r"0c89c792-16c3-11d5-b96b-0050bae0a95d";
+pub const SUBSYSTEMS: &'static str = r"37f2fa9a-b276-11d4-9435-004095e12fc7";
+pub const ENUM: &'static str = r"f6a80749-5ad7-400b-8519-39dc5dff2542";
+pub const INFORMATION_REGISTER: &'static str = r"13134201-f60b-11d5-a3c7-0050bae0a776";
+pub const ACCUMULATION_REGISTER: &'static str = r"b64d9a40-1642-11d6-a3c7-0050bae0a776";
+pub const ACCOUNTING_REGISTER: &'static str = r"2deed9b8-0056-4ffe-a473-c20a6c32a0bc";
+pub const CALCULATION_REGISTER: &'static str = r"f2de87a8-64e5-45eb-a22d-b3aedab050e7";
+pub const CHARTS_OF_ACCOUNTS: &'static str = r"238e7e88-3c5f-48b2-8a3b-81ebbecb20ed";
+pub const REPORTS: &'static str = r"631b75a0-29e2-11d6-a3c7-0050bae0a776";
+pub const DATA_PROCESSORS: &'static str = r"bf845118-327b-4682-b5c6-285d2a0eb296";
+
+/// Версия платформы: (8, 3, 5) - 8.3.5
+pub type Version = (u32, u32, u32);
+
+/// Описание типа объектов метаданных
+pub struct MetadataType {
+    pub id: &'static str, // идентификатор типа в описании конфигурации
+    pub name: &'static str, // имя коллекции объектов (Справочники)
+    pub english_name: &'static str, // имя коллекции объектов на английском (Catalogs) и имя каталога выгрузки
+    pub since: Version, // версия платформы, в которой появился тип
+    pub modules: &'static [(&'static str, &'static str)], /* модули объекта: номер блока с данными
+                                                           * объекта (<идентификатор>.<номер>) и вид модуля */
+}
+
+impl MetadataType {
+    /// Возвращает признак того, что тип доступен в версии платформы
+    pub fn is_available(&self, version: Version) -> bool {
+        self.since <= version
+    }
+
+    /// Возвращает имена коллекции, по которым тип указывается в настройках:
+    /// на русском, на английском и прежние имена
+    pub fn names(&self) -> Vec<&'static str> {
+        let mut retval = vec![self.name, self.english_name];
+        retval.extend(LEGACY_NAMES.iter().filter(|x| x.0.eq(self.id)).map(|x| x.1));
+
+        return retval;
+    }
+
+    /// Возвращает признак соответствия одному из имен коллекции
+    pub fn has_name(&self, name: &str) -> bool {
+        self.names().iter().any(|x| (*x).eq(name))
+    }
+
+    /// Возвращает вид модуля по номеру блока с данными объекта
+    pub fn module_kind(&self, suffix: &str) -> Option<&'static str> {
+        self.modules.iter().find(|x| x.0.eq(suffix)).map(|x| x.1)
+    }
+}
+
+const OBJECT_MODULES: &'static [(&'static str, &'static str)] = &[("0", "ObjectModule"),
+                                                                  ("2", "ManagerModule")];
+const RECORD_SET_MODULES: &'static [(&'static str, &'static str)] = &[("1", "RecordSetModule"),
+                                                                      ("2", "ManagerModule")];
+const MANAGER_MODULE: &'static [(&'static str, &'static str)] = &[("0", "ManagerModule")];
+const MODULE: &'static [(&'static str, &'static str)] = &[("0", "Module")];
+const NO_MODULES: &'static [(&'static str, &'static str)] = &[];
+
+// Прежние имена коллекций, которые поддерживаются в настройках
+const LEGACY_NAMES: &'static [(&'static str, &'static str)] =
+    &[("1c57eabe-7349-44b3-b1de-ebfeab67b47d", "ГруппыКоманды"),
+      ("cc9df798-7c94-4616-97d2-7aa0b7bc515e", "XDTO"),
+      ("bc587f20-35d9-11d6-a3c7-0050bae0a776", "ПоследовательностиДокументов"),
+      ("30b100d6-b29f-47ac-aec7-cb8ca8a54767", "ПланыВидовРасчетов"),
+      (ACCOUNTING_REGISTER, "РегистрыБухгалтерскогоУчета")];
+
+macro_rules! metadata_type {
+    ($id:expr, $name:expr, $english_name:expr, $since:expr, $modules:expr) => {
+        MetadataType {
+            id: $id,
+            name: $name,
+            english_name: $english_name,
+            since: $since,
+            modules: $modules,
+        }
+    }
+}
+
+/// Типы объектов метаданных конфигурации в порядке следования в конфигураторе.
+/// Типы, которых нет в описании конфигурации (например, появившиеся в более новой версии платформы),
+/// пропускаются при обработке.
+pub const TYPES: &'static [MetadataType] = &[
+    metadata_type!(SUBSYSTEMS, "Подсистемы", "Subsystems", (8, 0, 0), NO_MODULES),
+    metadata_type!(COMMON_MODULES, "ОбщиеМодули", "CommonModules", (8, 0, 0), MODULE),
+    metadata_type!("24c43748-c938-45d0-8d14-01424a72b11e", "ПараметрыСеанса", "SessionParameters",
+                   (8, 0, 0), NO_MODULES),
+    metadata_type!("09736b02-9cac-4e3f-b4f7-d3e9576ab948", "Роли", "Roles", (8, 0, 0), NO_MODULES),
+    metadata_type!("15794563-ccec-41f6-a83c-ec5f7b9a5bc1", "ОбщиеРеквизиты", "CommonAttributes",
+                   (8, 2, 14), NO_MODULES),
+    metadata_type!("857c4a91-e5f4-4fac-86ec-787626f1c108", "ПланыОбмена", "ExchangePlans",
+                   (8, 0, 0), OBJECT_MODULES),
+    metadata_type!("3e7bfcc0-067d-11d6-a3c7-0050bae0a776", "КритерииОтбора", "FilterCriteria",
+                   (8, 0, 0), MANAGER_MODULE),
+    metadata_type!("4e828da6-0f44-4b5b-b1c0-a2b3cfe7bdcc", "ПодпискиНаСобытия", "EventSubscriptions",
+                   (8, 1, 0), NO_MODULES),
+    metadata_type!("11bdaf85-d5ad-4d91-bb24-aa0eee139052", "РегламентныеЗадания", "ScheduledJobs",
+                   (8, 1, 0), NO_MODULES),
+    metadata_type!("af547940-3268-434f-a3e7-e47d6d2638c3", "ФункциональныеОпции", "FunctionalOptions",
+                   (8, 2, 0), NO_MODULES),
+    metadata_type!("30d554db-541e-4f62-8970-a1c6dcfeb2bc", "ПараметрыФункциональныхОпций",
+                   "FunctionalOptionsParameters", (8, 2, 0), NO_MODULES),
+    metadata_type!("c045099e-13b9-4fb6-9d50-fca00202971e", "ОпределяемыеТипы", "DefinedTypes",
+                   (8, 3, 3), NO_MODULES),
+    metadata_type!("46b4cd97-fd13-4eaa-aba2-3bddd7699218", "ХранилищаНастроек", "SettingsStorages",
+                   (8, 2, 0), MANAGER_MODULE),
+    metadata_type!(COMMON_FORMS, "ОбщиеФормы", "CommonForms", (8, 0, 0), NO_MODULES),
+    metadata_type!("2f1a5187-fb0e-4b05-9489-dc5dd6412348", "ОбщиеКоманды", "CommonCommands",
+                   (8, 2, 0), &[("2", "CommandModule")]),
+    metadata_type!("1c57eabe-7349-44b3-b1de-ebfeab67b47d", "ГруппыКоманд", "CommandGroups",
+                   (8, 2, 0), NO_MODULES),
+    metadata_type!("39bddf6a-0c3c-452b-921c-d99cfa1c2f1b", "Интерфейсы", "Interfaces", (8, 0, 0), NO_MODULES),
+    metadata_type!(COMMON_TEMPLATES, "ОбщиеМакеты", "CommonTemplates", (8, 0, 0), NO_MODULES),
+    metadata_type!("7dcd43d9-aca5-4926-b549-1842e6a4e8cf", "ОбщиеКартинки", "CommonPictures",
+                   (8, 0, 0), NO_MODULES),
+    metadata_type!("cc9df798-7c94-4616-97d2-7aa0b7bc515e", "XDTOПакеты", "XDTOPackages", (8, 1, 0), NO_MODULES),
+    metadata_type!("8657032e-7740-4e1d-a3ba-5dd6e8afb78f", "WebСервисы", "WebServices", (8, 1, 0), MODULE),
+    metadata_type!("0fffc09c-8f4c-47cc-b41c-8d5c5a221d79", "HTTPСервисы", "HTTPServices", (8, 3, 5), MODULE),
+    metadata_type!("d26096fb-7a5d-4df9-af63-47d04771fa9b", "WSСсылки", "WSReferences", (8, 1, 0), NO_MODULES),
+    metadata_type!("58848766-36ea-4076-8800-e91eb49590d7", "ЭлементыСтиля", "StyleItems", (8, 0, 0), NO_MODULES),
+    metadata_type!("3e5404af-6ef8-4c73-ad11-91bd2dfac4c8", "Стили", "Styles", (8, 0, 0), NO_MODULES),
+    metadata_type!("9cd510ce-abfc-11d4-9434-004095e12fc7", "Языки", "Languages", (8, 0, 0), NO_MODULES),
+    metadata_type!("0195e80c-b157-11d4-9435-004095e12fc7", "Константы", "Constants", (8, 0, 0),
+                   &[("0", "ValueManagerModule"), ("1", "ManagerModule")]),
+    metadata_type!(CATALOG, "Справочники", "Catalogs", (8, 0, 0),
+                   &[("0", "ObjectModule"), ("3", "ManagerModule")]),
+    metadata_type!(DOCUMENT, "Документы", "Documents", (8, 0, 0), OBJECT_MODULES),
+    metadata_type!("36a8e346-9aaa-4af9-bdbd-83be3c177977", "НумераторыДокументов", "DocumentNumerators",
+                   (8, 0, 0), NO_MODULES),
+    metadata_type!("bc587f20-35d9-11d6-a3c7-0050bae0a776", "Последовательности", "Sequences",
+                   (8, 0, 0), &[("1", "RecordSetModule")]),
+    metadata_type!("4612bd75-71b7-4a5c-8cc5-2b0b65f9fa0d", "ЖурналыДокументов", "DocumentJournals",
+                   (8, 0, 0), &[("2", "ManagerModule")]),
+    metadata_type!(ENUM, "Перечисления", "Enums", (8, 0, 0), MANAGER_MODULE),
+    metadata_type!(REPORTS, "Отчеты", "Reports", (8, 0, 0), OBJECT_MODULES),
+    metadata_type!(DATA_PROCESSORS, "Обработки", "DataProcessors", (8, 0, 0), OBJECT_MODULES),
+    metadata_type!("82a1b659-b220-4d94-a9bd-14d757b95a48", "ПланыВидовХарактеристик",
+                   "ChartsOfCharacteristicTypes", (8, 0, 0), OBJECT_MODULES),
+    metadata_type!(CHARTS_OF_ACCOUNTS, "ПланыСчетов", "ChartsOfAccounts", (8, 0, 0), OBJECT_MODULES),
+    metadata_type!("30b100d6-b29f-47ac-aec7-cb8ca8a54767", "ПланыВидовРасчета", "ChartsOfCalculationTypes",
+                   (8, 0, 0), OBJECT_MODULES),
+    metadata_type!(INFORMATION_REGISTER, "РегистрыСведений", "InformationRegisters",
+                   (8, 0, 0), RECORD_SET_MODULES),
+    metadata_type!(ACCUMULATION_REGISTER, "РегистрыНакопления", "AccumulationRegisters",
+                   (8, 0, 0), RECORD_SET_MODULES),
+    metadata_type!(ACCOUNTING_REGISTER, "РегистрыБухгалтерии", "AccountingRegisters",
+                   (8, 0, 0), RECORD_SET_MODULES),
+    metadata_type!(CALCULATION_REGISTER, "РегистрыРасчета", "CalculationRegisters",
+                   (8, 0, 0), RECORD_SET_MODULES),
+    metadata_type!("fcd3404e-1523-48ce-9bc0-ecdb822684a1", "БизнесПроцессы", "BusinessProcesses",
+                   (8, 0, 0), OBJECT_MODULES),
+    metadata_type!("3e63355c-1378-4953-be9b-1deb5fb6bec5", "Задачи", "Tasks", (8, 0, 0), OBJECT_MODULES),
+    metadata_type!("5274d9fc-9c3a-4a71-8f5e-a0db8ab23de5", "ВнешниеИсточникиДанных", "ExternalDataSources",
+                   (8, 2, 14), NO_MODULES),
+];
+
+/// Возвращает описание типа по идентификатору
+pub fn find_type(type_id: &str) -> Option<&'static MetadataType> {
+    TYPES.iter().find(|x| x.id.eq(type_id))
+}
+
+/// Возвращает описание типа по имени коллекции на русском или английском языке
+pub fn find_type_by_name(name: &str) -> Option<&'static MetadataType> {
+    TYPES.iter().find(|x| x.has_name(name))
+}
+
+/// Возвращает типы, доступные в версии платформы
+pub fn types_for_version(version: Version) -> Vec<&'static MetadataType> {
+    TYPES.iter().filter(|x| x.is_available(version)).collect()
+}
+
+/// Возвращает версию платформы, не ниже которой должна быть платформа для описания конфигурации
+/// с указанными типами: наибольшую из версий, в которых появились эти типы
+pub fn version_for_types(types_ids: &Vec<&str>) -> Version {
+    TYPES.iter()
+        .filter(|x| types_ids.contains(&x.id))
+        .map(|x| x.since)
+        .max()
+        .unwrap_or((8, 0, 0))
+}
+
+/// Возвращает имя каталога, в который выгружаются объекты типа
+pub fn type_dir_name(type_id: &str) -> Option<&'static str> {
+
+    let retval = match type_id {
+        FORMS_ID_DOC | FORMS_ID_CATALOG => "Forms",
+        LAYOUTS_ID => "Templates",
+        COMMANDS_ID => "Commands",
+        _ => return find_type(type_id).map(|x| x.english_name),
+    };
+
+    return Some(retval);
+}
+
+#[test]
+fn test_type_dir_name() {
+    for metadata_type in TYPES {
+        assert_eq!(Some(metadata_type.english_name), type_dir_name(metadata_type.id));
+    }
+
+    assert_eq!(Some("Forms"), type_dir_name(FORMS_ID_CATALOG));
+    assert_eq!(None, type_dir_name(EMPTY_REF));
+}
+
+#[test]
+fn test_types() {
+    let mut ids: Vec<&str> = TYPES.iter().map(|x| x.id).collect();
+    ids.sort();
+    ids.dedup();
+    assert_eq!(TYPES.len(), ids.len());
+
+    assert_eq!(Some(CATALOG), find_type_by_name("Catalogs").map(|x| x.id));
+    assert_eq!(Some(CATALOG), find_type_by_name("Справочники").map(|x| x.id));
+    assert_eq!(Some("ЖурналыДокументов"),
+               find_type("4612bd75-71b7-4a5c-8cc5-2b0b65f9fa0d").map(|x| x.name));
+    assert_eq!(Some("РегистрыРасчета"), find_type(CALCULATION_REGISTER).map(|x| x.name));
+    assert!(find_type_by_name("Жуналы").is_none());
+    assert_eq!(Some(ACCOUNTING_REGISTER),
+               find_type_by_name("РегистрыБухгалтерскогоУчета").map(|x| x.id));
+
+    assert_eq!(Some("ManagerModule"), find_type(CATALOG).unwrap().module_kind("3"));
+    assert_eq!(None, find_type(CATALOG).unwrap().module_kind("2"));
+
+    let old_types = types_for_version((8, 2, 19));
+    assert!(old_types.iter().any(|x| x.id == CATALOG));
+    assert!(old_types.iter().all(|x| x.english_name != "HTTPServices" && x.english_name != "DefinedTypes"));
+    assert_eq!(TYPES.len(), types_for_version((8, 3, 10)).len());
 
-/// Возвращает коллекцию идентификаторов типов и их наименования
-pub fn get_types() -> HashMap<&'static str, &'static str> {
-
-    let mut retval: HashMap<&'static str, &'static str> = HashMap::new();
-
-    retval.insert(r"09736b02-9cac-4e3f-b4f7-d3e9576ab948", r"Роли");
-    retval.insert(r"0c89c792-16c3-11d5-b96b-0050bae0a95d",
-                  r"ОбщиеМакеты");
-    retval.insert(r"0fe48980-252d-11d6-a3c7-0050bae0a776",
-                  r"ОбщиеМодули");
-    retval.insert(r"11bdaf85-d5ad-4d91-bb24-aa0eee139052",
-                  r"РегламентныеЗадания");
-    retval.insert(r"15794563-ccec-41f6-a83c-ec5f7b9a5bc1",
-                  r"ОбщиеРеквизиты");
-    retval.insert(r"24c43748-c938-45d0-8d14-01424a72b11e",
-                  r"ПараметрыСеанса");
-    retval.insert(r"30d554db-541e-4f62-8970-a1c6dcfeb2bc",
-                  r"ПараметрыФункциональныхОпций");
-    retval.insert(r"37f2fa9a-b276-11d4-9435-004095e12fc7",
-                  r"Подсистемы");
-    retval.insert(r"39bddf6a-0c3c-452b-921c-d99cfa1c2f1b",
-                  r"Интерфейсы");
-    retval.insert(r"3e5404af-6ef8-4c73-ad11-91bd2dfac4c8", r"Стили");
-    retval.insert(r"c045099e-13b9-4fb6-9d50-fca00202971e",
-                  r"ОпределяемыеТипы");
-    retval.insert(r"3e7bfcc0-067d-11d6-a3c7-0050bae0a776",
-                  r"КритерииОтбора");
-    retval.insert(r"46b4cd97-fd13-4eaa-aba2-3bddd7699218",
-                  r"ХранилищаНастроек");
-    retval.insert(r"4e828da6-0f44-4b5b-b1c0-a2b3cfe7bdcc",
-                  r"ПодпискиНаСобытия");
-    retval.insert(r"58848766-36ea-4076-8800-e91eb49590d7",
-                  r"ЭлементыСтиля");
-    retval.insert(r"7dcd43d9-aca5-4926-b549-1842e6a4e8cf",
-                  r"ОбщиеКартинки");
-    retval.insert(r"857c4a91-e5f4-4fac-86ec-787626f1c108",
-                  r"ПланыОбмена");
-    retval.insert(r"8657032e-7740-4e1d-a3ba-5dd6e8afb78f",
-                  r"WebСервисы");
-    retval.insert(r"9cd510ce-abfc-11d4-9434-004095e12fc7", r"Языки");
-    retval.insert(r"af547940-3268-434f-a3e7-e47d6d2638c3",
-                  r"ФункциональныеОпции");
-    retval.insert(r"cc9df798-7c94-4616-97d2-7aa0b7bc515e", r"XDTO");
-    retval.insert(r"d26096fb-7a5d-4df9-af63-47d04771fa9b", r"WSСсылки");
-    retval.insert(r"0195e80c-b157-11d4-9435-004095e12fc7",
-                  r"Константы");
-    retval.insert(DOCUMENT, r"Документы");
-    retval.insert(r"07ee8426-87f1-11d5-b99c-0050bae0a95d",
-                  r"ОбщиеФормы");
-    retval.insert(r"13134201-f60b-11d5-a3c7-0050bae0a776",
-                  r"РегистрыСведений");
-    retval.insert(r"1c57eabe-7349-44b3-b1de-ebfeab67b47d",
-                  r"ГруппыКоманды");
-    retval.insert(r"2f1a5187-fb0e-4b05-9489-dc5dd6412348",
-                  r"ОбщиеКоманды");
-    retval.insert(r"36a8e346-9aaa-4af9-bdbd-83be3c177977",
-                  r"НумераторыДокументов");
-    retval.insert(r"4612bd75-71b7-4a5c-8cc5-2b0b65f9fa0d", r"Жуналы");
-    retval.insert(r"631b75a0-29e2-11d6-a3c7-0050bae0a776", r"Отчеты");
-    retval.insert(r"82a1b659-b220-4d94-a9bd-14d757b95a48",
-                  r"ПланыВидовХарактеристик");
-    retval.insert(r"b64d9a40-1642-11d6-a3c7-0050bae0a776",
-                  r"РегистрыНакопления");
-    retval.insert(r"bc587f20-35d9-11d6-a3c7-0050bae0a776",
-                  r"ПоследовательностиДокументов");
-    retval.insert(r"bf845118-327b-4682-b5c6-285d2a0eb296",
-                  r"Обработки");
-    retval.insert(CATALOG, r"Справочники");
-    retval.insert(r"f6a80749-5ad7-400b-8519-39dc5dff2542",
-                  r"Перечисления");
-    retval.insert(r"30b100d6-b29f-47ac-aec7-cb8ca8a54767",
-                  r"ПланыВидовРасчетов");
-    retval.insert(r"2deed9b8-0056-4ffe-a473-c20a6c32a0bc",
-                  r"РегистрыБухгалтерскогоУчета");
-    retval.insert(r"238e7e88-3c5f-48b2-8a3b-81ebbecb20ed",
-                  r"РегистрыРачета");
-    retval.insert(r"fcd3404e-1523-48ce-9bc0-ecdb822684a1",
-                  r"БизнесПроцессы");
-    retval.insert(r"3e63355c-1378-4953-be9b-1deb5fb6bec5", r"Задачи");
-    retval.insert(r"5274d9fc-9c3a-4a71-8f5e-a0db8ab23de5",
-                  r"ВнешниеИсточникиДанных");
-
-    return retval;
+    let http_services = find_type_by_name("HTTPServices").unwrap();
+    assert_eq!((8, 0, 0), version_for_types(&Vec::new()));
+    assert_eq!(find_type(CATALOG).unwrap().since, version_for_types(&vec![CATALOG]));
+    assert_eq!(http_services.since, version_for_types(&vec![CATALOG, http_services.id]));
 }
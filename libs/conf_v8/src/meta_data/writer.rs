@@ -4,20 +4,277 @@ use meta_data;
 use settings;
 use structure::block::Block;
 use meta_data::description::Description;
+use meta_data::diff::json_string;
+use meta_data::modules::is_module_block;
+use meta_data::brace::{Document, List, Node, Value, element_id_and_name, elements_ids, find_type_list,
+                       find_type_list_mut, guids, parse_data, replace_guids};
 use aho_corasick::{Automaton, AcAutomaton};
+use error::{ConfError, metadata_error};
+
+use std::collections::HashMap;
+
+/// Типы объектов метаданных, которые оставляются в конфигурации, если на них ссылаются
+/// отобранные объекты
+const DEPENDENCY_TYPES: [&'static str; 7] = [meta_data::types::CATALOG,
+                                             meta_data::types::ENUM,
+                                             meta_data::types::INFORMATION_REGISTER,
+                                             meta_data::types::ACCUMULATION_REGISTER,
+                                             meta_data::types::ACCOUNTING_REGISTER,
+                                             meta_data::types::CALCULATION_REGISTER,
+                                             meta_data::types::COMMON_MODULES];
+
+/// Объект метаданных, оставленный в конфигурации, т.к. на него ссылается отобранный объект
+#[derive(Clone, PartialEq, Debug)]
+pub struct Dependency {
+    pub type_name: String, // наименование типа объекта
+    pub name: String, // имя объекта
+    pub required_by: String, // полное имя объекта (<Тип>.<Имя>), который ссылается на объект
+}
+
+impl Dependency {
+    /// Возвращает описание зависимости: <Тип>.<Имя> <- <Тип>.<Имя>
+    pub fn description(&self) -> String {
+        format!("{}.{} <- {}", self.type_name, self.name, self.required_by)
+    }
+}
+
+/// Ссылка, которая будет заменена при фильтрации
+#[derive(Clone, PartialEq, Debug)]
+pub struct ReplacedReference {
+    pub object: String, // полное имя удаляемого объекта, на который указывает ссылка
+    pub replacement: &'static str, // идентификатор типа, на который заменяется ссылка
+    pub used_in: Vec<String>, // объекты (или идентификаторы блоков), в которых заменяется ссылка
+}
+
+/// Отчет о фильтрации: результат отбора объектов без изменения конфигурации
+#[derive(Clone, PartialEq, Debug)]
+pub struct FilterReport {
+    pub kept: Vec<String>, // оставляемые объекты: <Тип>.<Имя>
+    pub removed: Vec<String>, // удаляемые объекты
+    pub removed_internal: Vec<String>, // удаляемые формы, макеты, команды: <Тип>.<Имя>.<Вид>.<Имя>
+    pub removed_modules: Vec<String>, // удаляемые модули: <Тип>.<Имя>.<Вид модуля>
+    pub distributive: Vec<String>, // удаляемые блоки файла поставки
+    pub dependencies: Vec<Dependency>, // объекты, оставленные как зависимости
+    pub references: Vec<ReplacedReference>, // заменяемые ссылки
+}
+
+impl FilterReport {
+    /// Возвращает отчет в текстовом виде
+    pub fn to_text(&self) -> String {
+
+        let mut text = String::new();
+
+        let sections: [(&str, &Vec<String>); 5] = [("kept", &self.kept),
+                                                   ("removed", &self.removed),
+                                                   ("removed internal objects", &self.removed_internal),
+                                                   ("removed modules", &self.removed_modules),
+                                                   ("removed distributive blocks", &self.distributive)];
+        for &(title, items) in sections.iter() {
+            text.push_str(&format!("{}: {}\n", title, items.len()));
+            for item in items {
+                text.push_str(&format!("    {}\n", item));
+            }
+        }
+
+        text.push_str(&format!("dependencies: {}\n", self.dependencies.len()));
+        for item in &self.dependencies {
+            text.push_str(&format!("    {}\n", item.description()));
+        }
+
+        text.push_str(&format!("replaced references: {}\n", self.references.len()));
+        for item in &self.references {
+            text.push_str(&format!("    {} -> {}\n", item.object, item.replacement));
+            for used_in in &item.used_in {
+                text.push_str(&format!("        {}\n", used_in));
+            }
+        }
+
+        return text;
+    }
+
+    /// Возвращает отчет в формате JSON
+    pub fn to_json(&self) -> String {
 
-/// Выполняет фильтрацию блоков, которые необходимы, остальные блоки удаляются
-pub fn filter(blocks: &mut Vec<Block>, settings: &settings::Settings) {
+        let strings = |items: &Vec<String>| {
+            items.iter().map(|x| json_string(x)).collect::<Vec<String>>().join(",")
+        };
+
+        let dependencies: Vec<String> = self.dependencies
+            .iter()
+            .map(|x| {
+                format!("{{\"type\":{},\"name\":{},\"required_by\":{}}}",
+                        json_string(&x.type_name),
+                        json_string(&x.name),
+                        json_string(&x.required_by))
+            })
+            .collect();
+
+        let references: Vec<String> = self.references
+            .iter()
+            .map(|x| {
+                format!("{{\"object\":{},\"replacement\":{},\"used_in\":[{}]}}",
+                        json_string(&x.object),
+                        json_string(x.replacement),
+                        strings(&x.used_in))
+            })
+            .collect();
+
+        return format!("{{\"kept\":[{}],\"removed\":[{}],\"removed_internal\":[{}],\
+                        \"removed_modules\":[{}],\"distributive\":[{}],\"dependencies\":[{}],\
+                        \"references\":[{}]}}\n",
+                       strings(&self.kept),
+                       strings(&self.removed),
+                       strings(&self.removed_internal),
+                       strings(&self.removed_modules),
+                       strings(&self.distributive),
+                       dependencies.join(","),
+                       references.join(","));
+    }
+}
+
+/// Объект метаданных конфигурации и настройки его отбора
+struct FilterObject {
+    type_id: &'static str,
+    type_name: &'static str,
+    block: usize, // индекс блока с описанием объекта
+    data: Vec<u8>,
+    desc: Description,
+    filtr: Option<settings::metadata::Metadata>, // None - объект удаляется
+}
+
+impl FilterObject {
+    fn full_name(&self) -> String {
+        format!("{}.{}", self.type_name, self.desc.name())
+    }
+}
+
+/// Результат отбора объектов метаданных: изменения, которые вносятся в конфигурацию
+struct Selection {
+    conf_id: String,
+    conf_doc: Document, // описание конфигурации с обновленными списками объектов
+    objects: Vec<FilterObject>,
+    dependencies: Vec<Dependency>,
+    force_blocks_ids: Vec<String>, // префиксы идентификаторов оставляемых блоков
+    except_blocks_ids: Vec<String>, // идентификаторы удаляемых блоков
+    distributive_ids: Vec<String>, // идентификаторы блоков файла поставки
+    except_internal: Vec<(usize, &'static str, String)>, // объект, внутренний тип, идентификатор
+    except_modules: Vec<(usize, &'static str)>, // объект, вид модуля
+    deleted_ref_ids: Vec<String>, // ссылки на удаляемые объекты
+    updated: Vec<(usize, Vec<u8>)>, // индекс блока и новое описание объекта
+}
+
+impl Selection {
+    // Проверить, что блок остается в конфигурации
+    fn is_kept(&self, block_id: &String) -> bool {
+        self.force_blocks_ids.iter().find(|y| block_id.starts_with(&*(*y))).is_some() &&
+        self.except_blocks_ids.iter().find(|y| block_id.eq(&*(*y))).is_none()
+    }
+}
+
+/// Выполняет фильтрацию блоков, которые необходимы, остальные блоки удаляются.
+/// Возвращает объекты, которые оставлены, т.к. на них ссылаются отобранные объекты.
+pub fn filter(blocks: &mut Vec<Block>,
+              settings: &settings::Settings)
+              -> Result<Vec<Dependency>, ConfError> {
 
     info!("Filtrating of blocks");
 
-    let conf_id = meta_data::reader::main_conf_block_id(blocks);
-    let mut conf_data = meta_data::reader::main_block_data(&conf_id, blocks);
-    let conf_desc = Description::new(&conf_id, &conf_data);
+    let selection = select(blocks, settings)?;
+
+    for &(i, ref data) in &selection.updated {
+        blocks[i].set_data(&blocks[i].id(), data)?;
+    }
+
+    // Удаляем блоки которые не прошли филтр
+    blocks.retain(|x| selection.is_kept(x.id()));
+
+    // Обновляем описание конфигурации
+    let conf_id = &selection.conf_id;
+    let conf_block = match meta_data::reader::block_by_name(blocks, conf_id) {
+        Some(v) => v,
+        None => return Err(metadata_error(conf_id, "Block of configuration is deleted.")),
+    };
+    conf_block.set_data(conf_id, &selection.conf_doc.to_bytes())?;
+
+    remove_references_deleted_blocks(&blocks, selection.deleted_ref_ids)?;
+
+    info!("-Filtrating of blocks");
+
+    Ok(selection.dependencies)
+}
+
+/// Выполняет отбор объектов так же, как фильтрация, но не изменяет блоки, а возвращает
+/// отчет об оставляемых и удаляемых объектах и заменяемых ссылках
+pub fn filter_report(blocks: &Vec<Block>,
+                     settings: &settings::Settings)
+                     -> Result<FilterReport, ConfError> {
+
+    info!("Report of filtration");
+
+    let selection = select(blocks, settings)?;
+    let objects = &selection.objects;
+
+    let mut report = FilterReport {
+        kept: Vec::new(),
+        removed: Vec::new(),
+        removed_internal: Vec::new(),
+        removed_modules: Vec::new(),
+        distributive: Vec::new(),
+        dependencies: selection.dependencies.clone(),
+        references: Vec::new(),
+    };
+
+    for object in objects {
+        match object.filtr {
+            Some(_) => report.kept.push(object.full_name()),
+            None => report.removed.push(object.full_name()),
+        }
+    }
+
+    for &(i, type_id, ref id) in &selection.except_internal {
+        let name = internal_object_name(blocks, &objects[i].desc, id)?.unwrap_or_else(|| id.clone());
+        let internal_name = format!("{}.{}.{}",
+                                    objects[i].full_name(),
+                                    nested_kind_name(type_id),
+                                    name);
+
+        report.references.push(ReplacedReference {
+            object: internal_name.clone(),
+            replacement: meta_data::types::EMPTY_REF,
+            used_in: vec![objects[i].full_name()],
+        });
+        report.removed_internal.push(internal_name);
+    }
+
+    for &(i, module) in &selection.except_modules {
+        report.removed_modules.push(format!("{}.{}", objects[i].full_name(), module));
+    }
+
+    report.distributive = blocks.iter()
+        .map(|x| x.id())
+        .filter(|x| selection.distributive_ids.contains(x))
+        .cloned()
+        .collect();
+
+    report.references.extend(find_references(blocks, &selection)?);
+
+    info!("-Report of filtration");
+
+    Ok(report)
+}
+
+/// Отбирает объекты метаданных по настройкам и определяет изменения конфигурации
+fn select(blocks: &Vec<Block>, settings: &settings::Settings) -> Result<Selection, ConfError> {
+
+    let conf_id = meta_data::reader::main_conf_block_id(blocks)?;
+    let conf_data = meta_data::reader::main_block_data(&conf_id, blocks)?;
+    let conf_desc = Description::new(&conf_id, &conf_data)?;
+    let mut conf_doc = parse_data(&conf_id, &conf_data)?;
 
     // Исключаем идентификаторы файла поставки, т.к. конфигурация уже получается не полной,
     // то эта информация уже является не актуальной и должна быть удалена
-    let mut except_blocks_ids = distributive_ids(&blocks, &conf_desc);
+    let distributive_ids = distributive_ids(&blocks, &conf_desc)?;
+    let mut except_blocks_ids = distributive_ids.clone();
 
     let mut force_blocks_ids =
         vec!["root", "version", "versions", &*conf_id, conf_desc.internal_id()]
@@ -25,97 +282,501 @@ pub fn filter(blocks: &mut Vec<Block>, settings: &settings::Settings) {
             .map(|x| String::from(*x))
             .collect::<Vec<String>>();
 
-    const INTERNAL_TYPES_IDS: [&'static str; 5] = [meta_data::types::FORMS_ID_DOC,
+    const INTERNAL_TYPES_IDS: [&'static str; 6] = [meta_data::types::FORMS_ID_DOC,
                                                    meta_data::types::FORMS_ID_CATALOG,
                                                    meta_data::types::PROPS_ID,
                                                    meta_data::types::COMMANDS_ID,
-                                                   meta_data::types::LAYOUTS_ID];
+                                                   meta_data::types::LAYOUTS_ID,
+                                                   meta_data::types::TABULAR_SELECTIONS_ID];
 
     let mut deleted_ref_ids = <Vec<String>>::new();
+    let mut except_internal: Vec<(usize, &'static str, String)> = Vec::new();
+    let mut except_modules: Vec<(usize, &'static str)> = Vec::new();
+    let mut updated: Vec<(usize, Vec<u8>)> = Vec::new();
 
-    let catalog_id: String = String::from(meta_data::types::CATALOG);
-    let document_id: String = String::from(meta_data::types::DOCUMENT.to_string());
+    let mut objects = read_objects(blocks, &conf_doc, settings)?;
+    select_subsystems(blocks, &mut objects, settings)?;
+    let mut dependencies = include_dependencies(&mut objects, settings)?;
 
-    for (type_id, type_name) in meta_data::types::get_types() {
-        let coordinates = find_type_coordinates(type_id, type_name, &conf_data);
-        let mut obj_ids = metadata_blocks_ids(&conf_data, &coordinates);
-        let type_filter = find_type_filter(type_name, &settings);
+    // В расширении заимствованные объекты, на которые ссылаются отобранные, не удаляются
+    if conf_desc.borrowed_id().is_some() {
+        dependencies.extend(include_borrowed(&mut objects, settings)?);
+    }
 
-        // [3] Определяем идентификаторы блоков, которые соответствуют типу и заданным фильтрам
-        for item in blocks.iter() {
-            if obj_ids.iter().find(|x| (*x).eq(&*item.id())).is_none() {
-                continue;
-            }
+    for metadata_type in meta_data::types::TYPES {
+        let type_id = metadata_type.id;
+        let mut obj_ids = match metadata_blocks_ids(type_id, &conf_doc) {
+            Some(v) => v,
+            None => continue,
+        };
 
-            let mut data = meta_data::reader::simply_block_data(item);
-            let desc = Description::new(item.id(), &data);
-            let filtr = check_object_name(&type_filter, desc.name().to_string());
+        // [3] Обрабатываем блоки, которые соответствуют типу и заданным фильтрам
+        for (i, object) in objects.iter().enumerate().filter(|&(_, x)| x.type_id == type_id) {
+            let item = &blocks[object.block];
+            let desc = &object.desc;
 
-            if filtr.is_none() {
-                // Имя объекта метаданных не найдено в списке нужных, следовательно удаляем его идентификатор
-                obj_ids.retain(|x| x.ne(&*item.id()));
+            let filtr = match object.filtr {
+                Some(ref v) => v,
+                None => {
+                    // Имя объекта метаданных не найдено в списке нужных, следовательно удаляем его идентификатор
+                    obj_ids.retain(|x| x.ne(&*item.id()));
 
-                // Записываем идентификаторы внешних ссылок, чтобы удалить из оставшихся объектов
-                deleted_ref_ids.extend_from_slice(&desc.reference_ids()[..]);
-                continue;
-            }
-
-            let filtr = filtr.unwrap();
+                    // Записываем идентификаторы внешних ссылок, чтобы удалить из оставшихся объектов
+                    deleted_ref_ids.extend_from_slice(&desc.reference_ids()[..]);
+                    continue;
+                }
+            };
 
             // Если объект не основной, то удаляем модули объекта и менеджера
             if !filtr.main() {
-                if catalog_id.eq(type_id) {
-                    except_blocks_ids.push(format!("{}.0", item.id())); // модуль объекта
-                    except_blocks_ids.push(format!("{}.3", item.id())); // модуль менеджера справочника
-                } else if document_id.eq(type_id) {
-                    except_blocks_ids.push(format!("{}.0", item.id())); // модуль объекта
-                    except_blocks_ids.push(format!("{}.2", item.id())); // модуль менеджера документа
+                for &(suffix, module) in metadata_type.modules {
+                    let module_id = format!("{}.{}", item.id(), suffix);
+                    if is_module(blocks, &module_id)? {
+                        except_modules.push((i, module));
+                        except_blocks_ids.push(module_id);
+                    }
                 }
             }
 
             // Отбираем блоки внутренних типов
+            let mut doc: Option<Document> = None;
+
             for internal_type_id in INTERNAL_TYPES_IDS.iter() {
                 let internal_ids = internal_objects_ids(internal_type_id, &desc);
 
                 let (force_internal_ids, except_internal_ids) =
-                    filtr_internal_ids(&filtr, internal_type_id, &internal_ids, &blocks);
+                    filtr_internal_ids(&filtr, internal_type_id, &internal_ids, &desc, &blocks)?;
 
                 force_blocks_ids.extend_from_slice(&force_internal_ids[..]);
                 except_blocks_ids.extend_from_slice(&except_internal_ids[..]);
+                except_internal.extend(except_internal_ids.iter().map(|x| (i, *internal_type_id, x.clone())));
 
                 if !except_internal_ids.is_empty() {
-                    update_internal_ids(&mut data,
-                                        internal_type_id,
-                                        force_internal_ids,
-                                        except_internal_ids);
+                    if doc.is_none() {
+                        doc = Some(parse_data(item.id(), &object.data)?);
+                    }
 
-                    item.set_data(&item.id(), &data);
+                    update_internal_ids(doc.as_mut().unwrap(),
+                                        item.id(),
+                                        internal_type_id,
+                                        except_internal_ids)?;
                 }
             }
 
+            if let Some(doc) = doc {
+                updated.push((object.block, doc.to_bytes()));
+            }
+
+            // Вложенные подсистемы хранятся в отдельных блоках и не указаны в описании конфигурации
+            if type_id == meta_data::types::SUBSYSTEMS {
+                force_blocks_ids.extend(nested_subsystems(blocks, item.id(), &object.data)?);
+            }
+
             force_blocks_ids.push(item.id().clone());
         }
 
-        replace_bytes!(conf_data, coordinates, type_desc(type_id, &obj_ids));
+        set_type_desc(&mut conf_doc.items, type_id, &obj_ids);
     }
 
-    // Удаляем блоки которые не прошли филтр
-    blocks.retain(|x| {
-        force_blocks_ids.iter().find(|y| (*x).id().starts_with(&*(*y))).is_some() &&
-        except_blocks_ids.iter().find(|y| (*x).id().eq(&*(*y))).is_none()
-    });
+    Ok(Selection {
+        conf_id: conf_id,
+        conf_doc: conf_doc,
+        objects: objects,
+        dependencies: dependencies,
+        force_blocks_ids: force_blocks_ids,
+        except_blocks_ids: except_blocks_ids,
+        distributive_ids: distributive_ids,
+        except_internal: except_internal,
+        except_modules: except_modules,
+        deleted_ref_ids: deleted_ref_ids,
+        updated: updated,
+    })
+}
 
-    // Обновляем описание конфигурации
-    let conf_block = meta_data::reader::block_by_name(blocks, &conf_id).unwrap();
-    conf_block.set_data(&conf_id, &conf_data);
+/// Возвращает признак блока с текстом модуля. Блоки с другими данными объекта не удаляются,
+/// даже если их номер указан в описании модулей типа.
+fn is_module(blocks: &Vec<Block>, block_id: &String) -> Result<bool, ConfError> {
+    let block = match meta_data::reader::block_by_name(blocks, block_id) {
+        Some(v) => v,
+        None => return Ok(false),
+    };
 
-    remove_references_deleted_blocks(&blocks, deleted_ref_ids);
+    let nested_blocks = match block.get_data() {
+        Ok(v) => v,
+        Err(_) => return Ok(false),
+    };
 
-    info!("-Filtrating of blocks");
+    Ok(nested_blocks.iter().any(|x| is_module_block(x.attrs.id())))
+}
+
+/// Возвращает признак исключения объекта в настройках по любому из имен его типа
+fn is_excluded(settings: &settings::Settings, object: &FilterObject) -> bool {
+    let type_names = match meta_data::types::find_type(object.type_id) {
+        Some(v) => v.names(),
+        None => vec![object.type_name],
+    };
+
+    type_names.iter().any(|x| settings.is_excluded(x, object.desc.name()))
+}
+
+/// Поиск ссылок на удаляемые объекты в оставляемых блоках (ссылки заменяются на тип "Любая ссылка")
+fn find_references(blocks: &Vec<Block>,
+                   selection: &Selection)
+                   -> Result<Vec<ReplacedReference>, ConfError> {
+
+    let mut ref_ids: Vec<String> = Vec::new();
+    let mut ref_objects: Vec<usize> = Vec::new();
+    for (i, object) in selection.objects.iter().enumerate() {
+        if object.filtr.is_some() {
+            continue;
+        }
+        for id in object.desc.reference_ids() {
+            ref_ids.push(id.clone());
+            ref_objects.push(i);
+        }
+    }
+
+    // Имена оставляемых объектов по идентификаторам блоков
+    let names: HashMap<&str, String> = selection.objects
+        .iter()
+        .filter(|x| x.filtr.is_some())
+        .map(|x| (x.desc.block_id(), x.full_name()))
+        .collect();
+
+    let aut = AcAutomaton::new(ref_ids);
+    let mut used_in: Vec<Vec<String>> = vec![Vec::new(); selection.objects.len()];
+
+    for item in blocks.iter() {
+        if !selection.is_kept(item.id()) {
+            continue;
+        }
+
+        let object_id = item.id().split('.').next().unwrap_or("");
+        let name = match names.get(object_id) {
+            Some(v) => v.clone(),
+            None => item.id().clone(),
+        };
+
+        for nested_block in item.get_data()? {
+            for m in aut.find(&nested_block.data[..]) {
+                let objects = &mut used_in[ref_objects[m.pati]];
+                if !objects.contains(&name) {
+                    objects.push(name.clone());
+                }
+            }
+        }
+    }
+
+    let retval = used_in.into_iter()
+        .enumerate()
+        .filter(|x| !x.1.is_empty())
+        .map(|(i, x)| {
+            ReplacedReference {
+                object: selection.objects[i].full_name(),
+                replacement: meta_data::types::ANY_REF,
+                used_in: x,
+            }
+        })
+        .collect();
+
+    Ok(retval)
+}
+
+/// Читает описания объектов метаданных всех типов и определяет настройки их отбора
+fn read_objects(blocks: &Vec<Block>,
+                conf_doc: &Document,
+                settings: &settings::Settings)
+                -> Result<Vec<FilterObject>, ConfError> {
+
+    let mut retval: Vec<FilterObject> = Vec::new();
+
+    // Версия платформы конфигурации определяется по типам, списки объектов которых есть
+    // в описании конфигурации. Типы более новых версий платформы пропускаются.
+    let types_ids: Vec<&str> = meta_data::types::TYPES.iter()
+        .map(|x| x.id)
+        .filter(|x| find_type_list(&conf_doc.items, x).is_some())
+        .collect();
+    let version = meta_data::types::version_for_types(&types_ids);
+
+    for type_name in settings.metadata_type_names() {
+        match meta_data::types::find_type_by_name(type_name) {
+            None => warn!("Unknown type of metadata objects in the settings: '{}'", type_name),
+            Some(v) if !v.is_available(version) => {
+                warn!("Type of metadata objects '{}' in the settings is available since the platform \
+                       {}.{}.{}, but the configuration is for the platform {}.{}.{}",
+                      type_name,
+                      v.since.0,
+                      v.since.1,
+                      v.since.2,
+                      version.0,
+                      version.1,
+                      version.2)
+            }
+            Some(_) => (),
+        }
+    }
+
+    for metadata_type in meta_data::types::types_for_version(version) {
+        let obj_ids = match metadata_blocks_ids(metadata_type.id, conf_doc) {
+            Some(v) => v,
+            None => {
+                debug!("Not found type '{}' in the description of the configuration.",
+                       metadata_type.name);
+                continue;
+            }
+        };
+        let type_names = metadata_type.names();
+        let type_filter = find_type_filter(&type_names, &settings);
+
+        for (i, item) in blocks.iter().enumerate() {
+            if obj_ids.iter().find(|x| (*x).eq(&*item.id())).is_none() {
+                continue;
+            }
+
+            let data = meta_data::reader::simply_block_data(item)?;
+            let desc = Description::new(item.id(), &data)?;
+            // Исключения имеют приоритет над отбором
+            let filtr = match type_names.iter().any(|x| settings.is_excluded(x, desc.name())) {
+                true => None,
+                false => check_object_name(&type_filter, desc.name().to_string()).cloned(),
+            };
+
+            retval.push(FilterObject {
+                type_id: metadata_type.id,
+                type_name: metadata_type.name,
+                block: i,
+                data: data,
+                desc: desc,
+                filtr: filtr,
+            });
+        }
+    }
+
+    Ok(retval)
+}
+
+/// Отбирает объекты, которые входят в подсистемы, указанные в настройках (<subsystem name="..."/>),
+/// так же, как если бы они были указаны по отдельности, с признаками отбора подсистемы.
+/// В подсистему входят сама подсистема, объекты ее состава и объекты вложенных подсистем.
+fn select_subsystems(blocks: &Vec<Block>,
+                     objects: &mut [FilterObject],
+                     settings: &settings::Settings)
+                     -> Result<(), ConfError> {
+
+    let selections = settings.subsystem_selections();
+    if selections.is_empty() {
+        return Ok(());
+    }
+
+    info!("Selecting of objects by subsystems");
+
+    let mut members: HashMap<String, settings::metadata::Metadata> = HashMap::new();
+    for object in objects.iter().filter(|x| x.type_id == meta_data::types::SUBSYSTEMS) {
+        let id = String::from(object.desc.block_id());
+        visit_subsystem(blocks, &id, &object.data, None, selections, &mut members)?;
+    }
+
+    let mut count: usize = 0;
+    for object in objects.iter_mut() {
+        if object.filtr.is_some() || is_excluded(settings, object) {
+            continue;
+        }
+        if let Some(filtr) = members.get(object.desc.block_id()) {
+            object.filtr = Some(filtr.clone());
+            count += 1;
+        }
+    }
+
+    info!("-Selecting of objects by subsystems: {}", count);
+
+    Ok(())
+}
+
+/// Обходит подсистему и вложенные подсистемы и записывает идентификаторы объектов состава
+/// подсистем, соответствующих отбору. filtr - отбор родительской подсистемы.
+fn visit_subsystem(blocks: &Vec<Block>,
+                   id: &String,
+                   data: &Vec<u8>,
+                   filtr: Option<&settings::metadata::Metadata>,
+                   selections: &Vec<settings::metadata::Metadata>,
+                   members: &mut HashMap<String, settings::metadata::Metadata>)
+                   -> Result<(), ConfError> {
+
+    let desc = Description::new(id, data)?;
+    let filtr = match filtr {
+        Some(v) => Some(v.clone()),
+        None => selections.iter().find(|x| x.is_match(desc.name())).cloned(),
+    };
+
+    let doc = parse_data(id, data)?;
+
+    if let Some(ref filtr) = filtr {
+        // Состав подсистемы - ссылки на объекты по их идентификаторам
+        members.entry(id.clone()).or_insert_with(|| filtr.clone());
+        for member_id in guids(&doc.items) {
+            members.entry(member_id).or_insert_with(|| filtr.clone());
+        }
+    }
+
+    let nested_ids = match find_type_list(&doc.items, meta_data::types::SUBSYSTEMS) {
+        Some(v) => elements_ids(&v.items),
+        None => Vec::new(),
+    };
+
+    for nested_id in &nested_ids {
+        if let Some(block) = meta_data::reader::block_by_name(blocks, nested_id) {
+            let nested_data = meta_data::reader::simply_block_data(block)?;
+            visit_subsystem(blocks, nested_id, &nested_data, filtr.as_ref(), selections, members)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Возвращает идентификаторы блоков вложенных подсистем (рекурсивно)
+fn nested_subsystems(blocks: &Vec<Block>,
+                     id: &String,
+                     data: &Vec<u8>)
+                     -> Result<Vec<String>, ConfError> {
+
+    let doc = parse_data(id, data)?;
+    let nested_ids = match find_type_list(&doc.items, meta_data::types::SUBSYSTEMS) {
+        Some(v) => elements_ids(&v.items),
+        None => return Ok(Vec::new()),
+    };
+
+    let mut retval: Vec<String> = Vec::new();
+    for nested_id in nested_ids {
+        if let Some(block) = meta_data::reader::block_by_name(blocks, &nested_id) {
+            let nested_data = meta_data::reader::simply_block_data(block)?;
+            retval.extend(nested_subsystems(blocks, &nested_id, &nested_data)?);
+        }
+        retval.push(nested_id);
+    }
+
+    Ok(retval)
+}
+
+/// Оставляет объекты метаданных, на которые ссылаются отобранные объекты с признаком
+/// "dependencies" (или все отобранные объекты, если признак указан для всех объектов).
+/// Ссылки ищутся по идентификаторам, которые объекты используют для указания типа
+/// (Description::reference_ids), в описаниях объектов. Оставленные объекты не являются
+/// основными, их зависимости также оставляются. Исключенные в настройках объекты не оставляются.
+fn include_dependencies(objects: &mut [FilterObject],
+                        settings: &settings::Settings)
+                        -> Result<Vec<Dependency>, ConfError> {
+
+    let stack: Vec<usize> = (0..objects.len())
+        .filter(|&i| match objects[i].filtr {
+            Some(ref v) => settings.dependencies() || v.dependencies(),
+            None => false,
+        })
+        .collect();
+
+    if stack.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    info!("Searching of dependencies");
+
+    let mut references: HashMap<String, usize> = HashMap::new();
+    for (i, object) in objects.iter().enumerate() {
+        if !DEPENDENCY_TYPES.contains(&object.type_id) {
+            continue;
+        }
+        for id in object.desc.reference_ids() {
+            references.insert(id.clone(), i);
+        }
+    }
+
+    let retval = keep_references(objects, settings, stack, &references)?;
+
+    info!("-Searching of dependencies: {}", retval.len());
+
+    Ok(retval)
+}
+
+/// Оставляет заимствованные объекты расширения, на которые ссылаются отобранные объекты.
+/// Ссылка на заимствованный объект указывает на объект основной конфигурации, поэтому она
+/// не заменяется на тип "Любая ссылка", а заимствованный объект оставляется как зависимость.
+fn include_borrowed(objects: &mut [FilterObject],
+                    settings: &settings::Settings)
+                    -> Result<Vec<Dependency>, ConfError> {
+
+    info!("Searching of borrowed objects");
+
+    let stack: Vec<usize> = (0..objects.len()).filter(|&i| objects[i].filtr.is_some()).collect();
+
+    let mut references: HashMap<String, usize> = HashMap::new();
+    for (i, object) in objects.iter().enumerate() {
+        if object.desc.borrowed_id().is_none() {
+            continue;
+        }
+        for id in object.desc.reference_ids() {
+            references.insert(id.clone(), i);
+        }
+    }
+
+    let retval = keep_references(objects, settings, stack, &references)?;
+
+    info!("-Searching of borrowed objects: {}", retval.len());
+
+    Ok(retval)
+}
+
+/// Оставляет объекты, на которые ссылаются объекты из стека, и объекты, на которые ссылаются
+/// оставленные объекты. references - идентификаторы ссылок и индексы объектов, которые можно оставить.
+fn keep_references(objects: &mut [FilterObject],
+                   settings: &settings::Settings,
+                   stack: Vec<usize>,
+                   references: &HashMap<String, usize>)
+                   -> Result<Vec<Dependency>, ConfError> {
+
+    let mut retval: Vec<Dependency> = Vec::new();
+    let mut stack = stack;
+
+    while let Some(i) = stack.pop() {
+        let doc = parse_data(&String::from(objects[i].desc.block_id()), &objects[i].data)?;
+        let required_by = format!("{}.{}", objects[i].type_name, objects[i].desc.name());
+
+        let mut ids = guids(&doc.items);
+        ids.sort();
+        ids.dedup();
+
+        for id in ids {
+            let j = match references.get(&id) {
+                Some(&v) if v != i => v,
+                _ => continue,
+            };
+            if objects[j].filtr.is_some() || is_excluded(settings, &objects[j]) {
+                continue;
+            }
+
+            // Имя объекта конфигурации не является шаблоном и может содержать любые буквы
+            let filtr =
+                settings::metadata::Metadata::with_exact_name(&objects[j].type_name.to_string(),
+                                                              &objects[j].desc.name().to_string());
+            objects[j].filtr = Some(filtr);
+
+            let dependency = Dependency {
+                type_name: objects[j].type_name.to_string(),
+                name: objects[j].desc.name().to_string(),
+                required_by: required_by.clone(),
+            };
+            info!("Dependency: {}", dependency.description());
+
+            retval.push(dependency);
+            stack.push(j);
+        }
+    }
+
+    Ok(retval)
 }
 
 /// Возращает идентификаторы файла поставки конфигурации
-fn distributive_ids(blocks: &Vec<Block>, conf_desc: &Description) -> Vec<String> {
+fn distributive_ids(blocks: &Vec<Block>,
+                    conf_desc: &Description)
+                    -> Result<Vec<String>, ConfError> {
 
     info!("Reading IDs of blocks with data of distributive.");
 
@@ -129,8 +790,8 @@ fn distributive_ids(blocks: &Vec<Block>, conf_desc: &Description) -> Vec<String>
             continue;
         }
 
-        let data = meta_data::reader::simply_block_data(item);
-        let dist_ids = meta_data::reader::find_ids(&data, meta_data::reader::RegexTypes::All);
+        let data = meta_data::reader::simply_block_data(item)?;
+        let dist_ids = guids(&parse_data(item.id(), &data)?.items);
 
         for id in &dist_ids {
             retval.push(format!("{}.{}", conf_desc.block_id(), id))
@@ -149,67 +810,31 @@ fn distributive_ids(blocks: &Vec<Block>, conf_desc: &Description) -> Vec<String>
     info!("-Reading IDs of blocks with data of distributive: {}",
           retval.len());
 
-    retval
-}
-
-/// Определяет координаты типа в описании конфигурации
-fn find_type_coordinates(type_id: &'static str,
-                         type_name: &'static str,
-                         conf_data: &Vec<u8>)
-                         -> meta_data::substr::Substr {
-
-    let coordinates = meta_data::reader::find_type_coordinates(type_id, &conf_data);
-
-    if coordinates.is_none() {
-        // Возможно что-то изменилось в конфигурационном файле и для типа определен новый идентификатор
-        error!("Failed finding type '{}' coordinates", type_name);
-        panic!("Failed finding type '{}' coordinates", type_name);
-    }
-
-    coordinates.unwrap()
+    Ok(retval)
 }
 
-/// Определяет параметры фильтрации типа
-fn find_type_filter(type_name: &'static str,
+/// Определяет параметры фильтрации типа по всем именам типа (на русском, на английском и прежним)
+fn find_type_filter(type_names: &Vec<&'static str>,
                     settings: &settings::Settings)
                     -> Vec<settings::metadata::Metadata> {
 
-    let mut type_filter = settings.metadata_selections(&type_name.to_string());
-    if type_filter.is_none() {
-        // Нет отбора по типу метаданных, но блоки необходимо обработать т.к. на них могут быть указаны ссылки в других объектах
-        type_filter = Some(Vec::new());
+    // Если нет отбора по типу метаданных, то блоки все равно необходимо обработать,
+    // т.к. на них могут быть указаны ссылки в других объектах
+    let mut type_filter: Vec<settings::metadata::Metadata> = Vec::new();
+    for type_name in type_names {
+        if let Some(v) = settings.metadata_selections(&type_name.to_string()) {
+            type_filter.extend(v);
+        }
     }
 
-    type_filter.unwrap()
+    type_filter
 }
 
-/// Поиск идентификаторов блоков с описанием объектов метаданных, которые относятся к типу
-fn metadata_blocks_ids(conf_data: &Vec<u8>,
-                       coordinates: &meta_data::substr::Substr)
-                       -> Vec<String> {
-
-    let type_description = part_bytes!(&conf_data, coordinates);
-    meta_data::reader::find_ids(&type_description,
-                                meta_data::reader::RegexTypes::ElementsOfType)
-}
-
-/// Сравнить имя с шаблоном
-#[inline(always)]
-fn compare_name(full_name: &String, name_template: String) -> bool {
-
-    let mut name_template = name_template;
-    let i = name_template.find("*");
-
-    match i.is_some() {
-        true => {
-            let i = i.unwrap();
-            name_template.remove(i);
-
-            name_template.is_empty() || (i == 0 && full_name.ends_with(&*name_template)) ||
-            (i > 0 && full_name.starts_with(&*name_template))
-        }
-        false => full_name.eq(&*name_template),
-    }
+/// Поиск идентификаторов блоков с описанием объектов метаданных, которые относятся к типу.
+/// Если тип не указан в описании конфигурации (например, он появился в более новой версии платформы),
+/// то возвращается None.
+fn metadata_blocks_ids(type_id: &'static str, conf_doc: &Document) -> Option<Vec<String>> {
+    find_type_list(&conf_doc.items, type_id).map(|v| elements_ids(&v.items))
 }
 
 /// Проверить соотвествие имени объекта установленному фильтру метаданных
@@ -218,30 +843,51 @@ fn check_object_name<'a>(type_filter: &'a Vec<settings::metadata::Metadata>,
                          object_name: String)
                          -> Option<&'a settings::metadata::Metadata> {
 
-    type_filter.iter().find(|&x| compare_name(&object_name, x.name().clone()))
+    type_filter.iter().find(|&x| x.is_match(&object_name))
 }
 
-/// Проверить имя внутреннего объекта (формы, шаблона и т.д.)
-fn check_internal_object_name(blocks: &Vec<Block>, block_id: &String, names: &Vec<String>) -> bool {
+/// Получить имя внутреннего объекта (формы, шаблона, реквизита и т.д.). Имена реквизитов и табличных частей
+/// указаны в описании объекта, имена форм, шаблонов и команд - в описании их блоков.
+fn internal_object_name(blocks: &Vec<Block>,
+                        desc: &Description,
+                        id: &String)
+                        -> Result<Option<String>, ConfError> {
 
-    let mut except = false;
+    if let Some(name) = desc.internal_name(id) {
+        return Ok(Some(name.to_string()));
+    }
 
-    for item in blocks.iter() {
-        if item.id().ne(&*block_id) {
-            continue;
+    match meta_data::reader::block_by_name(blocks, id) {
+        Some(block) => {
+            let data = meta_data::reader::simply_block_data(block)?;
+            let data = Description::new(&id, &data)?;
+            Ok(Some(data.name().to_string()))
         }
+        None => Ok(None),
+    }
+}
 
-        let data = meta_data::reader::simply_block_data(item);
-        let data = Description::new(&block_id, &data);
-        let name = String::from(data.name().clone());
-
-        except = names.iter()
-            .find(|x| compare_name(&name, (*x).clone()))
-            .is_some();
-        break;
+/// Возвращает вид вложенного объекта, по которому он отбирается в настройках (except_<вид>, only_<вид>)
+fn nested_kind(internal_type_id: &str) -> &'static str {
+    match internal_type_id {
+        meta_data::types::FORMS_ID_DOC |
+        meta_data::types::FORMS_ID_CATALOG => "forms",
+        meta_data::types::LAYOUTS_ID => "templates",
+        meta_data::types::COMMANDS_ID => "commands",
+        meta_data::types::TABULAR_SELECTIONS_ID => "tabular_sections",
+        _ => "attributes",
     }
+}
 
-    except
+/// Возвращает наименование вида вложенного объекта для отчета
+fn nested_kind_name(internal_type_id: &str) -> &'static str {
+    match nested_kind(internal_type_id) {
+        "forms" => "Forms",
+        "templates" => "Templates",
+        "commands" => "Commands",
+        "tabular_sections" => "TabularSections",
+        _ => "Attributes",
+    }
 }
 
 /// Получить идентфикаторы объектов внутренних типов (форм, шаблонов и т.д.)
@@ -255,40 +901,31 @@ fn internal_objects_ids(type_id: &'static str, desc: &Description) -> Vec<String
     <Vec<String>>::new()
 }
 
-/// Отфильтровать индентификаторы блоков внутренних типов (формы, шаблоны и т.д.)
+/// Отфильтровать индентификаторы внутренних объектов (формы, шаблоны, реквизиты и т.д.)
 fn filtr_internal_ids(object_filtr: &settings::metadata::Metadata,
                       internal_type_id: &'static str,
                       internal_ids: &Vec<String>,
+                      desc: &Description,
                       blocks: &Vec<Block>)
-                      -> (Vec<String>, Vec<String>) {
+                      -> Result<(Vec<String>, Vec<String>), ConfError> {
 
     let mut force_ids = <Vec<String>>::new();
     let mut except_ids = <Vec<String>>::new();
 
-    if object_filtr.main() {
-        let except_forms = object_filtr.except_forms();
-        let except_templates = object_filtr.except_templates();
-
-        let type_id = internal_type_id.clone().to_string();
+    // Табличные части не основного объекта оставляются, если в настройках не указано иное
+    if object_filtr.main() || internal_type_id == meta_data::types::TABULAR_SELECTIONS_ID {
+        let kind = nested_kind(internal_type_id);
 
         for id in internal_ids.iter() {
-            let mut except = false;
-
-            if !except_forms.is_empty() &&
-               (type_id == meta_data::types::FORMS_ID_DOC ||
-                type_id == meta_data::types::FORMS_ID_CATALOG) {
-
-                except = check_internal_object_name(&blocks, &id, &except_forms);
+            let kept = match internal_object_name(&blocks, &desc, &id)? {
+                Some(name) => object_filtr.is_nested_kept(kind, &name),
+                None => true,
+            };
 
-            } else if !except_templates.is_empty() && type_id == meta_data::types::LAYOUTS_ID {
-
-                except = check_internal_object_name(&blocks, &id, &except_templates);
-            }
-
-            if except {
-                except_ids.push(id.clone());
-            } else {
+            if kept {
                 force_ids.push(id.clone());
+            } else {
+                except_ids.push(id.clone());
             }
         }
     } else {
@@ -296,39 +933,31 @@ fn filtr_internal_ids(object_filtr: &settings::metadata::Metadata,
         except_ids.extend_from_slice(&internal_ids[..]);
     }
 
-    (force_ids, except_ids)
+    Ok((force_ids, except_ids))
 }
 
 /// Обновить описание идентификаторов внутренних типов в описании блока
-fn update_internal_ids(data: &mut Vec<u8>,
+fn update_internal_ids(doc: &mut Document,
+                       block_id: &String,
                        internal_type_id: &'static str,
-                       force_ids: Vec<String>,
-                       except_ids: Vec<String>) {
-
-    let internal_type_coordinates = meta_data::reader::find_type_coordinates(internal_type_id,
-                                                                             &data);
-    let internal_type_coordinates = internal_type_coordinates.unwrap();
+                       except_ids: Vec<String>)
+                       -> Result<(), ConfError> {
 
-    replace_bytes!(data,
-                   internal_type_coordinates,
-                   type_desc(internal_type_id, &force_ids));
-
-    let aut = AcAutomaton::new(except_ids);
-    let tmp_buf = data.clone();
-    let mut it = aut.find(&tmp_buf[..]);
+    if !remove_type_elements(&mut doc.items, internal_type_id, &except_ids) {
+        return Err(metadata_error(block_id,
+                                  format!("Failed finding internal type '{}' in the description",
+                                          internal_type_id)));
+    }
 
-    loop {
-        let m = match it.next() {
-            Some(m) => m,
-            None => break,
-        };
+    replace_guids(&mut doc.items, &except_ids, meta_data::types::EMPTY_REF);
 
-        replace_bytes!(data, m.start, m.end, meta_data::types::EMPTY_REF);
-    }
+    Ok(())
 }
 
 /// Заменить ссылки на удаленные объекты метаданных на тип "Любая ссылка"
-fn remove_references_deleted_blocks(blocks: &Vec<Block>, deleted_ref_ids: Vec<String>) {
+pub fn remove_references_deleted_blocks(blocks: &Vec<Block>,
+                                        deleted_ref_ids: Vec<String>)
+                                        -> Result<(), ConfError> {
 
     info!("Removing references of deleted blocks.");
 
@@ -340,7 +969,7 @@ fn remove_references_deleted_blocks(blocks: &Vec<Block>, deleted_ref_ids: Vec<St
 
     for item in blocks.iter() {
 
-        for nested_block in item.get_data() {
+        for nested_block in item.get_data()? {
             // т.к. длина заменяемго идентификатора всегда равна длине нового,
             // то не боимся за то что координаты изменятся
             let mut data = nested_block.data;
@@ -353,30 +982,73 @@ fn remove_references_deleted_blocks(blocks: &Vec<Block>, deleted_ref_ids: Vec<St
                     None => break,
                 };
 
-                replace_bytes!(data, m.start, m.end, meta_data::types::ANY_REF);
+                data.splice(m.start..m.end, meta_data::types::ANY_REF.bytes());
 
             }
 
-            item.set_data(nested_block.attrs.id(), &data);
+            item.set_data(nested_block.attrs.id(), &data)?;
         }
     }
 
     info!("-Removing references of deleted blocks.");
+
+    Ok(())
+}
+
+
+/// Возвращает описание типа в формате: {<идентификатор типа>,<количество подчиненных объектов>[, идентификаторы объектов]}
+/// Пример: {fdf816d2-1ead-11d5-b975-0050bae0a95d,2,2605a1e0-a034-4fd1-885b-7a2fdf618144,3305a1e0-a034-4fd1-885b-7a2fdf618144}
+fn type_desc(type_id: &str, obj_ids: &Vec<String>) -> List {
+
+    let mut values = vec![Value::Guid(String::from(type_id)),
+                          Value::Number(obj_ids.len().to_string())];
+    values.extend(obj_ids.iter().map(|x| Value::Guid(x.clone())));
+
+    List::new(values)
 }
 
+/// Заменить описание типа. Возвращает false, если описание типа не найдено.
+pub fn set_type_desc(items: &mut [Node], type_id: &str, obj_ids: &Vec<String>) -> bool {
+    match find_type_list_mut(items, type_id) {
+        Some(v) => {
+            *v = type_desc(type_id, obj_ids);
+            true
+        }
+        None => false,
+    }
+}
 
-/// Возвращает описание типа в формате: <идентификатор типа>,<количество подчиненных объектов>[, идентификаторы объектов]
-/// Пример: fdf816d2-1ead-11d5-b975-0050bae0a95d,2,2605a1e0-a034-4fd1-885b-7a2fdf618144,3305a1e0-a034-4fd1-885b-7a2fdf618144
-fn type_desc(type_id: &str, obj_ids: &Vec<String>) -> String {
+/// Удалить из описания типа элементы с указанными идентификаторами и обновить количество элементов.
+/// Остальные элементы не изменяются. Возвращает false, если описание типа не найдено.
+fn remove_type_elements(items: &mut [Node], type_id: &str, obj_ids: &Vec<String>) -> bool {
+    let list = match find_type_list_mut(items, type_id) {
+        Some(v) => v,
+        None => return false,
+    };
+
+    let count = list.items.len();
+    let mut index = 0;
+    list.items.retain(|node| {
+        index += 1;
+        if index <= 2 {
+            return true;
+        }
 
-    let mut obj_ids_text = String::new();
-    let obj_ids_text = obj_ids.iter().fold(&mut obj_ids_text, |acc, x| {
-        acc.push_str(",");
-        acc.push_str(&*(*x));
-        acc
+        match element_id_and_name(node) {
+            Some((id, _)) => !obj_ids.contains(&id),
+            None => true,
+        }
     });
 
-    format!("{},{}{}", type_id, obj_ids.len(), obj_ids_text)
+    let removed = count - list.items.len();
+    if removed > 0 {
+        let number = list.item(1).and_then(Value::number).and_then(|x| x.parse::<usize>().ok());
+        if let Some(number) = number {
+            list.items[1].value = Value::Number(number.saturating_sub(removed).to_string());
+        }
+    }
+
+    true
 }
 
 
@@ -391,7 +1063,15 @@ mod tests {
     use settings::Settings;
 
     use structure;
-    use super::filter;
+    use meta_data::description::Description;
+    use meta_data::types;
+    use meta_data::brace::Document;
+    use super::{Dependency, FilterObject, FilterReport, ReplacedReference, filter, filtr_internal_ids,
+                include_borrowed, include_dependencies, nested_subsystems, select_subsystems,
+                update_internal_ids};
+    use structure::block::Block;
+
+    use std::collections::HashMap;
 
     #[test]
     fn test_filter() {
@@ -401,7 +1081,7 @@ mod tests {
         let settings = read_settings();
 
         let blocks_before = blocks.clone();
-        filter(&mut blocks, &settings);
+        filter(&mut blocks, &settings).unwrap();
 
         let deleted = blocks_before.iter()
             .filter(|&x| blocks.iter().find(|y| x.id().eq(&*(y.id()))).is_none())
@@ -412,6 +1092,321 @@ mod tests {
         assert!(!deleted.is_empty());
     }
 
+    #[test]
+    fn test_include_dependencies() {
+
+        const DOC_ID: &'static str = "d0d0d0d0-0000-0000-0000-000000000001";
+        const CATALOG_ID: &'static str = "c0c0c0c0-0000-0000-0000-000000000002";
+        const CATALOG_REF: &'static str = "c1c1c1c1-0000-0000-0000-000000000002";
+        const ENUM_ID: &'static str = "e0e0e0e0-0000-0000-0000-000000000003";
+        const ENUM_REF: &'static str = "e1e1e1e1-0000-0000-0000-000000000003";
+        const OTHER_ID: &'static str = "f0f0f0f0-0000-0000-0000-000000000004";
+
+        let mut objects = vec![
+            object(types::DOCUMENT, "Документы", DOC_ID,
+                   format!("{{1,{{3,{{0,0,{}}},\"Заказ\",{{\"Pattern\",{{\"#\",{}}}}}}}}}",
+                           DOC_ID, CATALOG_REF)),
+            object(types::CATALOG, "Справочники", CATALOG_ID,
+                   format!("{{1,{{3,{{0,{{0,0,{}}}}},{{0,0,{}}},\"Валюты\",{{\"#\",{}}}}}}}",
+                           CATALOG_REF, CATALOG_ID, ENUM_REF)),
+            object(types::ENUM, "Перечисления", ENUM_ID,
+                   format!("{{1,{{{},{{0,0,{}}},\"Виды\"}}}}", ENUM_REF, ENUM_ID)),
+            object(types::CATALOG, "Справочники", OTHER_ID,
+                   format!("{{1,{{{{0,0,{}}},\"Прочее\"}}}}", OTHER_ID)),
+        ];
+
+        let settings = Settings::new(&String::from("<project/>")).unwrap();
+
+        // Без признака зависимости не ищутся
+        let mut attributes: HashMap<String, String> = HashMap::new();
+        objects[0].filtr = Some(create_filter("Заказ", &attributes));
+        assert!(include_dependencies(&mut objects, &settings).unwrap().is_empty());
+        assert!(objects[1].filtr.is_none());
+
+        attributes.insert(String::from("dependencies"), String::from("true"));
+        objects[0].filtr = Some(create_filter("Заказ", &attributes));
+
+        let dependencies = include_dependencies(&mut objects, &settings).unwrap();
+        let descriptions: Vec<String> = dependencies.iter().map(|x| x.description()).collect();
+        assert_eq!(vec!["Справочники.Валюты <- Документы.Заказ",
+                        "Перечисления.Виды <- Справочники.Валюты"],
+                   descriptions);
+
+        assert!(!objects[1].filtr.as_ref().unwrap().main());
+        assert!(objects[2].filtr.is_some());
+        assert!(objects[3].filtr.is_none());
+
+        // Исключенные в настройках объекты не оставляются
+        objects[1].filtr = None;
+        objects[2].filtr = None;
+        let settings = Settings::new(&String::from("<project><sourceIB><objects><exclude>\
+                                                    <Перечисления name=\"Вид?\"/>\
+                                                    </exclude></objects></sourceIB></project>")).unwrap();
+        assert_eq!(1, include_dependencies(&mut objects, &settings).unwrap().len());
+        assert!(objects[1].filtr.is_some());
+        assert!(objects[2].filtr.is_none());
+    }
+
+    #[test]
+    fn test_include_dependencies_exact_name() {
+
+        const DOC_ID: &'static str = "d0d0d0d0-0000-0000-0000-000000000001";
+        const CATALOG_ID: &'static str = "c0c0c0c0-0000-0000-0000-000000000002";
+        const CATALOG_REF: &'static str = "c1c1c1c1-0000-0000-0000-000000000002";
+
+        let mut objects = vec![
+            object(types::DOCUMENT, "Документы", DOC_ID,
+                   format!("{{1,{{3,{{0,0,{}}},\"Заказ\",{{\"Pattern\",{{\"#\",{}}}}}}}}}",
+                           DOC_ID, CATALOG_REF)),
+            object(types::CATALOG, "Справочники", CATALOG_ID,
+                   format!("{{1,{{3,{{0,{{0,0,{}}}}},{{0,0,{}}},\"СчётаУчёта\"}}}}",
+                           CATALOG_REF, CATALOG_ID)),
+        ];
+
+        let mut attributes: HashMap<String, String> = HashMap::new();
+        attributes.insert(String::from("dependencies"), String::from("true"));
+        objects[0].filtr = Some(create_filter("Заказ", &attributes));
+
+        let settings = Settings::new(&String::from("<project/>")).unwrap();
+        let dependencies = include_dependencies(&mut objects, &settings).unwrap();
+
+        assert_eq!(1, dependencies.len());
+        assert_eq!("Справочники.СчётаУчёта <- Документы.Заказ", dependencies[0].description());
+        assert!(objects[1].filtr.as_ref().unwrap().is_match("СчётаУчёта"));
+    }
+
+    #[test]
+    fn test_include_borrowed() {
+
+        const DOC_ID: &'static str = "d0d0d0d0-0000-0000-0000-000000000001";
+        const CATALOG_ID: &'static str = "c0c0c0c0-0000-0000-0000-000000000002";
+        const CATALOG_REF: &'static str = "c1c1c1c1-0000-0000-0000-000000000002";
+        const OWN_ID: &'static str = "f0f0f0f0-0000-0000-0000-000000000003";
+        const OWN_REF: &'static str = "f1f1f1f1-0000-0000-0000-000000000003";
+        const ADOPTED_ID: &'static str = "a0a0a0a0-0000-0000-0000-000000000004";
+
+        let mut objects = vec![
+            object(types::DOCUMENT, "Документы", DOC_ID,
+                   format!("{{1,{{3,{{0,0,{}}},\"ок_Заказ\",{{0}},\"\",0,\
+                            00000000-0000-0000-0000-000000000000}},{{\"#\",{}}},{{\"#\",{}}}}}",
+                           DOC_ID, CATALOG_REF, OWN_REF)),
+            object(types::CATALOG, "Справочники", CATALOG_ID,
+                   format!("{{1,{{{},{{3,{{0,0,{}}},\"Товары\",{{0}},\"\",1,{}}}}}}}",
+                           CATALOG_REF, CATALOG_ID, ADOPTED_ID)),
+            object(types::CATALOG, "Справочники", OWN_ID,
+                   format!("{{1,{{{},{{3,{{0,0,{}}},\"ок_Настройки\",{{0}},\"\",0,\
+                            00000000-0000-0000-0000-000000000000}}}}}}",
+                           OWN_REF, OWN_ID)),
+        ];
+
+        let settings = Settings::new(&String::from("<project/>")).unwrap();
+        objects[0].filtr = Some(create_filter("ок_Заказ", &HashMap::new()));
+
+        // Оставляется только заимствованный объект, собственный удаляется
+        let dependencies = include_borrowed(&mut objects, &settings).unwrap();
+        assert_eq!(vec![Dependency {
+                            type_name: String::from("Справочники"),
+                            name: String::from("Товары"),
+                            required_by: String::from("Документы.ок_Заказ"),
+                        }],
+                   dependencies);
+        assert!(!objects[1].filtr.as_ref().unwrap().main());
+        assert!(objects[2].filtr.is_none());
+    }
+
+    #[test]
+    fn test_filter_report() {
+
+        let report = FilterReport {
+            kept: vec![String::from("Документы.Заказ"), String::from("Справочники.Валюты")],
+            removed: vec![String::from("Справочники.Прочее")],
+            removed_internal: vec![String::from("Справочники.Валюты.Forms.ФормаЭлемента")],
+            removed_modules: vec![String::from("Справочники.Валюты.ObjectModule")],
+            distributive: Vec::new(),
+            dependencies: vec![Dependency {
+                                   type_name: String::from("Справочники"),
+                                   name: String::from("Валюты"),
+                                   required_by: String::from("Документы.Заказ"),
+                               }],
+            references: vec![ReplacedReference {
+                                 object: String::from("Справочники.Прочее"),
+                                 replacement: types::ANY_REF,
+                                 used_in: vec![String::from("Документы.Заказ")],
+                             }],
+        };
+
+        assert_eq!(format!("kept: 2\n    Документы.Заказ\n    Справочники.Валюты\n\
+                            removed: 1\n    Справочники.Прочее\n\
+                            removed internal objects: 1\n    Справочники.Валюты.Forms.ФормаЭлемента\n\
+                            removed modules: 1\n    Справочники.Валюты.ObjectModule\n\
+                            removed distributive blocks: 0\n\
+                            dependencies: 1\n    Справочники.Валюты <- Документы.Заказ\n\
+                            replaced references: 1\n    Справочники.Прочее -> {}\n        \
+                            Документы.Заказ\n",
+                           types::ANY_REF),
+                   report.to_text());
+
+        assert_eq!(format!("{{\"kept\":[\"Документы.Заказ\",\"Справочники.Валюты\"],\
+                            \"removed\":[\"Справочники.Прочее\"],\
+                            \"removed_internal\":[\"Справочники.Валюты.Forms.ФормаЭлемента\"],\
+                            \"removed_modules\":[\"Справочники.Валюты.ObjectModule\"],\
+                            \"distributive\":[],\"dependencies\":[{{\"type\":\"Справочники\",\
+                            \"name\":\"Валюты\",\"required_by\":\"Документы.Заказ\"}}],\
+                            \"references\":[{{\"object\":\"Справочники.Прочее\",\
+                            \"replacement\":\"{}\",\"used_in\":[\"Документы.Заказ\"]}}]}}\n",
+                           types::ANY_REF),
+                   report.to_json());
+    }
+
+    #[test]
+    fn test_select_subsystems() {
+
+        const SALES_ID: &'static str = "a0a0a0a0-0000-0000-0000-000000000001";
+        const DISCOUNTS_ID: &'static str = "a1a1a1a1-0000-0000-0000-000000000002";
+        const DOC_ID: &'static str = "d0d0d0d0-0000-0000-0000-000000000003";
+        const CATALOG_ID: &'static str = "c0c0c0c0-0000-0000-0000-000000000004";
+        const OTHER_ID: &'static str = "f0f0f0f0-0000-0000-0000-000000000005";
+
+        let sales = format!("{{1,{{{{0,0,{}}},\"Продажи\",{{1,{}}},{{{},1,{}}}}}}}",
+                            SALES_ID, DOC_ID, types::SUBSYSTEMS, DISCOUNTS_ID);
+        let discounts = format!("{{1,{{{{0,0,{}}},\"Скидки\",{{1,{}}},{{{},0}}}}}}",
+                                DISCOUNTS_ID, CATALOG_ID, types::SUBSYSTEMS);
+
+        let blocks = vec![Block::new(SALES_ID, &sales.clone().into_bytes()),
+                          Block::new(DISCOUNTS_ID, &discounts.clone().into_bytes())];
+
+        let mut objects = vec![
+            object(types::SUBSYSTEMS, "Подсистемы", SALES_ID, sales.clone()),
+            object(types::DOCUMENT, "Документы", DOC_ID,
+                   format!("{{1,{{{{0,0,{}}},\"Заказ\"}}}}", DOC_ID)),
+            object(types::CATALOG, "Справочники", CATALOG_ID,
+                   format!("{{1,{{{{0,0,{}}},\"Скидки\"}}}}", CATALOG_ID)),
+            object(types::CATALOG, "Справочники", OTHER_ID,
+                   format!("{{1,{{{{0,0,{}}},\"Прочее\"}}}}", OTHER_ID)),
+        ];
+
+        let settings = Settings::new(&String::from("<project><sourceIB><objects>\
+                                                    <subsystem name=\"Продажи\" main=\"true\"/>\
+                                                    </objects></sourceIB></project>")).unwrap();
+        select_subsystems(&blocks, &mut objects, &settings).unwrap();
+
+        assert!(objects[0].filtr.is_some());
+        assert!(objects[1].filtr.as_ref().unwrap().main());
+        assert!(objects[2].filtr.is_some()); // объект вложенной подсистемы
+        assert!(objects[3].filtr.is_none());
+
+        assert_eq!(vec![String::from(DISCOUNTS_ID)],
+                   nested_subsystems(&blocks, &String::from(SALES_ID), &sales.into_bytes()).unwrap());
+
+        // Отбор по вложенной подсистеме
+        for object in objects.iter_mut() {
+            object.filtr = None;
+        }
+        let settings = Settings::new(&String::from("<project><sourceIB><objects>\
+                                                    <subsystem name=\"Скидки\"/>\
+                                                    </objects></sourceIB></project>")).unwrap();
+        select_subsystems(&blocks, &mut objects, &settings).unwrap();
+
+        assert!(objects[0].filtr.is_none());
+        assert!(objects[1].filtr.is_none());
+        assert!(objects[2].filtr.is_some());
+    }
+
+    #[test]
+    fn test_filter_nested_objects() {
+
+        const CATALOG_ID: &'static str = "c0c0c0c0-0000-0000-0000-000000000001";
+        const HISTORY_ID: &'static str = "a0a0a0a0-0000-0000-0000-000000000002";
+        const PRICES_ID: &'static str = "a1a1a1a1-0000-0000-0000-000000000003";
+        const CODE_ID: &'static str = "b0b0b0b0-0000-0000-0000-000000000004";
+        const NOTE_ID: &'static str = "b1b1b1b1-0000-0000-0000-000000000005";
+
+        let data = format!("{{1,{{{{{{0,0,{}}},\"Товары\"}},\n\
+                            {{{},2,\n{{{{{{0,0,{}}},\"История\"}},0}},\n{{{{{{0,0,{}}},\"Цены\"}},0}}}},\n\
+                            {{{},2,{{{{{{0,0,{}}},\"Код\"}},{}}},{{{{{{0,0,{}}},\"Комментарий\"}},0}}}}}}}}",
+                           CATALOG_ID,
+                           types::TABULAR_SELECTIONS_ID,
+                           HISTORY_ID,
+                           PRICES_ID,
+                           types::PROPS_ID,
+                           CODE_ID,
+                           PRICES_ID,
+                           NOTE_ID);
+        let catalog = object(types::CATALOG, "Справочники", CATALOG_ID, data.clone());
+        let blocks: Vec<Block> = Vec::new();
+
+        assert_eq!(Some("Цены"), catalog.desc.internal_name(PRICES_ID));
+
+        let mut attributes: HashMap<String, String> = HashMap::new();
+        attributes.insert(String::from("main"), String::from("true"));
+        let mut filtr = create_filter("Товары", &attributes);
+        filtr.set_only("tabular_sections", vec![String::from("Ист*")]);
+        filtr.set_except("attributes", vec![String::from("Комментарий")]);
+
+        let tabular_ids = catalog.desc.internal_types_ids(types::TABULAR_SELECTIONS_ID).unwrap();
+        let (force_ids, except_ids) =
+            filtr_internal_ids(&filtr, types::TABULAR_SELECTIONS_ID, tabular_ids, &catalog.desc, &blocks)
+                .unwrap();
+        assert_eq!(vec![String::from(HISTORY_ID)], force_ids);
+        assert_eq!(vec![String::from(PRICES_ID)], except_ids);
+
+        let props_ids = catalog.desc.internal_types_ids(types::PROPS_ID).unwrap();
+        let (_, except_props_ids) =
+            filtr_internal_ids(&filtr, types::PROPS_ID, props_ids, &catalog.desc, &blocks).unwrap();
+        assert_eq!(vec![String::from(NOTE_ID)], except_props_ids);
+
+        // Удаляются только элементы списка, остальной текст не изменяется
+        let mut doc = Document::parse(&data.into_bytes()).unwrap();
+        let block_id = String::from(CATALOG_ID);
+        update_internal_ids(&mut doc, &block_id, types::TABULAR_SELECTIONS_ID, except_ids).unwrap();
+        update_internal_ids(&mut doc, &block_id, types::PROPS_ID, except_props_ids).unwrap();
+
+        let expected = format!("{{1,{{{{{{0,0,{}}},\"Товары\"}},\n\
+                                {{{},1,\n{{{{{{0,0,{}}},\"История\"}},0}}}},\n\
+                                {{{},1,{{{{{{0,0,{}}},\"Код\"}},{}}}}}}}}}",
+                               CATALOG_ID,
+                               types::TABULAR_SELECTIONS_ID,
+                               HISTORY_ID,
+                               types::PROPS_ID,
+                               CODE_ID,
+                               types::EMPTY_REF);
+        assert_eq!(expected, String::from_utf8(doc.to_bytes()).unwrap());
+
+        // У не основного объекта удаляются реквизиты, но остаются табличные части
+        let filtr = create_filter("Товары", &HashMap::new());
+        let (force_ids, except_ids) =
+            filtr_internal_ids(&filtr, types::TABULAR_SELECTIONS_ID, tabular_ids, &catalog.desc, &blocks)
+                .unwrap();
+        assert_eq!(2, force_ids.len());
+        assert!(except_ids.is_empty());
+
+        let (force_ids, except_ids) =
+            filtr_internal_ids(&filtr, types::PROPS_ID, props_ids, &catalog.desc, &blocks).unwrap();
+        assert!(force_ids.is_empty());
+        assert_eq!(2, except_ids.len());
+    }
+
+    fn object(type_id: &'static str, type_name: &'static str, id: &str, data: String) -> FilterObject {
+        let data = data.into_bytes();
+        FilterObject {
+            type_id: type_id,
+            type_name: type_name,
+            block: 0,
+            desc: Description::new(&String::from(id), &data).unwrap(),
+            data: data,
+            filtr: None,
+        }
+    }
+
+    fn create_filter(name: &str, attributes: &HashMap<String, String>) -> settings::metadata::Metadata {
+        settings::metadata::Metadata::new(&String::from("Документы"),
+                                          &String::from(name),
+                                          attributes,
+                                          Vec::new(),
+                                          Vec::new())
+            .unwrap()
+    }
+
     fn init_log() {
         let path_to_current_dir = file_system::get_current_dir()
             .ok()
@@ -444,7 +1439,7 @@ mod tests {
             Err(e) => panic!("{}", e),
         };
 
-        let blocks = structure::reader::from_cf(&data);
+        let (blocks, _) = structure::reader::from_cf(data).unwrap();
         assert!(!blocks.is_empty());
 
         return blocks;
@@ -467,6 +1462,6 @@ mod tests {
             Err(e) => panic!("{}", e),
         };
 
-        return Settings::new(&pom1c_xml);
+        return Settings::new(&pom1c_xml).unwrap();
     }
 }
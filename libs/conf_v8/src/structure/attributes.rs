@@ -17,6 +17,7 @@ pub struct Attributes {
     modification_date: u64, // дата модификации
     group_type: i32, // тип блока
     id: String, // идентификатор блока
+    unknown: [u8; 4], // значение, которое следует за идентификатором блока
 }
 
 
@@ -24,47 +25,71 @@ impl Attributes {
     // Получить атрибуты для нового блока, используется при создании нового конфигурационного файла
     pub fn new(_creation_date: u64, _group_type: i32, _id: &String) -> Attributes {
 
+        let unknown: [u8; 4] = match _id.len() {
+            0 => [0x7f, 0xff, 0xff, 0xff], // Пустое значение = 2147483647 - i32::max_value()
+            _ => [0x00, 0x00, 0x00, 0x00],
+        };
+
         Attributes {
             creation_date: _creation_date,
             modification_date: _creation_date,
             group_type: _group_type,
             id: _id.clone(),
+            unknown: unknown,
+        }
+    }
+
+    // Получить атрибуты блока с заданными значениями всех полей, используется для
+    // восстановления атрибутов, сохраненных при распаковке конфигурационного файла
+    pub fn from_values(_creation_date: u64,
+                       _modification_date: u64,
+                       _group_type: i32,
+                       _id: &String,
+                       _unknown: [u8; 4])
+                       -> Attributes {
+        Attributes {
+            creation_date: _creation_date,
+            modification_date: _modification_date,
+            group_type: _group_type,
+            id: _id.clone(),
+            unknown: _unknown,
         }
     }
 
     // Получить атрибуты блока на основании данных конфигурационного файла
-    pub fn from_cf(data: &[u8]) -> Attributes {
+    pub fn from_cf(data: &[u8]) -> Result<Attributes, String> {
+
+        if data.len() < 24 {
+            return Err(format!("Too small size of the attributes: {}", data.len()));
+        }
+
         let end_id = data.len() - 4;
         let id = match String::from_utf8(conv::utf16_to_utf8(&data[20..end_id])) {
             Ok(v) => v,
-            Err(e) => {
-                error!("Failed to get the id of the block: {}", e);
-                panic!("Failed to get the id of the block: {}", e);
-            }
+            Err(e) => return Err(format!("Failed to get the id of the block: {}", e)),
         };
 
-        Attributes {
+        let mut unknown = [0u8; 4];
+        unknown.copy_from_slice(&data[end_id..]);
+
+        Ok(Attributes {
             creation_date: conv::bytes_to_int64(&data[0..8]),
             modification_date: conv::bytes_to_int64(&data[8..16]),
             group_type: conv::bytes_to_int32(&data[16..20]),
             id: id,
-        }
+            unknown: unknown,
+        })
     }
 
     // Возвращает данные атрибутов для конфигурационного файла
     pub fn for_cf(&self) -> Vec<u8> {
 
-        let unknow_block: [u8; 4] = match self.id.len() {
-            0 => [0x7f, 0xff, 0xff, 0xff], // Пустое значение = 2147483647 - i32::max_value()
-            _ => [0x00, 0x00, 0x00, 0x00],
-        };
-
         let mut data: Vec<u8> = vec![];
         data.extend_from_slice(&conv::int64_to_bytes(self.creation_date));
         data.extend_from_slice(&conv::int64_to_bytes(self.modification_date));
         data.extend_from_slice(&conv::int32_to_bytes(self.group_type));
         data.extend_from_slice(&conv::utf8_to_utf16(&self.id.as_bytes()[..]));
-        data.extend_from_slice(&unknow_block);
+        data.extend_from_slice(&self.unknown);
 
         data
     }
@@ -73,6 +98,26 @@ impl Attributes {
     pub fn id<'a>(&'a self) -> &'a String {
         &self.id
     }
+
+    // Возвращает дату создания
+    pub fn creation_date(&self) -> u64 {
+        self.creation_date
+    }
+
+    // Возвращает дату модификации
+    pub fn modification_date(&self) -> u64 {
+        self.modification_date
+    }
+
+    // Возвращает тип блока
+    pub fn group_type(&self) -> i32 {
+        self.group_type
+    }
+
+    // Возвращает значение, которое следует за идентификатором блока
+    pub fn unknown(&self) -> [u8; 4] {
+        self.unknown
+    }
 }
 
 impl Clone for Attributes {
@@ -82,6 +127,7 @@ impl Clone for Attributes {
             modification_date: self.modification_date,
             group_type: self.group_type,
             id: self.id.clone(),
+            unknown: self.unknown,
         }
     }
 }
@@ -101,8 +147,31 @@ fn test_attributes() {
     data.extend_from_slice(&id);
     data.extend_from_slice(&unknown);
 
-    let attrs = Attributes::from_cf(&data[..]);
+    let attrs = Attributes::from_cf(&data[..]).unwrap();
     let test = attrs.for_cf();
 
     assert_eq!(data, test);
 }
+
+#[test]
+fn test_attributes_unknown_value() {
+    let mut data: Vec<u8> = vec![];
+    data.extend_from_slice(&[0xB0, 0x14, 0xC1, 0x30, 0x23, 0x42, 0x02, 0x00]);
+    data.extend_from_slice(&[0xC0, 0x15, 0xC1, 0x30, 0x23, 0x42, 0x02, 0x00]);
+    data.extend_from_slice(&[0xB1, 0x02, 0x00, 0x00]);
+    data.extend_from_slice(&[0x72, 0x00, 0x6F, 0x00, 0x6F, 0x00, 0x74, 0x00]);
+    data.extend_from_slice(&[0x01, 0x02, 0x03, 0x04]);
+
+    let attrs = Attributes::from_cf(&data[..]).unwrap();
+    assert_eq!([0x01, 0x02, 0x03, 0x04], attrs.unknown());
+    assert_eq!(GROUP_TYPE_FORM, attrs.group_type());
+    assert!(attrs.creation_date() != attrs.modification_date());
+    assert_eq!(data, attrs.for_cf());
+}
+
+#[test]
+fn test_attributes_too_small() {
+    let data = [0xB0, 0x14, 0xC1, 0x30, 0x23, 0x42, 0x02, 0x00];
+
+    assert!(Attributes::from_cf(&data[..]).is_err());
+}
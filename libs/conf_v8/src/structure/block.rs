@@ -2,16 +2,19 @@
 use zlib_wrapper;
 use file_system;
 
-use GROUP_BLOKS_FLAG;
 use structure::reader;
 use structure::header::Header;
 use structure::nested_block::NestedBlock;
 use structure::attributes::{Attributes, GROUP_TYPE_MODULE, GROUP_TYPE_FORM, GROUP_TYPE_SIMPLY};
 use structure::toc::TOC;
+use structure::source::SharedSource;
+use structure::layout::{ContainerLayout, ContainerFormat, PageSizes};
+use structure::manifest::BlockLayout;
+use error::{ConfError, attributes_error, inflate_error, io_error};
 use std::path::Path;
 use time;
 
-use std::cell::RefCell;
+use std::sync::{Mutex, MutexGuard};
 
 #[derive(Copy, Clone, PartialEq)]
 enum BlockType {
@@ -20,15 +23,36 @@ enum BlockType {
     Multiple, // составной тип блока
 }
 
+// Изменяемое состояние блока. Данные блока распаковываются при первом обращении к ним,
+// поэтому состояние защищено блокировкой, чтобы блоки можно было обрабатывать в нескольких потоках.
+#[derive(Clone)]
+struct BlockState {
+    block_type: BlockType, // тип блока
+    source_data: Vec<u8>, // Исходные сжатые данные блока (сохраняются после распаковки)
+    nested_blocks: Vec<NestedBlock>, // обработанные данные блока
+    container: ContainerLayout, // префикс и размер оглавления составного блока
+}
+
 // Блок конфигурационного файла включающий в себя две области:
 // 1. область атрибутов
 // 2. область данных
-#[derive(Clone)]
 pub struct Block {
-    block_type: RefCell<BlockType>, // тип блока
     attrs: Attributes, // атрибуты блока
-    source_data: RefCell<Vec<u8>>, // Исходные необработанные данные блока
-    nested_blocks: RefCell<Vec<NestedBlock>>, // обработанные данные блока
+    pages: PageSizes, // полные размеры областей атрибутов и данных блока
+    source: Option<(SharedSource, Header)>, /* Источник и заголовок данных блока, если данные
+                                             * читаются из источника при первом обращении */
+    state: Mutex<BlockState>, // тип и данные блока
+}
+
+impl Clone for Block {
+    fn clone(&self) -> Block {
+        Block {
+            attrs: self.attrs.clone(),
+            pages: self.pages,
+            source: self.source.clone(),
+            state: Mutex::new(self.state().clone()),
+        }
+    }
 }
 
 impl Block {
@@ -37,62 +61,172 @@ impl Block {
         let attrs = Attributes::new(0, 0, &String::from(name));
 
         Block {
-            block_type: RefCell::new(BlockType::Simply),
             attrs: attrs.clone(),
-            source_data: RefCell::new(Vec::new()),
-            nested_blocks: RefCell::new(vec![NestedBlock::new(&attrs, data)]),
+            pages: PageSizes::new(),
+            source: None,
+            state: Mutex::new(BlockState {
+                block_type: BlockType::Simply,
+                source_data: Vec::new(),
+                nested_blocks: vec![NestedBlock::new(&attrs, data)],
+                container: ContainerLayout::new(),
+            }),
         }
     }
 
     // Создать новый блок на основании данных конфигурационного файла
-    pub fn from_cf(source_data: &Vec<u8>, attrs_header: &Header, data_header: &Header) -> Block {
+    pub fn from_cf(source_data: &Vec<u8>,
+                   attrs_header: &Header,
+                   data_header: &Header)
+                   -> Result<Block, ConfError> {
 
         trace!("Init block from cf");
 
-        let (attrs, data) = get_attrs_and_data(source_data, attrs_header, data_header);
+        let (attrs, data) = get_attrs_and_data(source_data, attrs_header, data_header)?;
 
         trace!("Block name: {}", attrs.id());
 
         let retval = Block {
-            block_type: RefCell::new(BlockType::FromCf),
             attrs: attrs,
-            source_data: RefCell::new(data),
-            nested_blocks: RefCell::new(Vec::new()),
+            pages: page_sizes(attrs_header, data_header),
+            source: None,
+            state: Mutex::new(BlockState {
+                block_type: BlockType::FromCf,
+                source_data: data,
+                nested_blocks: Vec::new(),
+                container: ContainerLayout::new(),
+            }),
         };
         trace!("-Init block from cf");
 
-        retval
+        Ok(retval)
+    }
+
+    // Создать новый блок на основании источника данных конфигурационного файла.
+    // Сразу читаются только атрибуты блока и заголовок области данных, данные читаются
+    // при первом обращении к ним.
+    pub fn from_source(source: &SharedSource,
+                       attrs_header: &Header,
+                       data_header_pos: i64)
+                       -> Result<Block, ConfError> {
+
+        trace!("Init block from the source");
+
+        let header_position = attrs_header.region_position() - attrs_header.size();
+        let attrs_data = reader::read_block(source, attrs_header)?;
+
+        if !is_attrs_data(&attrs_data) {
+            return Err(attributes_error(header_position,
+                                        "Failed initializing a header of attributes."));
+        }
+
+        let attrs = match Attributes::from_cf(&attrs_data) {
+            Ok(v) => v,
+            Err(e) => return Err(attributes_error(header_position, e)),
+        };
+
+        trace!("Block name: {}", attrs.id());
+
+        let data_header = match Header::from_source(source, data_header_pos, attrs_header.format()) {
+            Ok(v) => v,
+            Err(e) => return Err(e.at_block(attrs.id())),
+        };
+
+        let retval = Block {
+            attrs: attrs,
+            pages: page_sizes(attrs_header, &data_header),
+            source: Some((source.clone(), data_header)),
+            state: Mutex::new(BlockState {
+                block_type: BlockType::FromCf,
+                source_data: Vec::new(),
+                nested_blocks: Vec::new(),
+                container: ContainerLayout::new(),
+            }),
+        };
+        trace!("-Init block from the source");
+
+        Ok(retval)
     }
 
-    // Инициализировать блок из данных сохраненных в файлы
-    pub fn from_file(path: &String) -> Block {
+    // Инициализировать блок из данных сохраненных в файлы.
+    // Если при распаковке было сохранено описание блока, то атрибуты блока, размеры областей
+    // и порядок вложенных блоков восстанавливаются по описанию.
+    pub fn from_file(path: &String, layout: Option<&BlockLayout>) -> Result<Block, ConfError> {
 
         trace!("Init block from the file: {}.", path);
 
+        let block_id = file_system::file_name(path);
+        let is_group = file_system::is_dir(path);
+
+        let mut nested_data: Vec<(String, Vec<u8>)> = Vec::new();
+
+        if is_group {
+            for (nested_block_id, nested_block_path) in &file_system::files_in_dir(path) {
+                let nested_block_data = match file_system::read_file(nested_block_path) {
+                    Ok(v) => v,
+                    Err(e) => return Err(io_error(nested_block_path, e)),
+                };
+                nested_data.push((nested_block_id.clone(), nested_block_data));
+            }
+        } else {
+            let block_data = match file_system::read_file(path) {
+                Ok(v) => v,
+                Err(e) => return Err(io_error(path, e)),
+            };
+            nested_data.push((block_id.clone(), block_data));
+        }
+
+        let retval = Block::from_parts(&block_id, is_group, nested_data, layout);
+
+        trace!("-Init block from the file.");
+
+        return Ok(retval);
+    }
+
+    // Инициализировать блок из данных вложенных блоков. У простого блока один вложенный блок.
+    // Если есть описание блока, то атрибуты блока, размеры областей и порядок вложенных
+    // блоков восстанавливаются по описанию, вложенные блоки без описания добавляются в конец.
+    pub fn from_parts(block_id: &str,
+                      is_group: bool,
+                      nested_data: Vec<(String, Vec<u8>)>,
+                      layout: Option<&BlockLayout>)
+                      -> Block {
+
         let mut block_type: BlockType = BlockType::Simply;
         let mut group_type: i32 = GROUP_TYPE_SIMPLY;
         let mut nested_blocks: Vec<NestedBlock> = Vec::new();
         let current_time = time::now().tm_nsec as u64;
-        let block_id = file_system::file_name(path);
 
-        if file_system::is_dir(path) {
+        if is_group {
 
             block_type = BlockType::Multiple;
 
-            for (nested_block_id, nested_block_path) in &file_system::files_in_dir(path) {
-                let nested_block_data = match file_system::read_file(nested_block_path) {
-                    Ok(v) => v,
-                    Err(e) => {
-                        error!("{}", e);
-                        panic!("Error initialize multiple block from the file {}", e);
+            // Сначала вложенные блоки из описания, затем добавленные после распаковки
+            let mut nested_data = nested_data;
+            if let Some(layout) = layout {
+                let mut ordered_data: Vec<(String, Vec<u8>)> = Vec::new();
+                for nested_layout in &layout.nested {
+                    let position = nested_data.iter().position(|v| v.0.eq(nested_layout.attrs.id()));
+                    if let Some(position) = position {
+                        ordered_data.push(nested_data.remove(position));
                     }
-                };
+                }
+                ordered_data.append(&mut nested_data);
+                nested_data = ordered_data;
+            }
 
-                let nested_block_attrs =
-                    Attributes::new(current_time, GROUP_TYPE_SIMPLY, &nested_block_id);
-                nested_blocks.push(NestedBlock::new(&nested_block_attrs, &nested_block_data));
+            for (nested_block_id, nested_block_data) in nested_data {
+                let nested_layout = layout.and_then(|v| v.nested_block(&nested_block_id));
+                let nested_block = match nested_layout {
+                    Some(v) => NestedBlock::with_pages(&v.attrs, &nested_block_data, v.pages),
+                    None => {
+                        let nested_block_attrs =
+                            Attributes::new(current_time, GROUP_TYPE_SIMPLY, &nested_block_id);
+                        NestedBlock::new(&nested_block_attrs, &nested_block_data)
+                    }
+                };
+                nested_blocks.push(nested_block);
 
-                match &*(*nested_block_id) {
+                match &*nested_block_id {
                     "module" => group_type = GROUP_TYPE_MODULE,
                     "form" => group_type = GROUP_TYPE_FORM,
                     _ => continue,
@@ -100,59 +234,65 @@ impl Block {
             }
 
         } else {
-            let nested_block_data = match file_system::read_file(path) {
-                Ok(v) => v,
-                Err(e) => {
-                    error!("{}", e);
-                    panic!("Error initialize simple block from the file {}", e);
-                }
-            };
-
-            let nested_block_attrs = Attributes::new(current_time, GROUP_TYPE_SIMPLY, &block_id);
-            nested_blocks.push(NestedBlock::new(&nested_block_attrs, &nested_block_data));
+            let block_id = String::from(block_id);
+            for (_, nested_block_data) in nested_data {
+                let nested_block_attrs = Attributes::new(current_time, GROUP_TYPE_SIMPLY, &block_id);
+                nested_blocks.push(NestedBlock::new(&nested_block_attrs, &nested_block_data));
+            }
         }
 
-        let retval = Block {
-            block_type: RefCell::new(block_type), // тип блока
-            attrs: Attributes::new(current_time, group_type, &block_id), /* атрибуты блока */
-            source_data: RefCell::new(Vec::new()), /* Исходные необработанные данные блока */
-            nested_blocks: RefCell::new(nested_blocks), /* подчиненные блоки (если это составной блок) */
+        let (attrs, pages, container) = match layout {
+            Some(v) => {
+                (v.attrs.clone(),
+                 v.pages,
+                 v.container.clone().unwrap_or(ContainerLayout::new()))
+            }
+            None => {
+                (Attributes::new(current_time, group_type, &String::from(block_id)),
+                 PageSizes::new(),
+                 ContainerLayout::new())
+            }
         };
 
-        trace!("-Init block from the file.");
-
-        return retval;
+        return Block {
+            attrs: attrs, // атрибуты блока
+            pages: pages, // полные размеры областей блока
+            source: None,
+            state: Mutex::new(BlockState {
+                block_type: block_type, // тип блока
+                source_data: Vec::new(), // Исходные необработанные данные блока
+                nested_blocks: nested_blocks, // подчиненные блоки (если это составной блок)
+                container: container, // префикс и размер оглавления составного блока
+            }),
+        };
     }
 
     // Возвращает данные блока
-    pub fn get_data(&self) -> Vec<NestedBlock> {
-        self.decompress_data();
-        if BlockType::Simply.ne(&self.block_type.borrow()) &&
-           BlockType::Multiple.ne(&self.block_type.borrow()) {
-            error!("Trying to getting an untreated data block.");
-            panic!("Trying to getting an untreated data block.")
-        }
+    pub fn get_data(&self) -> Result<Vec<NestedBlock>, ConfError> {
+        let mut state = self.state();
+        self.decompress_data(&mut state)?;
+
         let mut retval: Vec<NestedBlock> = Vec::new();
 
-        for sb in self.nested_blocks.borrow().iter() {
+        for sb in state.nested_blocks.iter() {
             retval.push(NestedBlock::new(&sb.attrs, &sb.data));
         }
-        return retval;
+        return Ok(retval);
     }
 
     // Устанавливает данные вложенного блока. Если блок простой, то у него есть только один вложенный блок
     // имя которого соотвествует имени самого блока
-    pub fn set_data(&self, nested_block_name: &String, nested_block_data: &Vec<u8>) {
+    pub fn set_data(&self,
+                    nested_block_name: &String,
+                    nested_block_data: &Vec<u8>)
+                    -> Result<(), ConfError> {
 
-        if BlockType::Simply.ne(&self.block_type.borrow()) &&
-           BlockType::Multiple.ne(&self.block_type.borrow()) {
-            error!("Trying to getting an untreated data block.");
-            panic!("Trying to getting an untreated data block.")
-        }
+        let mut state = self.state();
+        self.decompress_data(&mut state)?;
 
         let mut is_find = false;
 
-        for sb in self.nested_blocks.borrow_mut().iter_mut() {
+        for sb in state.nested_blocks.iter_mut() {
             if sb.attrs.id().eq(&*(*nested_block_name)) {
                 sb.data.clear();
                 sb.data.extend_from_slice(&nested_block_data[..]);
@@ -162,49 +302,48 @@ impl Block {
         }
 
         if !is_find {
-            error!("Failed set new data. Block id - '{}' nested block name - '{}'",
-                   &self.id(),
-                   nested_block_name);
-            panic!("Failed set new data. Block id - '{}' nested block name - '{}'",
-                   &self.id(),
-                   nested_block_name);
+            return Err(inflate_error(self.id(),
+                                     format!("Failed set new data. Nested block name - '{}'",
+                                             nested_block_name)));
         }
+
+        Ok(())
     }
 
     // Получить данные блока для конфигурационного файла.
-    pub fn for_cf(&self) -> (Vec<u8>, Vec<u8>) {
+    pub fn for_cf(&self) -> Result<(Vec<u8>, Vec<u8>), ConfError> {
 
+        let state = self.state();
         let mut data: Vec<u8> = Vec::new();
 
-        match *(self.block_type.borrow()) {
+        match state.block_type {
             BlockType::FromCf => {
-                data.extend_from_slice(&(self.source_data.borrow()));
+                data.append(&mut self.compressed_data(&state)?);
             }
             BlockType::Simply => {
-                for sb in self.nested_blocks.borrow().iter() {
+                for sb in state.nested_blocks.iter() {
                     data.extend_from_slice(&sb.data);
                 }
             }
             BlockType::Multiple => {
-                let mut toc = TOC::new();
+                let mut toc = TOC::with_layout(&state.container);
+                let format = state.container.format();
                 let mut block_data: Vec<u8> = Vec::new();
 
-                for sb in self.nested_blocks.borrow().iter() {
-                    let sb_attrs_data = sb.attrs.for_cf();
-
-                    let mut nested_block_data: Vec<u8> = Vec::new();
-                    nested_block_data.extend_from_slice(&Header::for_cf(sb_attrs_data.len()));
-                    nested_block_data.extend_from_slice(&sb_attrs_data);
+                for sb in state.nested_blocks.iter() {
+                    let mut nested_block_data: Vec<u8> =
+                        Header::region_for_cf(&sb.attrs.for_cf(), sb.pages.attrs, format);
 
-                    let data_header_pos = nested_block_data.len() as i32;
+                    let data_header_pos = nested_block_data.len() as i64;
 
-                    nested_block_data.extend_from_slice(&Header::for_cf(sb.data.len()));
-                    nested_block_data.extend_from_slice(&sb.data);
+                    nested_block_data.extend_from_slice(&Header::region_for_cf(&sb.data,
+                                                                               sb.pages.data,
+                                                                               format));
 
-                    let header_sb_attr = Header::from_cf(&nested_block_data, 0);
-                    let header_sb_data = Header::from_cf(&nested_block_data, data_header_pos);
+                    let header_sb_attr = Header::from_cf(&nested_block_data, 0)?;
+                    let header_sb_data = Header::from_cf(&nested_block_data, data_header_pos)?;
 
-                    toc.add(&header_sb_attr, &header_sb_data);
+                    toc.add(&header_sb_attr, &header_sb_data)?;
                     block_data.append(&mut nested_block_data);
                 }
 
@@ -213,24 +352,39 @@ impl Block {
             }
         }
 
-        if !data.is_empty() && BlockType::FromCf.ne(&self.block_type.borrow()) {
-            data = zlib_wrapper::compress(&data);
+        if !data.is_empty() && BlockType::FromCf.ne(&state.block_type) {
+            data = self.compress_data(&state, &data)?;
         }
 
-        return (self.attrs.for_cf(), data);
+        return Ok((self.attrs.for_cf(), data));
+    }
+
+    // Возвращает исходные сжатые данные блока. Если блок собран из файлов и исходные данные
+    // не были установлены, то возвращается пустой вектор.
+    pub fn original_data(&self) -> Result<Vec<u8>, ConfError> {
+        let state = self.state();
+        return self.compressed_data(&state);
+    }
+
+    // Установить исходные сжатые данные блока, собранного из файлов. Если данные блока
+    // не изменились, то при сборке используются исходные сжатые данные.
+    pub fn set_original_data(&self, data: Vec<u8>) {
+        if self.source.is_none() {
+            self.state().source_data = data;
+        }
     }
 
     // Записать данные блока в файлы
-    pub fn write_to_file(&self, path_to_dir: &String) {
+    pub fn write_to_file(&self, path_to_dir: &String) -> Result<(), ConfError> {
 
         trace!("Write block to the file.");
 
-        let nested_blocks = self.get_data();
+        let nested_blocks = self.get_data()?;
 
-        let path_to_block_dir: String = match *(self.block_type.borrow()) {
+        let block_type = self.state().block_type;
+        let path_to_block_dir: String = match block_type {
             BlockType::FromCf => {
-                error!("Error recording unprocessed block.");
-                panic!("Error recording unprocessed block.");
+                return Err(inflate_error(self.id(), "Error recording unprocessed block."));
             }
             BlockType::Simply => path_to_dir.clone(),
             BlockType::Multiple => {
@@ -250,14 +404,13 @@ impl Block {
 
             match file_system::write_file(&*file_name_str, &sb.data) {
                 Ok(_) => (),
-                Err(e) => {
-                    error!("Error writing block to the file: {}", e);
-                    panic!("Error writing block to the file: {}", e);
-                }
+                Err(e) => return Err(io_error(&file_name_str, e)),
             };
         }
 
         trace!("-Write block to the file.");
+
+        Ok(())
     }
 
     // Получить наименование блока
@@ -265,47 +418,159 @@ impl Block {
         return self.attrs.id();
     }
 
+    // Возвращает полные размеры областей атрибутов и данных блока
+    pub fn pages(&self) -> PageSizes {
+        return self.pages;
+    }
+
+    // Возвращает описание блока для побайтового восстановления конфигурационного файла
+    pub fn layout(&self) -> Result<BlockLayout, ConfError> {
+
+        let mut state = self.state();
+        self.decompress_data(&mut state)?;
+
+        let mut retval = BlockLayout {
+            attrs: self.attrs.clone(),
+            pages: self.pages,
+            container: None,
+            nested: Vec::new(),
+        };
+
+        if BlockType::Multiple.eq(&state.block_type) {
+            retval.container = Some(state.container.clone());
+
+            for sb in state.nested_blocks.iter() {
+                retval.nested.push(BlockLayout {
+                    attrs: sb.attrs.clone(),
+                    pages: sb.pages,
+                    container: None,
+                    nested: Vec::new(),
+                });
+            }
+        }
+
+        return Ok(retval);
+    }
+
+    // Установить формат адресов оглавления составного блока. Данные блока распаковываются,
+    // чтобы при сборке оглавление было записано в новом формате.
+    pub fn set_container_format(&self, format: ContainerFormat) -> Result<(), ConfError> {
+
+        let mut state = self.state();
+        self.decompress_data(&mut state)?;
+
+        if state.block_type == BlockType::Multiple && state.container.format() != format {
+            state.container = ContainerLayout {
+                toc_page_size: state.container.toc_page_size,
+                ..ContainerLayout::with_format(format)
+            };
+        }
+
+        Ok(())
+    }
+
     // Распаковывает данные блоков если они были получены из конфигурационного файла и не распакованы ранее.
-    fn decompress_data(&self) {
+    fn decompress_data(&self, state: &mut BlockState) -> Result<(), ConfError> {
 
-        if BlockType::FromCf.ne(&self.block_type.borrow()) {
-            return;
+        if BlockType::FromCf.ne(&state.block_type) {
+            return Ok(());
         }
 
+        let compressed_data = self.compressed_data(state)?;
+
         let mut block_data: Vec<u8> = Vec::new();
-        if !self.source_data.borrow().is_empty() {
-            let mut decompress_data = zlib_wrapper::decompress(&self.source_data.borrow());
+        if !compressed_data.is_empty() {
+            let mut decompress_data = match zlib_wrapper::decompress(&compressed_data) {
+                Ok(v) => v,
+                Err(e) => {
+                    let error = inflate_error(self.id(), e);
+                    return Err(match self.source {
+                        Some((_, ref data_header)) => error.at_position(data_header.region_position()),
+                        None => error,
+                    });
+                }
+            };
             block_data.append(&mut decompress_data);
         }
 
-        match TOC::from_cf(&block_data) {
+        // Позиции ошибок оглавления и вложенных блоков указываются в распакованных данных блока
+        let toc = match TOC::from_cf(&block_data) {
+            Ok(v) => v,
+            Err(e) => return Err(e.at_block(self.id())),
+        };
+
+        match toc {
             None => {
-                *self.block_type.borrow_mut() = BlockType::Simply;
-                self.nested_blocks.borrow_mut().push(NestedBlock::new(&self.attrs, &block_data));
+                state.block_type = BlockType::Simply;
+                state.nested_blocks.push(NestedBlock::new(&self.attrs, &block_data));
             }
             Some(toc) => {
-                if block_data[0..GROUP_BLOKS_FLAG.len()] != GROUP_BLOKS_FLAG {
-                    error!("Flag of the group block is not found.");
-                    panic!("Flag of the group block is not found.");
+                if !block_data.starts_with(toc.layout().format().group_flag()) {
+                    return Err(inflate_error(self.id(), "Flag of the group block is not found."));
                 }
 
-                *self.block_type.borrow_mut() = BlockType::Multiple;
-                self.nested_blocks.borrow_mut().clear();
+                let mut nested_blocks: Vec<NestedBlock> = Vec::new();
 
                 for address in toc.addresses() {
-                    let attr_header = Header::from_cf(&block_data, address.attr_header_pos());
-                    let data_header = Header::from_cf(&block_data, address.data_header_pos());
-
-                    let (nested_block_attrs, nested_block_data) =
-                        get_attrs_and_data(&block_data, &attr_header, &data_header);
-                    self.nested_blocks
-                        .borrow_mut()
-                        .push(NestedBlock::new(&nested_block_attrs, &nested_block_data));
+                    let (nested_block_attrs, nested_block_data, nested_block_pages) =
+                        match nested_attrs_and_data(&block_data,
+                                                    address.attr_header_pos(),
+                                                    address.data_header_pos()) {
+                            Ok(v) => v,
+                            Err(e) => return Err(e.at_block(self.id())),
+                        };
+                    nested_blocks.push(NestedBlock::with_pages(&nested_block_attrs,
+                                                               &nested_block_data,
+                                                               nested_block_pages));
+                }
+
+                state.block_type = BlockType::Multiple;
+                state.nested_blocks = nested_blocks;
+                state.container = toc.layout().clone();
+            }
+        }
+
+        Ok(())
+    }
+
+    // Сжать данные блока. Если исходные сжатые данные распаковываются в те же самые данные,
+    // то возвращаются исходные данные: сжатие 1С не совпадает побайтово со сжатием zlib.
+    fn compress_data(&self, state: &BlockState, data: &Vec<u8>) -> Result<Vec<u8>, ConfError> {
+
+        let original_data = self.compressed_data(state)?;
+        if !original_data.is_empty() {
+            match zlib_wrapper::decompress(&original_data) {
+                Ok(ref v) if v.eq(data) => return Ok(original_data),
+                _ => (),
+            }
+        }
+
+        match zlib_wrapper::compress(data) {
+            Ok(v) => Ok(v),
+            Err(e) => Err(inflate_error(self.id(), e)),
+        }
+    }
+
+    // Возвращает исходные сжатые данные блока. Если блок получен из источника,
+    // то данные читаются из источника.
+    fn compressed_data(&self, state: &BlockState) -> Result<Vec<u8>, ConfError> {
+        match self.source {
+            Some((ref source, ref data_header)) => {
+                match reader::read_block(source, data_header) {
+                    Ok(v) => Ok(v),
+                    Err(e) => Err(e.at_block(self.id())),
                 }
             }
+            None => Ok(state.source_data.clone()),
         }
+    }
 
-        self.source_data.borrow_mut().clear();
+    // Возвращает состояние блока
+    fn state<'a>(&'a self) -> MutexGuard<'a, BlockState> {
+        match self.state.lock() {
+            Ok(v) => v,
+            Err(e) => e.into_inner(),
+        }
     }
 
     // TODO
@@ -350,39 +615,67 @@ impl Block {
 fn get_attrs_and_data(source_data: &Vec<u8>,
                       attrs_header: &Header,
                       data_header: &Header)
-                      -> (Attributes, Vec<u8>) {
+                      -> Result<(Attributes, Vec<u8>), ConfError> {
 
-    let attrs = get_attr(source_data, attrs_header);
-    let mut block_data: Vec<u8> = Vec::new();
-    let mut header = data_header.clone();
+    let attrs = get_attr(source_data, attrs_header)?;
+    let block_data = reader::get_block(source_data, data_header)?;
 
-    loop {
-        block_data.extend_from_slice(reader::get_region(source_data, &header));
+    return Ok((attrs, block_data));
+}
 
-        match header.next_header_position() {
-            Some(pos) => header = Header::from_cf(source_data, pos),
-            None => break,
-        }
+// Получить данные атрибутов, данных и размеры областей вложенного блока по позициям заголовков
+fn nested_attrs_and_data(source_data: &Vec<u8>,
+                         attrs_header_pos: i64,
+                         data_header_pos: i64)
+                         -> Result<(Attributes, Vec<u8>, PageSizes), ConfError> {
+
+    let attrs_header = Header::from_cf(source_data, attrs_header_pos)?;
+    let data_header = Header::from_cf(source_data, data_header_pos)?;
+
+    let (attrs, data) = get_attrs_and_data(source_data, &attrs_header, &data_header)?;
 
+    Ok((attrs, data, page_sizes(&attrs_header, &data_header)))
+}
+
+// Возвращает полные размеры областей блока по заголовкам атрибутов и данных
+fn page_sizes(attrs_header: &Header, data_header: &Header) -> PageSizes {
+    PageSizes {
+        attrs: attrs_header.full_region_size(),
+        data: data_header.full_region_size(),
     }
+}
 
-    return (attrs, block_data);
+// Проверить, что данные являются данными атрибутов блока
+fn is_attrs_data(data: &Vec<u8>) -> bool {
+
+    const MIN_BLOCK_SIZE: usize = 21; // второй символ в имени блока
+    if data.len() <= MIN_BLOCK_SIZE {
+        return false;
+    }
+
+    return    data[7]  == 0x00  // последний символ в значении даты модификации
+           && data[15] == 0x00  // последний символ в значении даты создания
+           && data[19] == 0x00  // последний символ в значении типа блока
+           && data[21] == 0x00; // второй символ в имени блока
 }
 
 // Получить данные атрибутов на основании заголовка
-fn get_attr(source_data: &Vec<u8>, header: &Header) -> Attributes {
+fn get_attr(source_data: &Vec<u8>, header: &Header) -> Result<Attributes, ConfError> {
+
+    let header_position = header.region_position() - header.size();
 
     let is_attrs_header = header.is_region_of_attrs(&source_data);
     if !is_attrs_header {
-        error!("Failed initializing a header of attributes: position={}",
-               header.region_position());
-        panic!("Failed initializing a header of attributes: position={}",
-               header.region_position());
+        return Err(attributes_error(header_position,
+                                    "Failed initializing a header of attributes."));
     }
 
-    let attrs_data = reader::get_region(source_data, header);
+    let attrs_data = reader::get_region(source_data, header)?;
 
-    return Attributes::from_cf(attrs_data);
+    match Attributes::from_cf(attrs_data) {
+        Ok(v) => Ok(v),
+        Err(e) => Err(attributes_error(header_position, e)),
+    }
 }
 
 #[test]
@@ -406,14 +699,16 @@ fn test_simple_block_from_cf() {
     block_data.extend_from_slice(&data_header);
     block_data.extend_from_slice(&data);
 
-    let header_attr_in_block = Header::from_cf(&block_data, 0);
+    let header_attr_in_block = Header::from_cf(&block_data, 0).unwrap();
     let header_data_in_block = Header::from_cf(&block_data,
-                                               (attr_header.len() + attrs.len()) as i32);
+                                               (attr_header.len() + attrs.len()) as i64)
+        .unwrap();
 
-    let test = Block::from_cf(&block_data, &header_attr_in_block, &header_data_in_block);
+    let test = Block::from_cf(&block_data, &header_attr_in_block, &header_data_in_block)
+        .unwrap();
 
-    let nested_blocks = test.get_data();
-    let (new_block_attrs, new_block_data) = test.for_cf();
+    let nested_blocks = test.get_data().unwrap();
+    let (new_block_attrs, new_block_data) = test.for_cf().unwrap();
 
     assert_eq!(1, nested_blocks.len());
     assert_eq!(attrs, new_block_attrs);
@@ -445,19 +740,20 @@ fn test_multi_block_from_cf() {
     block_data.extend_from_slice(&data_header);
     block_data.extend_from_slice(&data);
 
-    let header_attr_in_block = Header::from_cf(&block_data, 0);
+    let header_attr_in_block = Header::from_cf(&block_data, 0).unwrap();
     let header_data_in_block = Header::from_cf(&block_data,
-                                               (attr_header.len() + attrs.len()) as i32);
+                                               (attr_header.len() + attrs.len()) as i64)
+        .unwrap();
 
     let mut toc = TOC::new();
-    toc.add(&header_attr_in_block, &header_data_in_block);
-    toc.add(&header_attr_in_block, &header_data_in_block);
+    toc.add(&header_attr_in_block, &header_data_in_block).unwrap();
+    toc.add(&header_attr_in_block, &header_data_in_block).unwrap();
 
     let mut data_multi_block = toc.for_cf();
 
     data_multi_block.extend_from_slice(&block_data);
     data_multi_block.extend_from_slice(&block_data);
-    data_multi_block = zlib_wrapper::compress(&data_multi_block);
+    data_multi_block = zlib_wrapper::compress(&data_multi_block).unwrap();
 
     let data_multi_block_header = Header::for_cf(data_multi_block.len());
 
@@ -467,17 +763,85 @@ fn test_multi_block_from_cf() {
     multi_block.extend_from_slice(&data_multi_block_header);
     multi_block.extend_from_slice(&data_multi_block);
 
-    let header_attr_in_multi_block = Header::from_cf(&multi_block, 0);
+    let header_attr_in_multi_block = Header::from_cf(&multi_block, 0).unwrap();
     let header_data_in_multi_block = Header::from_cf(&multi_block,
-                                                     (attr_header.len() + attrs.len()) as i32);
+                                                     (attr_header.len() + attrs.len()) as i64)
+        .unwrap();
 
     let test = Block::from_cf(&multi_block,
                               &header_attr_in_multi_block,
-                              &header_data_in_multi_block);
-    let nested_blocks = test.get_data();
-    let (new_block_attrs, new_block_data) = test.for_cf();
+                              &header_data_in_multi_block)
+        .unwrap();
+    let nested_blocks = test.get_data().unwrap();
+    let (new_block_attrs, new_block_data) = test.for_cf().unwrap();
 
     assert_eq!(2, nested_blocks.len());
     assert_eq!(attrs, new_block_attrs);
     assert_eq!(data_multi_block, new_block_data);
 }
+
+#[test]
+fn test_multi_block_nested_error() {
+    use zlib_wrapper;
+    use error::Stage;
+
+    let mut attrs: Vec<u8> = Vec::new();
+    attrs.extend_from_slice(&[0xB0, 0x14, 0xC1, 0x30, 0x23, 0x42, 0x02, 0x00]);
+    attrs.extend_from_slice(&[0xB0, 0x14, 0xC1, 0x30, 0x23, 0x42, 0x02, 0x00]);
+    attrs.extend_from_slice(&[0xE4, 0x02, 0x00, 0x00]);
+    attrs.extend_from_slice(&[0x72, 0x00, 0x6F, 0x00, 0x6F, 0x00, 0x74, 0x00]); // root
+    attrs.extend_from_slice(&[0x00, 0x00, 0x00, 0x00]);
+
+    let attr_header = Header::for_cf(attrs.len());
+
+    let data: Vec<u8> = vec![0x68, 0x65, 0x6C, 0x6C, 0x6F]; // hello
+    let data_header = Header::for_cf(data.len());
+
+    let mut block_data: Vec<u8> = Vec::new();
+    block_data.extend_from_slice(&attr_header);
+    block_data.extend_from_slice(&attrs);
+    block_data.extend_from_slice(&data_header);
+    block_data.extend_from_slice(&data);
+
+    let header_attr_in_block = Header::from_cf(&block_data, 0).unwrap();
+    let header_data_in_block = Header::from_cf(&block_data,
+                                               (attr_header.len() + attrs.len()) as i64)
+        .unwrap();
+
+    // атрибуты второго вложенного блока повреждены
+    let mut broken_block_data = block_data.clone();
+    broken_block_data[attr_header.len() + 7] = 0x01;
+
+    let mut toc = TOC::new();
+    toc.add(&header_attr_in_block, &header_data_in_block).unwrap();
+    toc.add(&header_attr_in_block, &header_data_in_block).unwrap();
+
+    let mut data_multi_block = toc.for_cf();
+
+    data_multi_block.extend_from_slice(&block_data);
+    data_multi_block.extend_from_slice(&broken_block_data);
+    data_multi_block = zlib_wrapper::compress(&data_multi_block).unwrap();
+
+    let data_multi_block_header = Header::for_cf(data_multi_block.len());
+
+    let mut multi_block: Vec<u8> = Vec::new();
+    multi_block.extend_from_slice(&attr_header);
+    multi_block.extend_from_slice(&attrs);
+    multi_block.extend_from_slice(&data_multi_block_header);
+    multi_block.extend_from_slice(&data_multi_block);
+
+    let header_attr_in_multi_block = Header::from_cf(&multi_block, 0).unwrap();
+    let header_data_in_multi_block = Header::from_cf(&multi_block,
+                                                     (attr_header.len() + attrs.len()) as i64)
+        .unwrap();
+
+    let test = Block::from_cf(&multi_block,
+                              &header_attr_in_multi_block,
+                              &header_data_in_multi_block)
+        .unwrap();
+    let e = test.get_data().err().unwrap();
+
+    assert_eq!(Stage::Attributes, e.stage());
+    assert_eq!(Some("root"), e.block_id());
+    assert!(e.position().is_some());
+}
@@ -1,125 +1,153 @@
 
 use conv;
+use structure::layout::ContainerFormat;
+#[cfg(test)]
 use structure::header::Header;
-use std::i32::MAX;
+use error::{ConfError, toc_error};
+#[cfg(test)]
+use error::Stage;
 
 // Адреса областей атрибутов и данных одного блока для оглавления конфигурационного файла
 #[derive(Clone)]
 pub struct BlockAddress {
-    attrs_header_pos: i32, // Позиция заголовка атрибутов блока
-    data_header_pos: i32, // Позиция заголовка данных блока
-    data_size: i32, // Размер полезных данных блока
+    attrs_header_pos: i64, // Позиция заголовка атрибутов блока
+    data_header_pos: i64, // Позиция заголовка данных блока
+    data_size: i64, // Размер полезных данных блока
+    format: ContainerFormat, // Формат адресов оглавления
 }
 
 impl BlockAddress {
     // Получить адреса атрибутоы и данных блока, используется при создании нового конфигурационного файла
-    pub fn new(_attrs_header_pos: i32, _data_header_pos: i32, _data_size: i32) -> BlockAddress {
+    pub fn new(_attrs_header_pos: i64,
+               _data_header_pos: i64,
+               _data_size: i64,
+               _format: ContainerFormat)
+               -> BlockAddress {
         return BlockAddress {
             attrs_header_pos: _attrs_header_pos,
             data_header_pos: _data_header_pos,
             data_size: _data_size,
+            format: _format,
         };
     }
 
     // Инициализировать данные адреса на основании данных конфигурационного файла
-    pub fn from_cf(data: &Vec<u8>, start_pos: usize) -> Option<BlockAddress> {
+    pub fn from_cf(data: &Vec<u8>,
+                   start_pos: usize,
+                   format: ContainerFormat)
+                   -> Result<Option<BlockAddress>, ConfError> {
 
-        let mut values: Vec<i32> = Vec::new();
+        let element_size = BlockAddress::element_size(format);
+        let mut values: Vec<i64> = Vec::new();
         let mut end_region_pos: usize = 0;
 
         for i in 0..3 {
-            let start_region_pos = start_pos + i * BlockAddress::element_size();
-            end_region_pos = start_region_pos + BlockAddress::element_size();
+            let start_region_pos = start_pos + i * element_size;
+            end_region_pos = start_region_pos + element_size;
+
+            if end_region_pos > data.len() {
+                return Err(toc_error(start_pos as i64,
+                                     format!("Address is out of the table of contents: \
+                                              size={}; end_pos={}",
+                                             data.len(),
+                                             end_region_pos)));
+            }
 
-            let val = conv::bytes_to_int32(&data[start_region_pos..end_region_pos]) as i32;
+            let val = match format {
+                ContainerFormat::Bits32 => {
+                    conv::bytes_to_int32(&data[start_region_pos..end_region_pos]) as u32 as u64
+                }
+                ContainerFormat::Bits64 => {
+                    conv::bytes_to_int64(&data[start_region_pos..end_region_pos])
+                }
+            };
 
-            if val == MAX {
+            if val == format.empty_value() {
                 break;
             } else if val == 0 {
 
                 if i == 0 {
-                    return None;
+                    return Ok(None);
                 } else {
-                    error!("Error creation a address object: start_pos={}; end_pos={}; data={:?}",
-                           start_pos,
-                           end_region_pos,
-                           &data[start_pos..end_region_pos]);
-                    panic!("Error creation a address object: start_pos={}; end_pos={}; data={:?}",
-                           start_pos,
-                           end_region_pos,
-                           &data[start_pos..end_region_pos]);
+                    return Err(toc_error(start_pos as i64,
+                                         format!("Error creation a address object: end_pos={}; \
+                                                  data={:?}",
+                                                 end_region_pos,
+                                                 &data[start_pos..end_region_pos])));
                 }
 
             }
 
-            values.push(val);
+            values.push(val as i64);
         }
 
-        return match values.len() {
-            2 => {
-                let data_header_pos = *values.get(1).unwrap();
+        if values.len() != 2 {
+            return Err(toc_error(start_pos as i64,
+                                 format!("Error creation a address object: end_pos={}; data={:?}",
+                                         end_region_pos,
+                                         &data[start_pos..end_region_pos])));
+        }
 
-                Some(BlockAddress {
-                    attrs_header_pos: *values.get(0).unwrap(),
-                    data_header_pos: data_header_pos,
-                    data_size: -1,
-                })
-            }
-            _ => {
-                error!("Error creation a address object: start_pos={}; end_pos={}; data={:?}",
-                       start_pos,
-                       end_region_pos,
-                       &data[start_pos..end_region_pos]);
-                panic!("Error creation a address object: start_pos={}; end_pos={}; data={:?}",
-                       start_pos,
-                       end_region_pos,
-                       &data[start_pos..end_region_pos]);
-            }
-        };
+        return Ok(Some(BlockAddress {
+            attrs_header_pos: values[0],
+            data_header_pos: values[1],
+            data_size: -1,
+            format: format,
+        }));
     }
 
     // Возвращает данные атрибутов для конфигурационного файла
     pub fn for_cf(&self, toc_len: usize) -> Vec<u8> {
 
         let mut data: Vec<u8> = Vec::new();
-        data.extend_from_slice(&conv::int32_to_bytes(self.attrs_header_pos + toc_len as i32));
-        data.extend_from_slice(&conv::int32_to_bytes(self.data_header_pos + toc_len as i32));
-        data.extend_from_slice(&[0xFF, 0xFF, 0xFF, 0x7F]); // &conv::int32_to_bytes(i32::max_value())
+        for value in [self.attrs_header_pos + toc_len as i64,
+                      self.data_header_pos + toc_len as i64]
+            .iter() {
+            match self.format {
+                ContainerFormat::Bits32 => {
+                    data.extend_from_slice(&conv::int32_to_bytes(*value as i32))
+                }
+                ContainerFormat::Bits64 => {
+                    data.extend_from_slice(&conv::int64_to_bytes(*value as u64))
+                }
+            }
+        }
+        data.extend_from_slice(self.format.group_flag()); // Разделитель адресов - пустое значение
 
         return data;
     }
 
     // Возвращает позицию заголовка атрибутов блока
-    pub fn attr_header_pos(&self) -> i32 {
+    pub fn attr_header_pos(&self) -> i64 {
         return self.attrs_header_pos;
     }
 
     // Возвращает позицию данных блока
-    pub fn data_header_pos(&self) -> i32 {
+    pub fn data_header_pos(&self) -> i64 {
         return self.data_header_pos;
     }
 
     // Возвращает количество байт одного из значений адреса таблицы оглавления:
-    // - 4 байта (Позиция заголовка атрибутов)
-    // - 4 байта (Позиция заголовка данных)
-    // - 4 байта (Разделитель адресов - emptyValue)
-    pub fn element_size() -> usize {
-        return 4; // = conv.int32_to_bytes(BlockAddress.attrs_header_pos).len()
+    // - 4 (8) байт (Позиция заголовка атрибутов)
+    // - 4 (8) байт (Позиция заголовка данных)
+    // - 4 (8) байт (Разделитель адресов - emptyValue)
+    pub fn element_size(format: ContainerFormat) -> usize {
+        return format.address_element_size();
     }
 
     // Возвращает размер одного адреса в таблице оглавления в байтах
-    pub fn size() -> usize {
-        return 12; // Позиция заголовка атрибутов + Позиция заголовка данных + Разделитель адресов = element_size() * 3
+    pub fn size(format: ContainerFormat) -> usize {
+        return BlockAddress::element_size(format) * 3; // Позиция заголовка атрибутов + Позиция заголовка данных + Разделитель адресов
     }
 
-    // Возвращает позицию следующего блока
-    pub fn next_block_position(&self) -> i32 {
+    // Возвращает позицию следующего блока. Размер данных известен только у адресов,
+    // добавленных при создании оглавления.
+    pub fn next_block_position(&self) -> Result<i64, ConfError> {
         if self.data_size < 0 {
-            error!("The size of the data is not initilized.");
-            panic!("The size of the data is not initilized.");
+            return Err(toc_error(self.data_header_pos, "The size of the data is not initilized."));
         }
 
-        return self.data_header_pos + Header::size() + self.data_size;
+        return Ok(self.data_header_pos + self.format.header_size() + self.data_size);
     }
 }
 
@@ -139,20 +167,42 @@ fn test_block_address_from_cf() {
     source_data.extend_from_slice(&block_data_header);
     source_data.extend_from_slice(&block_data);
 
-    let test = BlockAddress::from_cf(&Vec::from(&source_data[..]), 0).unwrap();
+    let test = BlockAddress::from_cf(&Vec::from(&source_data[..]), 0, ContainerFormat::Bits32)
+        .unwrap()
+        .unwrap();
 
     assert_eq!(toc, test.for_cf(0));
     assert_eq!(13, test.attr_header_pos());
+
+    let e = test.next_block_position().err().unwrap();
+    assert_eq!(Stage::Toc, e.stage());
+    assert_eq!(Some(14), e.position());
 }
 
 #[test]
 fn test_block_address_for_cf() {
 
-    let block_data_size = 1i32;
-    let test = BlockAddress::new(13, 14, block_data_size);
+    let block_data_size = 1i64;
+    let test = BlockAddress::new(13, 14, block_data_size, ContainerFormat::Bits32);
     let toc: Vec<u8> = vec![0x0D, 0x00, 0x00, 0x00, 0x0E, 0x00, 0x00, 0x00, 0xFF, 0xFF, 0xFF, 0x7F]; // 13-14
 
     assert_eq!(toc, test.for_cf(0));
-    assert_eq!(14 + Header::size() + block_data_size,
-               test.next_block_position());
+    assert_eq!(14 + 31 + block_data_size, test.next_block_position().unwrap());
+}
+
+#[test]
+fn test_block_address_64() {
+
+    let mut toc: Vec<u8> = vec![0x0D, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00]; // 0x10000000D
+    toc.extend_from_slice(&[0x0E, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]); // 14
+    toc.extend_from_slice(&[0xFF; 8]);
+
+    let test = BlockAddress::from_cf(&toc, 0, ContainerFormat::Bits64).unwrap().unwrap();
+    assert_eq!(0x10000000D, test.attr_header_pos());
+    assert_eq!(14, test.data_header_pos());
+    assert_eq!(toc, test.for_cf(0));
+    assert_eq!(24, BlockAddress::size(ContainerFormat::Bits64));
+
+    let test = BlockAddress::new(13, 14, 1, ContainerFormat::Bits64);
+    assert_eq!(14 + 55 + 1, test.next_block_position().unwrap());
 }
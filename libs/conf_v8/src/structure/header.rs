@@ -1,6 +1,10 @@
 
 use conv;
+use std::cmp;
 use std::clone::Clone;
+use error::{ConfError, header_error};
+use structure::source::{self, SharedSource};
+use structure::layout::ContainerFormat;
 
 // Маркеры начала и окончания заголовка
 const BEGIN_HEADER_MARKER: [u8; 2] = [b'\r', b'\n'];
@@ -8,6 +12,7 @@ const END_HEADER_MARKER: [u8; 2] = [b'\r', b'\n'];
 
 const SPACE: u8 = 0x20;
 const EMPTY_HEADER_VALUE: [u8; 8] = [b'7', b'f', b'f', b'f', b'f', b'f', b'f', b'f']; // Пустое значение = 2147483647 - i32::max_value()
+const EMPTY_HEADER_VALUE_64: [u8; 16] = [b'f'; 16]; // Пустое значение 64-разрядного заголовка = u64::max_value()
 
 #[test]
 fn test_check_control_characters() {
@@ -30,15 +35,15 @@ fn test_check_control_characters() {
 //
 // @result - true - проверка контрольных байт выполнена успешно
 pub fn check_control_characters(data: &Vec<u8>,
-                                start_pos_in_data: i32,
-                                control_char_pos: i32,
+                                start_pos_in_data: i64,
+                                control_char_pos: i64,
                                 control_characters: &[u8])
                                 -> bool {
 
     let start_data_pos = start_pos_in_data + control_char_pos;
-    let end_data_pos = start_data_pos + control_characters.len() as i32;
+    let end_data_pos = start_data_pos + control_characters.len() as i64;
 
-    if start_data_pos > data.len() as i32 || end_data_pos > data.len() as i32 {
+    if start_data_pos < 0 || start_data_pos > data.len() as i64 || end_data_pos > data.len() as i64 {
         return false;
     } else {
         return &data[start_data_pos as usize..end_data_pos as usize] == control_characters;
@@ -56,20 +61,48 @@ fn test_is_header() {
 
     assert_eq!(false, is_header(&data, 0));
     assert_eq!(true, is_header(&data, 1));
+    assert_eq!(Some(ContainerFormat::Bits32), header_format(&data, 1));
+
+    let data = Header::for_cf_with_page(0xa3, 0x200, ContainerFormat::Bits64);
+    assert_eq!(true, is_header(&data, 0));
+    assert_eq!(false, is_header_of(&data, 0, ContainerFormat::Bits32));
+    assert_eq!(Some(ContainerFormat::Bits64), header_format(&data, 0));
 }
 
-// В текущей позиции начинаются данные заголовка
-// Пример: "\r\n000000a3 00000200 7fffffff \r\n" (всегда 31 символ)
+// В текущей позиции начинаются данные заголовка любого формата
 //
 // @param - данные
 // @param - текущая позиция, в которой ожидаем увидеть данные заголовка
-pub fn is_header(data: &Vec<u8>, start_pos: i32) -> bool {
+pub fn is_header(data: &Vec<u8>, start_pos: i64) -> bool {
+    return header_format(data, start_pos).is_some();
+}
+
+// Возвращает формат заголовка, который начинается в текущей позиции.
+// Значения 32-разрядного заголовка содержат цифры в позициях пробелов 64-разрядного,
+// поэтому форматы не пересекаются.
+pub fn header_format(data: &Vec<u8>, start_pos: i64) -> Option<ContainerFormat> {
+
+    for format in [ContainerFormat::Bits64, ContainerFormat::Bits32].iter() {
+        if is_header_of(data, start_pos, *format) {
+            return Some(*format);
+        }
+    }
+
+    return None;
+}
+
+// В текущей позиции начинаются данные заголовка указанного формата
+// Пример: "\r\n000000a3 00000200 7fffffff \r\n" (31 символ)
+// Пример: "\r\n00000000000000a3 0000000000000200 ffffffffffffffff \r\n" (55 символов)
+pub fn is_header_of(data: &Vec<u8>, start_pos: i64, format: ContainerFormat) -> bool {
+
+    let step = format.header_digits() as i64 + 1;
 
     return check_control_characters(&data, start_pos, 0, &BEGIN_HEADER_MARKER) &&
-           check_control_characters(&data, start_pos, 10, &[SPACE]) &&
-           check_control_characters(&data, start_pos, 19, &[SPACE]) &&
-           check_control_characters(&data, start_pos, 28, &[SPACE]) &&
-           check_control_characters(&data, start_pos, 29, &END_HEADER_MARKER);
+           check_control_characters(&data, start_pos, 1 + step, &[SPACE]) &&
+           check_control_characters(&data, start_pos, 1 + step * 2, &[SPACE]) &&
+           check_control_characters(&data, start_pos, 1 + step * 3, &[SPACE]) &&
+           check_control_characters(&data, start_pos, 2 + step * 3, &END_HEADER_MARKER);
 }
 
 // Заголовок области, содержащий координаты и размеры в конфигурационном файле 1С
@@ -79,39 +112,42 @@ pub fn is_header(data: &Vec<u8>, start_pos: i32) -> bool {
 //   - первое значение: "000000ac" - размер полезных данных области
 //   - второе значение: "00000200" - полный размер данных области
 //   - третье значение: "00000200" - позиция продолжения данных области. Если содержит значение "7fffffff" - продолжение отсутствует
+// В 64-разрядном формате значения состоят из 16 цифр, пустое значение - "ffffffffffffffff"
 pub struct Header {
-    valuable_region_size: i32, // размер полезных данных области
-    total_region_size: i32, // полный размер области
-    next_header_position: i32, // позиция заголовка продолжения данных
-    region_position: i32, // позиция с которой начинаются данные
+    valuable_region_size: i64, // размер полезных данных области
+    total_region_size: i64, // полный размер области
+    next_header_position: i64, // позиция заголовка продолжения данных
+    region_position: i64, // позиция с которой начинаются данные
+    format: ContainerFormat, // формат заголовка
 }
 
 impl Header {
-    // Инициализивать объект на основании данных конфигурационного файла
-    pub fn from_cf(data: &Vec<u8>, header_position: i32) -> Header {
+    // Инициализивать объект на основании данных конфигурационного файла.
+    // Формат заголовка определяется по расположению разделителей значений.
+    pub fn from_cf(data: &Vec<u8>, header_position: i64) -> Result<Header, ConfError> {
+
+        if header_position < 0 {
+            return Err(header_error(header_position, "Negative position of the header."));
+        }
+
+        let format = header_format(data, header_position).unwrap_or(ContainerFormat::Bits32);
 
         let begin_pos = header_position as usize + BEGIN_HEADER_MARKER.len();
-        let end_pos = begin_pos + Header::value_size() as usize;
+        let end_pos = begin_pos + Header::value_size(format) as usize;
         if begin_pos > data.len() || end_pos > data.len() {
-            error!("Failed create header object: data size={}; header position={}; begin \
-                    position={}; end position={}.",
-                   data.len(),
-                   header_position,
-                   begin_pos,
-                   end_pos);
-            panic!("Failed create header object: data size={}; header position={}; begin \
-                    position={}; end position={}.",
-                   data.len(),
-                   header_position,
-                   begin_pos,
-                   end_pos);
+            return Err(header_error(header_position,
+                                    format!("Failed create header object: data size={}; begin \
+                                             position={}; end position={}.",
+                                            data.len(),
+                                            begin_pos,
+                                            end_pos)));
         }
 
         // "000000ac 00000200 00000200 " => [172, 512, 512]
         let mut num_group = 0i8;
-        let mut valuable_region_size = 0i32;
-        let mut total_region_size = 0i32;
-        let mut next_header_position = 0i32;
+        let mut valuable_region_size = 0i64;
+        let mut total_region_size = 0i64;
+        let mut next_header_position = 0i64;
 
         for group in data[begin_pos..end_pos].to_vec().split(|byte| *byte == SPACE) {
             if group.is_empty() {
@@ -119,55 +155,112 @@ impl Header {
             }
 
             num_group += 1;
-            let mut value = conv::hex_to_int(group);
-            if value == i32::max_value() {
-                value = 0;
-            }
+            let value = match conv::hex_to_int64(group) {
+                v if v == format.empty_value() => 0,
+                v => v as i64,
+            };
 
             match num_group {
                 1 => valuable_region_size = value,
                 2 => total_region_size = value,
                 3 => next_header_position = value,
-                _ => {
-                    error!("Bad format of the header.");
-                    panic!("Bad format of the header.");
-                }
+                _ => return Err(header_error(header_position, "Bad format of the header.")),
             }
         }
 
-        return Header {
+        return Ok(Header {
             valuable_region_size: valuable_region_size,
             total_region_size: total_region_size,
             next_header_position: next_header_position,
-            region_position: header_position + Header::size(),
+            region_position: header_position + format.header_size(),
+            format: format,
+        });
+    }
+
+    // Инициализировать объект на основании данных источника, прочитав только данные заголовка
+    // указанного формата
+    pub fn from_source(source: &SharedSource,
+                       header_position: i64,
+                       format: ContainerFormat)
+                       -> Result<Header, ConfError> {
+
+        if header_position < 0 {
+            return Err(header_error(header_position, "Negative position of the header."));
+        }
+
+        let data = match source::read_at(source,
+                                         header_position as u64,
+                                         format.header_size() as usize) {
+            Ok(v) => v,
+            Err(e) => return Err(header_error(header_position, e)),
+        };
+
+        let mut header = match Header::from_cf(&data, 0) {
+            Ok(v) => v,
+            Err(e) => return Err(header_error(header_position, e.message())),
         };
+        if header.format != format {
+            return Err(header_error(header_position, "Unexpected format of the header."));
+        }
+        header.region_position = header_position + format.header_size();
+
+        return Ok(header);
     }
 
     // Получить данные заголовка в виде пригодном для записи в конфигурационный файл
     pub fn for_cf(valuable_region_size: usize) -> Vec<u8> {
+        return Header::for_cf_with_page(valuable_region_size, 0, ContainerFormat::Bits32);
+    }
+
+    // Получить данные заголовка указанного формата для области с указанным полным размером
+    // (размером страницы). Если полезных данных больше, чем размер страницы, то полный размер
+    // равен размеру данных.
+    pub fn for_cf_with_page(valuable_region_size: usize,
+                            page_size: i64,
+                            format: ContainerFormat)
+                            -> Vec<u8> {
+
+        let total_region_size = cmp::max(valuable_region_size as i64, page_size);
 
         let mut data: Vec<u8> = Vec::new();
-        data.reserve(Header::size() as usize);
+        data.reserve(format.header_size() as usize);
 
         data.extend_from_slice(&BEGIN_HEADER_MARKER);
-        data.extend_from_slice(&conv::int32_to_hex_bytes(valuable_region_size as i32));
+        data.extend_from_slice(&value_for_cf(valuable_region_size as i64, format));
         data.push(SPACE);
-        data.extend_from_slice(&conv::int32_to_hex_bytes(valuable_region_size as i32));
+        data.extend_from_slice(&value_for_cf(total_region_size, format));
         data.push(SPACE);
-        data.extend_from_slice(&EMPTY_HEADER_VALUE);
+        match format {
+            ContainerFormat::Bits32 => data.extend_from_slice(&EMPTY_HEADER_VALUE),
+            ContainerFormat::Bits64 => data.extend_from_slice(&EMPTY_HEADER_VALUE_64),
+        }
         data.push(SPACE);
         data.extend_from_slice(&END_HEADER_MARKER);
 
         return data;
     }
 
+    // Получить заголовок и данные области, дополненные нулями до полного размера области
+    pub fn region_for_cf(region_data: &[u8], page_size: i64, format: ContainerFormat) -> Vec<u8> {
+
+        let mut data: Vec<u8> = Header::for_cf_with_page(region_data.len(), page_size, format);
+        data.extend_from_slice(region_data);
+
+        if (region_data.len() as i64) < page_size {
+            let new_len = data.len() + page_size as usize - region_data.len();
+            data.resize(new_len, 0x00);
+        }
+
+        return data;
+    }
+
     // Возвращает позицию области в файле
-    pub fn region_position(&self) -> i32 {
+    pub fn region_position(&self) -> i64 {
         return self.region_position;
     }
 
     // Возвращает размер полезных данных области
-    pub fn region_size(&self) -> i32 {
+    pub fn region_size(&self) -> i64 {
 
         match self.valuable_region_size > self.total_region_size || self.valuable_region_size == 0 {
             true => self.total_region_size,
@@ -176,28 +269,33 @@ impl Header {
     }
 
     // Возвращает полный размер области к которой относится заголовок
-    pub fn full_region_size(&self) -> i32 {
+    pub fn full_region_size(&self) -> i64 {
         return self.total_region_size;
     }
 
     // Возвращает позицию продолжения данных
-    pub fn next_header_position(&self) -> Option<i32> {
+    pub fn next_header_position(&self) -> Option<i64> {
         return match self.next_header_position {
             0 => None,
-            _ => Some(self.next_header_position as i32),
+            _ => Some(self.next_header_position),
         };
     }
 
+    // Возвращает формат заголовка
+    pub fn format(&self) -> ContainerFormat {
+        return self.format;
+    }
+
     // Заголовок принадлежит области с данными атрибутов блока
     pub fn is_region_of_attrs(&self, data: &Vec<u8>) -> bool {
 
-        const MIN_BLOCK_SIZE: i32 = 21; // второй символ в имени блока
+        const MIN_BLOCK_SIZE: i64 = 21; // второй символ в имени блока
         if self.region_size() < MIN_BLOCK_SIZE {
             return false;
         }
 
         let start_pos = self.region_position();
-        if data.len() - start_pos as usize <= MIN_BLOCK_SIZE as usize {
+        if start_pos < 0 || data.len() <= start_pos as usize + MIN_BLOCK_SIZE as usize {
             return false;
         }
 
@@ -208,13 +306,22 @@ impl Header {
     }
 
     // Полный размер заголовка вместе с маркерами
-    pub fn size() -> i32 {
-        return 31;
+    pub fn size(&self) -> i64 {
+        return self.format.header_size();
     }
 
-    // Размер полезных данных заголовка
-    pub fn value_size() -> i32 {
-        return Header::size() - BEGIN_HEADER_MARKER.len() as i32 - END_HEADER_MARKER.len() as i32;
+    // Размер полезных данных заголовка указанного формата
+    pub fn value_size(format: ContainerFormat) -> i64 {
+        return format.header_size() - BEGIN_HEADER_MARKER.len() as i64 -
+               END_HEADER_MARKER.len() as i64;
+    }
+}
+
+// Значение заголовка указанного формата: 8 или 16 шестнадцатеричных цифр
+fn value_for_cf(value: i64, format: ContainerFormat) -> Vec<u8> {
+    match format {
+        ContainerFormat::Bits32 => conv::int32_to_hex_bytes(value as i32).to_vec(),
+        ContainerFormat::Bits64 => conv::int64_to_hex_bytes(value as u64).to_vec(),
     }
 }
 
@@ -225,6 +332,7 @@ impl Clone for Header {
             total_region_size: self.total_region_size,
             next_header_position: self.next_header_position,
             region_position: self.region_position,
+            format: self.format,
         };
     }
 }
@@ -247,7 +355,7 @@ fn test_header_new() {
     let mut data: Vec<u8> = vec![0u8];
     data.extend(Header::for_cf(172));
 
-    let test = Header::from_cf(&data, 1);
+    let test = Header::from_cf(&data, 1).unwrap();
     assert_eq!(test.valuable_region_size, 172);
     assert_eq!(test.total_region_size, 172);
     assert_eq!(test.next_header_position, 0);
@@ -260,9 +368,9 @@ fn test_region_position() {
         let mut data: Vec<u8> = vec![0u8; *i];
         data.extend(Header::for_cf(172));
 
-        let pos = *i as i32;
-        let test = Header::from_cf(&data, pos);
-        assert_eq!(test.region_position(), pos + Header::size());
+        let pos = *i as i64;
+        let test = Header::from_cf(&data, pos).unwrap();
+        assert_eq!(test.region_position(), pos + test.size());
     }
 }
 
@@ -273,17 +381,17 @@ fn test_get_region_size() {
         let val_size: usize = source_data[0] as usize;
 
         let data = Header::for_cf(val_size);
-        let test = Header::from_cf(&data, 0);
+        let test = Header::from_cf(&data, 0).unwrap();
 
-        assert_eq!(test.region_size(), val_size as i32);
-        assert_eq!(test.full_region_size(), val_size as i32);
+        assert_eq!(test.region_size(), val_size as i64);
+        assert_eq!(test.full_region_size(), val_size as i64);
     }
 }
 
 #[test]
 fn test_get_next_header_position() {
 
-    let next_header = 10i32;
+    let next_header = 10i64;
     let mut data: Vec<u8> = Vec::new();
 
     data.extend_from_slice(&BEGIN_HEADER_MARKER);
@@ -291,11 +399,11 @@ fn test_get_next_header_position() {
     data.push(SPACE);
     data.extend_from_slice(&conv::int32_to_hex_bytes(2));
     data.push(SPACE);
-    data.extend_from_slice(&conv::int32_to_hex_bytes(next_header));
+    data.extend_from_slice(&conv::int32_to_hex_bytes(next_header as i32));
     data.push(SPACE);
     data.extend_from_slice(&END_HEADER_MARKER);
 
-    let test = Header::from_cf(&data, 0);
+    let test = Header::from_cf(&data, 0).unwrap();
 
     assert_eq!(test.next_header_position(), Some(next_header));
 }
@@ -332,6 +440,50 @@ fn test_is_region_of_attrs() {
     data.extend(header_data);
     data.extend(attr_data);
 
-    let test = Header::from_cf(&data, 0);
+    let test = Header::from_cf(&data, 0).unwrap();
     assert_eq!(true, test.is_region_of_attrs(&data));
 }
+
+#[test]
+fn test_header_out_of_range() {
+    let data = Header::for_cf(172);
+
+    let test = Header::from_cf(&data, 1);
+    assert!(test.is_err());
+    assert_eq!(Some(1), test.err().unwrap().position());
+}
+
+#[test]
+fn test_region_for_cf() {
+    let data = Header::region_for_cf(&[1, 2, 3], 512, ContainerFormat::Bits32);
+    assert_eq!(31 + 512, data.len());
+
+    let test = Header::from_cf(&data, 0).unwrap();
+    assert_eq!(3, test.region_size());
+    assert_eq!(512, test.full_region_size());
+
+    let data = Header::region_for_cf(&[1, 2, 3], 0, ContainerFormat::Bits32);
+    assert_eq!(31 + 3, data.len());
+}
+
+#[test]
+fn test_header_64() {
+    let mut data: Vec<u8> = vec![0u8];
+    data.extend_from_slice(b"\r\n00000000000000ac 0000000000000200 ffffffffffffffff \r\n");
+    assert_eq!(data[1..].to_vec(),
+               Header::for_cf_with_page(172, 512, ContainerFormat::Bits64));
+
+    let test = Header::from_cf(&data, 1).unwrap();
+    assert_eq!(ContainerFormat::Bits64, test.format());
+    assert_eq!(172, test.region_size());
+    assert_eq!(512, test.full_region_size());
+    assert_eq!(None, test.next_header_position());
+    assert_eq!(1 + 55, test.region_position());
+
+    let big = Header::for_cf_with_page(0, 0x1_0000_0000, ContainerFormat::Bits64);
+    assert_eq!(0x1_0000_0000, Header::from_cf(&big, 0).unwrap().full_region_size());
+
+    let data = Header::region_for_cf(&[1, 2, 3], 0, ContainerFormat::Bits64);
+    assert_eq!(55 + 3, data.len());
+    assert_eq!(3, Header::from_cf(&data, 0).unwrap().region_size());
+}
@@ -0,0 +1,115 @@
+use configuration::CF;
+use {GROUP_BLOKS_FLAG, GROUP_BLOKS_FLAG_64};
+
+/// Формат адресов контейнера
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum ContainerFormat {
+    Bits32, // заголовки "\r\n%08x %08x %08x \r\n", 4-байтовые адреса, пустое значение 7fffffff
+    Bits64, // заголовки "\r\n%016x %016x %016x \r\n", 8-байтовые адреса, пустое значение ffffffffffffffff
+}
+
+impl ContainerFormat {
+    /// Получить формат по разрядности адресов: "32" или "64"
+    pub fn from_bits(bits: &str) -> Option<ContainerFormat> {
+        match bits {
+            "32" => Some(ContainerFormat::Bits32),
+            "64" => Some(ContainerFormat::Bits64),
+            _ => None,
+        }
+    }
+
+    // Маркер группы блоков в начале префикса контейнера
+    pub fn group_flag(&self) -> &'static [u8] {
+        match *self {
+            ContainerFormat::Bits32 => &GROUP_BLOKS_FLAG,
+            ContainerFormat::Bits64 => &GROUP_BLOKS_FLAG_64,
+        }
+    }
+
+    // Пустое значение в заголовках и адресах оглавления
+    pub fn empty_value(&self) -> u64 {
+        match *self {
+            ContainerFormat::Bits32 => i32::MAX as u64,
+            ContainerFormat::Bits64 => u64::MAX,
+        }
+    }
+
+    // Количество шестнадцатеричных цифр в одном значении заголовка
+    pub fn header_digits(&self) -> usize {
+        match *self {
+            ContainerFormat::Bits32 => 8,
+            ContainerFormat::Bits64 => 16,
+        }
+    }
+
+    // Полный размер заголовка вместе с маркерами: 2 + 3 * (цифры + пробел) + 2
+    pub fn header_size(&self) -> i64 {
+        return (4 + 3 * (self.header_digits() + 1)) as i64;
+    }
+
+    // Количество байт одного значения адреса в оглавлении
+    pub fn address_element_size(&self) -> usize {
+        match *self {
+            ContainerFormat::Bits32 => 4,
+            ContainerFormat::Bits64 => 8,
+        }
+    }
+}
+
+// Параметры размещения данных контейнера (конфигурационного файла или составного блока),
+// которые нужны для побайтового восстановления контейнера после распаковки
+#[derive(Clone, PartialEq, Debug)]
+pub struct ContainerLayout {
+    pub prefix: Vec<u8>, // префикс контейнера (маркер группы, размер страницы по умолчанию, ...)
+    pub toc_page_size: i64, // полный размер области оглавления
+}
+
+impl ContainerLayout {
+    // Параметры контейнера, используемые при создании нового конфигурационного файла
+    pub fn new() -> ContainerLayout {
+        return ContainerLayout::with_format(ContainerFormat::Bits32);
+    }
+
+    // Параметры нового контейнера с указанным форматом адресов
+    pub fn with_format(format: ContainerFormat) -> ContainerLayout {
+        return ContainerLayout {
+            prefix: CF::prefix(format),
+            toc_page_size: 0,
+        };
+    }
+
+    // Формат адресов контейнера, определяется по маркеру группы в префиксе
+    pub fn format(&self) -> ContainerFormat {
+        match self.prefix.starts_with(&GROUP_BLOKS_FLAG_64) {
+            true => ContainerFormat::Bits64,
+            false => ContainerFormat::Bits32,
+        }
+    }
+}
+
+// Полные размеры (размеры страниц) областей атрибутов и данных блока.
+// Нулевой размер означает, что размер области равен размеру ее полезных данных.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct PageSizes {
+    pub attrs: i64, // полный размер области атрибутов
+    pub data: i64, // полный размер области данных
+}
+
+impl PageSizes {
+    pub fn new() -> PageSizes {
+        return PageSizes { attrs: 0, data: 0 };
+    }
+}
+
+#[test]
+fn test_container_format() {
+    assert_eq!(31, ContainerFormat::Bits32.header_size());
+    assert_eq!(55, ContainerFormat::Bits64.header_size());
+
+    assert_eq!(ContainerFormat::Bits32, ContainerLayout::new().format());
+    assert_eq!(ContainerFormat::Bits64,
+               ContainerLayout::with_format(ContainerFormat::Bits64).format());
+
+    assert_eq!(Some(ContainerFormat::Bits64), ContainerFormat::from_bits("64"));
+    assert_eq!(None, ContainerFormat::from_bits("16"));
+}
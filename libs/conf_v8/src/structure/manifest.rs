@@ -0,0 +1,358 @@
+
+use file_system;
+
+use structure::attributes::Attributes;
+use structure::layout::{ContainerLayout, PageSizes};
+use error::{ConfError, io_error};
+
+use std::path::Path;
+
+// Имя файла описания распакованной конфигурации
+pub const MANIFEST_FILE_NAME: &'static str = ".manifest";
+
+// Имя каталога с исходными сжатыми данными блоков распакованной конфигурации
+pub const COMPRESSED_DIR_NAME: &'static str = ".compressed";
+
+const MANIFEST_TITLE: &'static str = "conf_robber manifest 1";
+const CONTAINER: &'static str = "container";
+const BLOCK: &'static str = "block";
+const NESTED: &'static str = "nested";
+
+// Описание блока, сохраняемое при распаковке конфигурационного файла
+#[derive(Clone)]
+pub struct BlockLayout {
+    pub attrs: Attributes, // исходные атрибуты блока
+    pub pages: PageSizes, // полные размеры областей блока
+    pub container: Option<ContainerLayout>, // параметры составного блока
+    pub nested: Vec<BlockLayout>, // вложенные блоки в порядке оглавления составного блока
+}
+
+impl BlockLayout {
+    // Найти описание вложенного блока
+    pub fn nested_block(&self, id: &str) -> Option<&BlockLayout> {
+        self.nested.iter().find(|v| v.attrs.id().eq(id))
+    }
+}
+
+// Описание распакованной конфигурации, необходимое для побайтового восстановления
+// конфигурационного файла:
+//  - параметры контейнера (префикс и размер области оглавления);
+//  - атрибуты блоков и размеры их областей в порядке оглавления;
+//  - то же самое для вложенных блоков составных блоков.
+//
+// Описание хранится в текстовом файле, каждая строка которого описывает одну сущность:
+//   container <префикс в hex> <размер области оглавления>
+//   block <дата создания> <дата модификации> <тип блока> <hex> <размер атрибутов> <размер данных> <id>
+//   nested <дата создания> <дата модификации> <тип блока> <hex> <размер атрибутов> <размер данных> <id>
+// Строка "container" после строки "block" описывает составной блок.
+//
+// Исходные сжатые данные блоков хранятся рядом с описанием в каталоге ".compressed" (файл
+// на каждый блок), т.к. сжатие 1С не совпадает побайтово со сжатием zlib_wrapper. При сборке
+// сохраненные данные используются, если после распаковки они совпадают с данными блока.
+pub struct Manifest {
+    pub container: ContainerLayout, // параметры конфигурационного файла
+    pub blocks: Vec<BlockLayout>, // блоки в порядке оглавления
+}
+
+impl Manifest {
+    // Найти описание блока
+    pub fn block(&self, id: &str) -> Option<&BlockLayout> {
+        self.blocks.iter().find(|v| v.attrs.id().eq(id))
+    }
+
+    // Возвращает текст описания
+    pub fn to_text(&self) -> String {
+
+        let mut text = String::new();
+        text.push_str(MANIFEST_TITLE);
+        text.push('\n');
+        text.push_str(&container_to_text(&self.container));
+
+        for block in &self.blocks {
+            text.push_str(&block_to_text(BLOCK, block, ""));
+
+            if let Some(ref container) = block.container {
+                text.push_str("    ");
+                text.push_str(&container_to_text(container));
+            }
+
+            for nested_block in &block.nested {
+                text.push_str(&block_to_text(NESTED, nested_block, "    "));
+            }
+        }
+
+        return text;
+    }
+
+    // Получить описание из текста
+    pub fn from_text(text: &str) -> Result<Manifest, String> {
+
+        let mut lines = text.lines();
+
+        match lines.next() {
+            Some(v) if v.trim().eq(MANIFEST_TITLE) => (),
+            _ => return Err(String::from("Unknown format of the manifest.")),
+        }
+
+        let mut container: Option<ContainerLayout> = None;
+        let mut blocks: Vec<BlockLayout> = Vec::new();
+
+        for (num, line) in lines.enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let error = |e: String| format!("Line {}: {}", num + 2, e);
+            let kind = line.split(' ').next().unwrap_or("");
+
+            if kind.eq(CONTAINER) {
+                let value = container_from_text(line).map_err(&error)?;
+
+                match blocks.last_mut() {
+                    None => container = Some(value),
+                    Some(block) => block.container = Some(value),
+                }
+            } else if kind.eq(BLOCK) {
+                blocks.push(block_from_text(line).map_err(&error)?);
+            } else if kind.eq(NESTED) {
+                let nested_block = block_from_text(line).map_err(&error)?;
+
+                match blocks.last_mut() {
+                    None => return Err(error(String::from("Nested block without block."))),
+                    Some(block) => block.nested.push(nested_block),
+                }
+            } else {
+                return Err(error(format!("Unknown record '{}'.", kind)));
+            }
+        }
+
+        let container = match container {
+            Some(v) => v,
+            None => return Err(String::from("Parameters of the container are not found.")),
+        };
+
+        return Ok(Manifest {
+            container: container,
+            blocks: blocks,
+        });
+    }
+
+    // Прочитать описание из каталога распакованной конфигурации
+    pub fn read(path_to_dir: &String) -> Result<Option<Manifest>, ConfError> {
+
+        let path = manifest_path(path_to_dir);
+        if !file_system::exist(&path) {
+            return Ok(None);
+        }
+
+        let data = match file_system::read_file(&path) {
+            Ok(v) => v,
+            Err(e) => return Err(io_error(&path, e)),
+        };
+
+        let text = match String::from_utf8(data) {
+            Ok(v) => v,
+            Err(e) => return Err(io_error(&path, format!("{}", e))),
+        };
+
+        match Manifest::from_text(&text) {
+            Ok(v) => Ok(Some(v)),
+            Err(e) => Err(io_error(&path, e)),
+        }
+    }
+
+    // Записать описание в каталог распакованной конфигурации
+    pub fn write(&self, path_to_dir: &String) -> Result<(), ConfError> {
+
+        let path = manifest_path(path_to_dir);
+
+        match file_system::write_file(&path, &self.to_text().into_bytes()) {
+            Ok(_) => Ok(()),
+            Err(e) => Err(io_error(&path, e)),
+        }
+    }
+}
+
+// Записать исходные сжатые данные блока в каталог распакованной конфигурации
+pub fn write_compressed_data(path_to_dir: &String,
+                             block_id: &str,
+                             data: &Vec<u8>)
+                             -> Result<(), ConfError> {
+
+    let path_to_compressed_dir = compressed_dir_path(path_to_dir);
+    file_system::create_dir(&path_to_compressed_dir);
+
+    let path = file_system::path_to_str(Path::new(&path_to_compressed_dir)
+        .join(block_id)
+        .as_path());
+
+    match file_system::write_file(&path, data) {
+        Ok(_) => Ok(()),
+        Err(e) => Err(io_error(&path, e)),
+    }
+}
+
+// Прочитать исходные сжатые данные блока из каталога распакованной конфигурации
+pub fn read_compressed_data(path_to_dir: &String,
+                            block_id: &str)
+                            -> Result<Option<Vec<u8>>, ConfError> {
+
+    let path = file_system::path_to_str(Path::new(&compressed_dir_path(path_to_dir))
+        .join(block_id)
+        .as_path());
+
+    if !file_system::exist(&path) {
+        return Ok(None);
+    }
+
+    match file_system::read_file(&path) {
+        Ok(v) => Ok(Some(v)),
+        Err(e) => Err(io_error(&path, e)),
+    }
+}
+
+// Возвращает путь к каталогу исходных сжатых данных блоков
+fn compressed_dir_path(path_to_dir: &String) -> String {
+    file_system::path_to_str(Path::new(path_to_dir).join(COMPRESSED_DIR_NAME).as_path())
+}
+
+// Возвращает путь к файлу описания
+fn manifest_path(path_to_dir: &String) -> String {
+    file_system::path_to_str(Path::new(path_to_dir).join(MANIFEST_FILE_NAME).as_path())
+}
+
+fn container_to_text(container: &ContainerLayout) -> String {
+    format!("{} {} {}\n",
+            CONTAINER,
+            bytes_to_hex(&container.prefix),
+            container.toc_page_size)
+}
+
+fn container_from_text(line: &str) -> Result<ContainerLayout, String> {
+
+    let values: Vec<&str> = line.split(' ').collect();
+    if values.len() != 3 {
+        return Err(String::from("Bad format of the container."));
+    }
+
+    Ok(ContainerLayout {
+        prefix: hex_to_bytes(values[1])?,
+        toc_page_size: parse_value(values[2])?,
+    })
+}
+
+fn block_to_text(kind: &str, block: &BlockLayout, indent: &str) -> String {
+    format!("{}{} {} {} {} {} {} {} {}\n",
+            indent,
+            kind,
+            block.attrs.creation_date(),
+            block.attrs.modification_date(),
+            block.attrs.group_type(),
+            bytes_to_hex(&block.attrs.unknown()),
+            block.pages.attrs,
+            block.pages.data,
+            block.attrs.id())
+}
+
+fn block_from_text(line: &str) -> Result<BlockLayout, String> {
+
+    let values: Vec<&str> = line.splitn(8, ' ').collect();
+    if values.len() != 8 {
+        return Err(String::from("Bad format of the block."));
+    }
+
+    let unknown_data = hex_to_bytes(values[4])?;
+    if unknown_data.len() != 4 {
+        return Err(format!("Bad value of the attributes: {}", values[4]));
+    }
+
+    let mut unknown = [0u8; 4];
+    unknown.copy_from_slice(&unknown_data);
+
+    let attrs = Attributes::from_values(parse_value(values[1])?,
+                                        parse_value(values[2])?,
+                                        parse_value(values[3])?,
+                                        &String::from(values[7]),
+                                        unknown);
+
+    Ok(BlockLayout {
+        attrs: attrs,
+        pages: PageSizes {
+            attrs: parse_value(values[5])?,
+            data: parse_value(values[6])?,
+        },
+        container: None,
+        nested: Vec::new(),
+    })
+}
+
+fn parse_value<T: ::std::str::FromStr>(value: &str) -> Result<T, String> {
+    match value.parse::<T>() {
+        Ok(v) => Ok(v),
+        Err(_) => Err(format!("Bad value: {}", value)),
+    }
+}
+
+fn bytes_to_hex(data: &[u8]) -> String {
+    data.iter().map(|v| format!("{:02x}", v)).collect()
+}
+
+fn hex_to_bytes(value: &str) -> Result<Vec<u8>, String> {
+
+    if value.len() % 2 != 0 || !value.is_ascii() {
+        return Err(format!("Bad hex value: {}", value));
+    }
+
+    let mut retval: Vec<u8> = Vec::new();
+
+    for i in 0..value.len() / 2 {
+        match u8::from_str_radix(&value[i * 2..i * 2 + 2], 16) {
+            Ok(v) => retval.push(v),
+            Err(_) => return Err(format!("Bad hex value: {}", value)),
+        }
+    }
+
+    return Ok(retval);
+}
+
+#[test]
+fn test_manifest_text() {
+
+    let mut form = BlockLayout {
+        attrs: Attributes::from_values(10, 20, 689, &String::from("id 0"), [0, 0, 0, 1]),
+        pages: PageSizes {
+            attrs: 0,
+            data: 512,
+        },
+        container: Some(ContainerLayout::new()),
+        nested: Vec::new(),
+    };
+    form.nested.push(BlockLayout {
+        attrs: Attributes::new(30, 0, &String::from("form")),
+        pages: PageSizes::new(),
+        container: None,
+        nested: Vec::new(),
+    });
+
+    let manifest = Manifest {
+        container: ContainerLayout {
+            prefix: vec![0xFF, 0xFF, 0xFF, 0x7F],
+            toc_page_size: 512,
+        },
+        blocks: vec![form],
+    };
+
+    let test = Manifest::from_text(&manifest.to_text()).unwrap();
+
+    assert_eq!(manifest.container, test.container);
+    assert_eq!(1, test.blocks.len());
+
+    let block = test.block("id 0").unwrap();
+    assert_eq!(manifest.blocks[0].attrs.for_cf(), block.attrs.for_cf());
+    assert_eq!(manifest.blocks[0].pages, block.pages);
+    assert_eq!(Some(ContainerLayout::new()), block.container);
+    assert_eq!(30, block.nested_block("form").unwrap().attrs.creation_date());
+
+    assert!(Manifest::from_text("block 1 2 3 00000000 0 0 root").is_err());
+}
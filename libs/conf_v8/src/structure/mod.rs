@@ -1,4 +1,6 @@
+pub mod source;
 pub mod header;
+pub mod layout;
 pub mod attributes;
 pub mod nested_block;
 pub mod block;
@@ -6,3 +8,4 @@ pub mod toc;
 pub mod block_address;
 pub mod reader;
 pub mod writer;
+pub mod manifest;
@@ -1,19 +1,27 @@
 
 use structure::attributes::Attributes;
+use structure::layout::PageSizes;
 
 // Вложенный блок
 #[derive(Clone)]
 pub struct NestedBlock {
     pub attrs: Attributes,
     pub data: Vec<u8>,
+    pub pages: PageSizes,
 }
 
 impl NestedBlock {
     pub fn new(attrs: &Attributes, data: &Vec<u8>) -> NestedBlock {
+        return NestedBlock::with_pages(attrs, data, PageSizes::new());
+    }
+
+    // Вложенный блок с полными размерами областей, прочитанными из конфигурационного файла
+    pub fn with_pages(attrs: &Attributes, data: &Vec<u8>, pages: PageSizes) -> NestedBlock {
 
         return NestedBlock {
             attrs: attrs.clone(),
             data: data.clone(),
+            pages: pages,
         };
     }
 }
@@ -1,27 +1,39 @@
-
 use file_system;
 
 use structure::toc::TOC;
 use structure::block::Block;
 use structure::header::Header;
+use structure::source::{self, SharedSource, MemorySource};
+use structure::layout::ContainerLayout;
+use structure::manifest::{self, Manifest, MANIFEST_FILE_NAME, COMPRESSED_DIR_NAME};
+use error::{ConfError, header_error, toc_error, io_error};
 
 /// Возвращает область данных по данным заголовка
-pub fn get_region<'a>(data: &'a Vec<u8>, h: &Header) -> &'a [u8] {
+pub fn get_region<'a>(data: &'a Vec<u8>, h: &Header) -> Result<&'a [u8], ConfError> {
 
     debug!("Region");
 
     let start_pos = h.region_position() as usize;
     let end_pos = (h.region_position() + h.region_size()) as usize;
 
+    if start_pos > end_pos || end_pos > data.len() {
+        return Err(header_error(h.region_position() - h.size(),
+                                format!("Region is out of the data: data size={}; begin \
+                                         position={}; end position={}.",
+                                        data.len(),
+                                        start_pos,
+                                        end_pos)));
+    }
+
     let buffer = &data[start_pos..end_pos];
 
     debug!("-Region");
 
-    return buffer;
+    return Ok(buffer);
 }
 
 /// Получить все данные блока по данным заголовка
-pub fn get_block(data: &Vec<u8>, h: &Header) -> Vec<u8> {
+pub fn get_block(data: &Vec<u8>, h: &Header) -> Result<Vec<u8>, ConfError> {
 
     debug!("Reading regions of the block.");
 
@@ -29,60 +41,183 @@ pub fn get_block(data: &Vec<u8>, h: &Header) -> Vec<u8> {
     let mut header = h.clone();
 
     loop {
-        block.extend_from_slice(&get_region(data, &header));
+        block.extend_from_slice(get_region(data, &header)?);
 
         match header.next_header_position() {
-            Some(pos) => header = Header::from_cf(data, pos),
+            Some(pos) => header = Header::from_cf(data, pos)?,
             None => break,
         }
     }
 
     debug!("-Reading regions of the block.");
 
-    return block;
+    return Ok(block);
 }
 
-/// Получить коллекцию блоков на основании данных конфигурационного файла
-pub fn from_cf(data: &Vec<u8>) -> Vec<Block> {
+/// Прочитать из источника все данные блока по данным заголовка
+pub fn read_block(source: &SharedSource, h: &Header) -> Result<Vec<u8>, ConfError> {
 
-    info!("Read configuration file");
+    debug!("Reading regions of the block from the source.");
 
-    let toc = match TOC::from_cf(&data) {
-        None => {
-            error!("Bad file format. Required format: *.cf.");
-            panic!("Bad file format. Required format: *.cf.");
+    let mut block: Vec<u8> = Vec::new();
+    let mut header = h.clone();
+
+    loop {
+        let region = match source::read_at(source,
+                                           header.region_position() as u64,
+                                           header.region_size() as usize) {
+            Ok(v) => v,
+            Err(e) => return Err(header_error(header.region_position() - header.size(), e)),
+        };
+        block.extend_from_slice(&region);
+
+        match header.next_header_position() {
+            Some(pos) => header = Header::from_source(source, pos, header.format())?,
+            None => break,
         }
+    }
+
+    debug!("-Reading regions of the block from the source.");
+
+    return Ok(block);
+}
+
+/// Получить коллекцию блоков и параметры контейнера на основании данных конфигурационного файла.
+/// Буфер передается источнику без копирования.
+pub fn from_cf(data: Vec<u8>) -> Result<(Vec<Block>, ContainerLayout), ConfError> {
+    from_source(&source::shared(MemorySource::new(data)))
+}
+
+/// Получить коллекцию блоков и параметры контейнера на основании источника данных
+/// конфигурационного файла. Из источника читаются только оглавление и атрибуты блоков,
+/// данные блоков читаются при первом обращении к ним.
+pub fn from_source(source: &SharedSource) -> Result<(Vec<Block>, ContainerLayout), ConfError> {
+
+    info!("Read configuration file");
+
+    let toc = match TOC::from_source(source)? {
+        None => return Err(toc_error(0, "Bad file format. Required format: *.cf, *.cfe, *.epf or *.erf.")),
         Some(v) => v,
     };
 
+    let format = toc.layout().format();
     let mut retval: Vec<Block> = Vec::new();
 
     for address in toc.addresses() {
 
-        let header_attr = Header::from_cf(&data, address.attr_header_pos());
-        let header_data = Header::from_cf(&data, address.data_header_pos());
+        let header_attr = Header::from_source(source, address.attr_header_pos(), format)?;
 
-        let block = Block::from_cf(&data, &header_attr, &header_data);
+        let block = Block::from_source(source, &header_attr, address.data_header_pos())?;
         retval.push(block);
     }
 
     info!("-Read configuration file");
 
-    return retval;
+    return Ok((retval, toc.layout().clone()));
 }
 
-/// Получить коллекцию блоков на основании ранее распакованных данных конфигурационного файла
-pub fn from_file(path_to_dir: &String) -> Vec<Block> {
+/// Получить коллекцию блоков и параметры контейнера на основании ранее распакованных данных
+/// конфигурационного файла. Если при распаковке было сохранено описание конфигурации, то
+/// блоки следуют в порядке оглавления исходного файла, а их атрибуты восстанавливаются.
+/// Для неизмененных блоков используются исходные сжатые данные.
+pub fn from_file(path_to_dir: &String) -> Result<(Vec<Block>, ContainerLayout), ConfError> {
 
     info!("Read files");
 
+    if !file_system::exist(path_to_dir) {
+        return Err(io_error(path_to_dir, "Directory not found."));
+    }
+
+    let manifest = Manifest::read(path_to_dir)?;
+    let files = file_system::files_in_dir(path_to_dir);
+
     let mut retval: Vec<Block> = Vec::new();
+    let mut container = ContainerLayout::new();
+
+    if let Some(ref manifest) = manifest {
+        container = manifest.container.clone();
+
+        for layout in &manifest.blocks {
+            if let Some(path) = files.get(layout.attrs.id()) {
+                let block = Block::from_file(&path, Some(layout))?;
+                if let Some(data) = manifest::read_compressed_data(path_to_dir, block.id())? {
+                    block.set_original_data(data);
+                }
+                retval.push(block);
+            }
+        }
+    }
+
+    for (name, path) in &files {
+        let is_described = match manifest {
+            Some(ref v) => v.block(name).is_some(),
+            None => false,
+        };
 
-    for (_, path) in &file_system::files_in_dir(path_to_dir) {
-        retval.push(Block::from_file(&path));
+        if !is_described && name.ne(MANIFEST_FILE_NAME) && name.ne(COMPRESSED_DIR_NAME) {
+            retval.push(Block::from_file(&path, None)?);
+        }
     }
 
     info!("-Read files");
 
-    return retval;
+    return Ok((retval, container));
+}
+
+#[test]
+fn test_from_source() {
+    use std::io::Cursor;
+    use structure::writer;
+    use structure::source::StreamSource;
+
+    let blocks = vec![Block::new("b1", &"data1".as_bytes().to_vec()),
+                      Block::new("b2", &"data2".as_bytes().to_vec())];
+    let data = writer::inflate_cf(&blocks, &ContainerLayout::new(), 2).unwrap();
+
+    let source = source::shared(StreamSource::new(Cursor::new(data)).unwrap());
+    let (test, _) = from_source(&source).unwrap();
+
+    assert_eq!(2, test.len());
+    assert_eq!("b1", test.get(0).unwrap().id());
+    assert_eq!("b2", test.get(1).unwrap().id());
+
+    let nested_blocks = test.get(1).unwrap().get_data().unwrap();
+    assert_eq!(1, nested_blocks.len());
+    assert_eq!("data2".as_bytes().to_vec(), nested_blocks.get(0).unwrap().data);
+}
+
+#[test]
+fn test_from_file_order() {
+    use std::env;
+    use std::path::Path;
+    use structure::writer;
+
+    let path_to_dir = file_system::path_to_str(env::temp_dir()
+        .join("conf_v8_from_file_order")
+        .as_path());
+    file_system::create_dir(&path_to_dir);
+
+    let blocks = vec![Block::new("b", &"data_b".as_bytes().to_vec()),
+                      Block::new("a", &"data_a".as_bytes().to_vec())];
+    writer::deflate_to_files(&blocks, &ContainerLayout::new(), &path_to_dir, 1).unwrap();
+
+    let path_to_new_block = file_system::path_to_str(Path::new(&path_to_dir).join("c").as_path());
+    file_system::write_file(&path_to_new_block, &"data_c".as_bytes().to_vec()).unwrap();
+
+    let ids = |blocks: Vec<Block>| blocks.iter().map(|v| v.id().clone()).collect::<Vec<String>>();
+
+    // порядок оглавления исходного файла, новые блоки в конце
+    let (test, _) = from_file(&path_to_dir).unwrap();
+    assert_eq!(vec!["b", "a", "c"], ids(test));
+
+    // без описания блоки упорядочены по имени
+    let path_to_manifest = file_system::path_to_str(Path::new(&path_to_dir)
+        .join(MANIFEST_FILE_NAME)
+        .as_path());
+    file_system::remove(&path_to_manifest).unwrap();
+
+    let (test, _) = from_file(&path_to_dir).unwrap();
+    file_system::remove(&path_to_dir).unwrap();
+
+    assert_eq!(vec!["a", "b", "c"], ids(test));
 }
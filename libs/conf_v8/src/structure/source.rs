@@ -0,0 +1,133 @@
+use std::io::{Read, Seek, SeekFrom};
+use std::sync::{Arc, Mutex};
+
+/// Источник данных конфигурационного файла, из которого данные читаются по требованию
+pub trait Source: Send {
+    /// Возвращает размер данных источника
+    fn len(&self) -> u64;
+
+    /// Прочитать данные указанного размера, начиная с указанной позиции
+    fn read_at(&mut self, position: u64, size: usize) -> Result<Vec<u8>, String>;
+}
+
+/// Источник, общий для всех блоков конфигурационного файла
+pub type SharedSource = Arc<Mutex<Box<dyn Source>>>;
+
+/// Создать общий источник
+pub fn shared<S: Source + 'static>(source: S) -> SharedSource {
+    Arc::new(Mutex::new(Box::new(source)))
+}
+
+/// Прочитать данные из общего источника
+pub fn read_at(source: &SharedSource, position: u64, size: usize) -> Result<Vec<u8>, String> {
+    match source.lock() {
+        Ok(mut v) => v.read_at(position, size),
+        Err(_) => Err(String::from("Source of the data is poisoned.")),
+    }
+}
+
+/// Данные, полностью загруженные в память
+pub struct MemorySource {
+    data: Vec<u8>,
+}
+
+impl MemorySource {
+    pub fn new(data: Vec<u8>) -> MemorySource {
+        MemorySource { data: data }
+    }
+}
+
+impl Source for MemorySource {
+    fn len(&self) -> u64 {
+        self.data.len() as u64
+    }
+
+    fn read_at(&mut self, position: u64, size: usize) -> Result<Vec<u8>, String> {
+        let start_pos = position as usize;
+        let end_pos = start_pos + size;
+
+        if end_pos > self.data.len() {
+            return Err(format!("Reading is out of the data: data size={}; begin position={}; \
+                                end position={}.",
+                               self.data.len(),
+                               start_pos,
+                               end_pos));
+        }
+
+        Ok(self.data[start_pos..end_pos].to_vec())
+    }
+}
+
+/// Данные, которые читаются из потока (например, из файла) только в момент обращения к ним
+pub struct StreamSource<R: Read + Seek + Send> {
+    reader: R,
+    len: u64,
+}
+
+impl<R: Read + Seek + Send> StreamSource<R> {
+    pub fn new(reader: R) -> Result<StreamSource<R>, String> {
+        let mut reader = reader;
+
+        let len = match reader.seek(SeekFrom::End(0)) {
+            Ok(v) => v,
+            Err(e) => return Err(format!("Failed to get size of the stream: {}", e)),
+        };
+
+        Ok(StreamSource {
+            reader: reader,
+            len: len,
+        })
+    }
+}
+
+impl<R: Read + Seek + Send> Source for StreamSource<R> {
+    fn len(&self) -> u64 {
+        self.len
+    }
+
+    fn read_at(&mut self, position: u64, size: usize) -> Result<Vec<u8>, String> {
+
+        if position + size as u64 > self.len {
+            return Err(format!("Reading is out of the stream: stream size={}; begin \
+                                position={}; end position={}.",
+                               self.len,
+                               position,
+                               position + size as u64));
+        }
+
+        if let Err(e) = self.reader.seek(SeekFrom::Start(position)) {
+            return Err(format!("Failed seek to the position {}: {}", position, e));
+        }
+
+        let mut buffer: Vec<u8> = vec![0u8; size];
+        match self.reader.read_exact(&mut buffer[..]) {
+            Ok(_) => Ok(buffer),
+            Err(e) => Err(format!("Failed reading from the position {}: {}", position, e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Source, MemorySource, StreamSource};
+    use std::io::Cursor;
+
+    #[test]
+    fn test_memory_source() {
+        let mut test = MemorySource::new(vec![1, 2, 3, 4, 5]);
+
+        assert_eq!(5, test.len());
+        assert_eq!(Ok(vec![2, 3]), test.read_at(1, 2));
+        assert!(test.read_at(4, 2).is_err());
+    }
+
+    #[test]
+    fn test_stream_source() {
+        let mut test = StreamSource::new(Cursor::new(vec![1u8, 2, 3, 4, 5])).unwrap();
+
+        assert_eq!(5, test.len());
+        assert_eq!(Ok(vec![4, 5]), test.read_at(3, 2));
+        assert_eq!(Ok(vec![1]), test.read_at(0, 1));
+        assert!(test.read_at(4, 2).is_err());
+    }
+}
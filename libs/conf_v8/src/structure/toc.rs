@@ -1,36 +1,94 @@
 
 use configuration::CF;
-use GROUP_BLOKS_FLAG;
 use structure::reader;
 use structure::header::{Header, is_header, check_control_characters};
 use structure::block_address::BlockAddress;
+use structure::source::{self, SharedSource};
+use structure::layout::{ContainerLayout, ContainerFormat};
+use error::ConfError;
+
+use std::cmp;
 
 // Оглавление конфигурационного файла или группы блоков
 pub struct TOC {
     addresses: Vec<BlockAddress>, // Адреса блоков
+    layout: ContainerLayout, // Префикс и размер области оглавления
 }
 
 impl TOC {
     // Создать новое оглавление
+    #[cfg(test)]
     pub fn new() -> TOC {
-        return TOC { addresses: Vec::new() };
+        return TOC::with_layout(&ContainerLayout::new());
+    }
+
+    // Создать новое оглавление с указанными префиксом и размером области оглавления
+    pub fn with_layout(layout: &ContainerLayout) -> TOC {
+        return TOC {
+            addresses: Vec::new(),
+            layout: layout.clone(),
+        };
     }
 
     // Получить объект оглавления, на основании данных конфигурационного файла
-    pub fn from_cf(data: &Vec<u8>) -> Option<TOC> {
+    pub fn from_cf(data: &Vec<u8>) -> Result<Option<TOC>, ConfError> {
 
         trace!("Init table of contents");
 
-        let reval = match find_toc(data) {
+        let reval = match find_toc(data)? {
             None => None,
             Some(toc_header) => {
-                return Some(TOC { addresses: read_toc(data, &toc_header) });
+                let format = toc_header.format();
+                Some(TOC {
+                    addresses: read_toc(data, &toc_header)?,
+                    layout: ContainerLayout {
+                        prefix: data[0..CF::prefix(format).len()].to_vec(),
+                        toc_page_size: toc_header.full_region_size(),
+                    },
+                })
             }
         };
 
         trace!("-Init table of contents: {}.", reval.is_some());
 
-        return reval;
+        return Ok(reval);
+    }
+
+    // Получить объект оглавления, прочитав из источника только область оглавления
+    pub fn from_source(source: &SharedSource) -> Result<Option<TOC>, ConfError> {
+
+        trace!("Init table of contents from the source");
+
+        let mut reval: Option<TOC> = None;
+
+        for format in [ContainerFormat::Bits32, ContainerFormat::Bits64].iter() {
+            let prefix_size = CF::prefix(*format).len();
+            let data = match source::read_at(source,
+                                             0,
+                                             prefix_size + format.header_size() as usize) {
+                Ok(v) => v,
+                Err(_) => continue,
+            };
+
+            if find_toc(&data)?.is_none() {
+                continue;
+            }
+
+            let toc_header = Header::from_source(source, prefix_size as i64, *format)?;
+            let data_toc = reader::read_block(source, &toc_header)?;
+            reval = Some(TOC {
+                addresses: addresses_from_data(&data_toc, *format)?,
+                layout: ContainerLayout {
+                    prefix: data[0..prefix_size].to_vec(),
+                    toc_page_size: toc_header.full_region_size(),
+                },
+            });
+            break;
+        }
+
+        trace!("-Init table of contents from the source: {}.", reval.is_some());
+
+        return Ok(reval);
     }
 
     // Возвращает адреса оглавления
@@ -38,19 +96,29 @@ impl TOC {
         return &self.addresses;
     }
 
+    // Возвращает префикс и размер области оглавления
+    pub fn layout(&self) -> &ContainerLayout {
+        return &self.layout;
+    }
+
     // Добавить новый адрес
-    pub fn add(&mut self, attr_header: &Header, data_header: &Header) {
+    pub fn add(&mut self, attr_header: &Header, data_header: &Header) -> Result<(), ConfError> {
         let attrs_header_pos = match self.addresses.last() {
-            Some(v) => v.next_block_position(),
+            Some(v) => v.next_block_position()?,
             None => 0,
         };
 
-        let data_header_pos = attrs_header_pos + Header::size() + attr_header.full_region_size();
+        let format = self.layout.format();
+        let data_header_pos = attrs_header_pos + format.header_size() +
+                              attr_header.full_region_size();
         let address = BlockAddress::new(attrs_header_pos,
                                         data_header_pos,
-                                        data_header.full_region_size());
+                                        data_header.full_region_size(),
+                                        format);
 
         self.addresses.push(address);
+
+        Ok(())
     }
 
     // Возвращает данные оглавления для конфигурационного файла
@@ -58,68 +126,91 @@ impl TOC {
 
         trace!("Create table of content.");
 
-        let valuable_toc_size = BlockAddress::size() * self.addresses.len();
+        let format = self.layout.format();
+        let valuable_toc_size = BlockAddress::size(format) * self.addresses.len();
         let mut data: Vec<u8> = Vec::new();
         data.reserve(valuable_toc_size);
 
-        data.extend_from_slice(&CF::prefix()[..]);                  // префикс мультиблока
-        data.extend_from_slice(&Header::for_cf(valuable_toc_size)); // Заголовок области оглавления
+        let mut toc_data: Vec<u8> = Vec::new();
+        toc_data.reserve(valuable_toc_size);
 
-        let begin_file_size = data.len() + valuable_toc_size;
+        let toc_size = cmp::max(valuable_toc_size as i64, self.layout.toc_page_size) as usize;
+        let begin_file_size = self.layout.prefix.len() + format.header_size() as usize + toc_size;
 
         for address in &self.addresses {
-            data.extend_from_slice(&address.for_cf(begin_file_size)[..]);
+            toc_data.extend_from_slice(&address.for_cf(begin_file_size)[..]);
         }
 
+        data.extend_from_slice(&self.layout.prefix[..]); // префикс мультиблока
+        data.extend_from_slice(&Header::region_for_cf(&toc_data, self.layout.toc_page_size, format)); // область оглавления
+
         trace!("-Create table of content.");
 
         return data;
     }
 }
 
-// Найти оглавление
-fn find_toc(data: &Vec<u8>) -> Option<Header> {
+// Найти оглавление. Формат контейнера определяется по маркеру группы в префиксе
+// и формату заголовка оглавления.
+fn find_toc(data: &Vec<u8>) -> Result<Option<Header>, ConfError> {
 
     trace!("Find table of content.");
 
     let mut header: Option<Header> = None;
 
-    if check_control_characters(&data, 0, 0, &GROUP_BLOKS_FLAG) {
+    for format in [ContainerFormat::Bits32, ContainerFormat::Bits64].iter() {
 
-        let header_pos = CF::prefix().len() as i32;
+        if !check_control_characters(&data, 0, 0, format.group_flag()) {
+            continue;
+        }
+
+        let header_pos = CF::prefix(*format).len() as i64;
 
         if is_header(data, header_pos) {
-            header = Some(Header::from_cf(&data, header_pos));
+            let toc_header = Header::from_cf(&data, header_pos)?;
+            if toc_header.format() == *format {
+                header = Some(toc_header);
+                break;
+            }
         }
     }
 
     trace!("-Find table of content: {}.", header.is_none());
 
-    return header;
+    return Ok(header);
 }
 
 // Возвращает коллекцию адресов оглавления, прочитанных из конфигурационного файла
-fn read_toc(data: &Vec<u8>, header_toc: &Header) -> Vec<BlockAddress> {
+fn read_toc(data: &Vec<u8>, header_toc: &Header) -> Result<Vec<BlockAddress>, ConfError> {
 
     trace!("Read table of content. Position={}",
            header_toc.region_position());
 
-    let mut toc: Vec<BlockAddress> = Vec::new();
-    let data_toc = reader::get_block(data, header_toc);
+    let data_toc = reader::get_block(data, header_toc)?;
+    let toc = addresses_from_data(&data_toc, header_toc.format())?;
 
-    let value_size = BlockAddress::size();
+    trace!("-Read table of content. Count addresses={}", toc.len());
+
+    return Ok(toc);
+}
+
+// Возвращает коллекцию адресов, прочитанных из данных области оглавления
+fn addresses_from_data(data_toc: &Vec<u8>,
+                       format: ContainerFormat)
+                       -> Result<Vec<BlockAddress>, ConfError> {
+
+    let mut toc: Vec<BlockAddress> = Vec::new();
+    let value_size = BlockAddress::size(format);
 
     for i in 0..data_toc.len() / value_size {
 
-        match BlockAddress::from_cf(&data_toc, i * value_size) {
+        match BlockAddress::from_cf(&data_toc, i * value_size, format)? {
             Some(val) => toc.push(val),
             None => break,
         }
     }
 
-    trace!("-Read table of content. Count addresses={}", toc.len());
-
-    return toc;
+    return Ok(toc);
 }
 
 #[test]
@@ -133,11 +224,11 @@ fn test_find_toc() {
 
     let header_data = Header::for_cf(data_toc.len());
 
-    let mut data = CF::prefix();
+    let mut data = CF::prefix(ContainerFormat::Bits32);
     data.extend(header_data);
     data.extend(data_toc);
 
-    let toc = match find_toc(&data) {
+    let toc = match find_toc(&data).unwrap() {
         None => panic!("Failed test - find_toc"),
         Some(v) => v,
     };
@@ -151,10 +242,10 @@ fn test_read_toc() {
     let block_data: Vec<u8> = vec![0x64, 0x61, 0x74, 0x61];
     let block_data_header = Header::for_cf(block_data.len());
 
-    let mut conf_file = CF::prefix();
-    let data_size_before_toc = conf_file.len() + Header::size() as usize + BlockAddress::size();
+    let mut conf_file = CF::prefix(ContainerFormat::Bits32);
+    let data_size_before_toc = conf_file.len() + 31 + BlockAddress::size(ContainerFormat::Bits32);
 
-    let source_address = BlockAddress::new(1, 1, block_data.len() as i32);
+    let source_address = BlockAddress::new(1, 1, block_data.len() as i64, ContainerFormat::Bits32);
     let source_address_cf = source_address.for_cf(data_size_before_toc);
     let header_toc = Header::for_cf(source_address_cf.len());
 
@@ -164,7 +255,7 @@ fn test_read_toc() {
     conf_file.extend_from_slice(&block_data_header[..]);
     conf_file.extend_from_slice(&block_data[..]);
 
-    let test = match TOC::from_cf(&conf_file) {
+    let test = match TOC::from_cf(&conf_file).unwrap() {
         None => panic!("Failed create table of contents."),
         Some(v) => v,
     };
@@ -172,9 +263,9 @@ fn test_read_toc() {
     assert_eq!(1, test.addresses().len());
 
     let test_address = test.addresses().get(0).unwrap();
-    assert_eq!(source_address.attr_header_pos() + data_size_before_toc as i32,
+    assert_eq!(source_address.attr_header_pos() + data_size_before_toc as i64,
                test_address.attr_header_pos());
-    assert_eq!(source_address.data_header_pos() + data_size_before_toc as i32,
+    assert_eq!(source_address.data_header_pos() + data_size_before_toc as i64,
                test_address.data_header_pos());
 }
 
@@ -199,13 +290,14 @@ fn test_add() {
     data_block.extend_from_slice(&data_header);
     data_block.extend_from_slice(&data);
 
-    let header_attr_in_block = Header::from_cf(&data_block, 0);
+    let header_attr_in_block = Header::from_cf(&data_block, 0).unwrap();
     let header_data_in_block = Header::from_cf(&data_block,
-                                               (attr_header.len() + attrs.len()) as i32);
+                                               (attr_header.len() + attrs.len()) as i64)
+        .unwrap();
 
     let mut toc = TOC::new();
-    toc.add(&header_attr_in_block, &header_data_in_block);
-    toc.add(&header_attr_in_block, &header_data_in_block);
+    toc.add(&header_attr_in_block, &header_data_in_block).unwrap();
+    toc.add(&header_attr_in_block, &header_data_in_block).unwrap();
 
     for i in 0..toc.addresses().len() {
         let test = toc.addresses().get(i).unwrap();
@@ -214,11 +306,11 @@ fn test_add() {
         assert_eq!(i * data_block.len() + attr_header.len() + attrs.len(),
                    test.data_header_pos() as usize);
         assert_eq!(i * data_block.len() + data_block.len(),
-                   test.next_block_position() as usize);
+                   test.next_block_position().unwrap() as usize);
     }
 
     let cf = toc.for_cf();
-    let test_toc = TOC::from_cf(&cf).unwrap();
+    let test_toc = TOC::from_cf(&cf).unwrap().unwrap();
 
     assert_eq!(toc.addresses().len(), test_toc.addresses().len());
 
@@ -228,3 +320,57 @@ fn test_add() {
                    test_toc.addresses().get(i).unwrap().for_cf(0));
     }
 }
+
+#[test]
+fn test_toc_layout() {
+
+    let mut prefix = CF::prefix(ContainerFormat::Bits32);
+    prefix[8] = 0x01;
+
+    let layout = ContainerLayout {
+        prefix: prefix.clone(),
+        toc_page_size: 512,
+    };
+
+    let header = Header::for_cf(4);
+    let data_block = Header::region_for_cf(&[0x64, 0x61, 0x74, 0x61], 0, ContainerFormat::Bits32);
+    let header_in_block = Header::from_cf(&data_block, 0).unwrap();
+
+    let mut toc = TOC::with_layout(&layout);
+    toc.add(&header_in_block, &header_in_block).unwrap();
+
+    let cf = toc.for_cf();
+    assert_eq!(prefix.len() + header.len() + 512, cf.len());
+
+    let test = TOC::from_cf(&cf).unwrap().unwrap();
+    assert_eq!(&layout, test.layout());
+    assert_eq!(cf.len() as i64, test.addresses().get(0).unwrap().attr_header_pos());
+}
+
+#[test]
+fn test_toc_64() {
+
+    let layout = ContainerLayout {
+        toc_page_size: 512,
+        ..ContainerLayout::with_format(ContainerFormat::Bits64)
+    };
+    assert_eq!(20, layout.prefix.len());
+
+    let data_block = Header::region_for_cf(&[0x64, 0x61, 0x74, 0x61], 0, ContainerFormat::Bits64);
+    let header_in_block = Header::from_cf(&data_block, 0).unwrap();
+
+    let mut toc = TOC::with_layout(&layout);
+    toc.add(&header_in_block, &header_in_block).unwrap();
+    toc.add(&header_in_block, &header_in_block).unwrap();
+
+    let cf = toc.for_cf();
+    assert_eq!(20 + 55 + 512, cf.len());
+
+    let test = TOC::from_cf(&cf).unwrap().unwrap();
+    assert_eq!(&layout, test.layout());
+    assert_eq!(ContainerFormat::Bits64, test.layout().format());
+    assert_eq!(2, test.addresses().len());
+    assert_eq!(cf.len() as i64, test.addresses().get(0).unwrap().attr_header_pos());
+    assert_eq!(cf.len() as i64 + 2 * data_block.len() as i64,
+               test.addresses().get(1).unwrap().attr_header_pos());
+}
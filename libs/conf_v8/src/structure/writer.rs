@@ -1,34 +1,55 @@
 
-
-
 use structure::toc::TOC;
 use structure::block::Block;
 use structure::header::Header;
+use structure::layout::ContainerLayout;
+use structure::manifest::{self, Manifest};
+use error::ConfError;
+
+use std::cmp;
+use std::thread;
+use std::sync::mpsc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Возвращает количество потоков для обработки блоков по умолчанию
+pub fn default_workers() -> usize {
+    match thread::available_parallelism() {
+        Ok(v) => v.get(),
+        Err(_) => 1,
+    }
+}
 
-/// Получить данные для конфигурационного файла
-pub fn inflate_cf(blocks: &Vec<Block>) -> Vec<u8> {
+/// Получить данные для конфигурационного файла.
+/// Данные блоков упаковываются в нескольких потоках, порядок блоков в оглавлении сохраняется.
+pub fn inflate_cf(blocks: &Vec<Block>,
+                  container: &ContainerLayout,
+                  workers: usize)
+                  -> Result<Vec<u8>, ConfError> {
 
     info!("Inflate to the configuration file.");
 
-    let mut toc = TOC::new();
+    let blocks_for_cf = process_blocks(blocks, workers, |block| {
+        let (attrs, data) = block.for_cf()?;
+        Ok((attrs, data, block.pages()))
+    })?;
+
+    let mut toc = TOC::with_layout(container);
     let mut cf_data: Vec<u8> = Vec::new();
 
-    for block in blocks {
-        let (attrs, data) = block.for_cf();
+    let format = container.format();
+
+    for (attrs, data, pages) in blocks_for_cf {
 
-        let mut block_for_cf: Vec<u8> = Vec::new();
-        block_for_cf.extend_from_slice(&Header::for_cf(attrs.len()));
-        block_for_cf.extend_from_slice(&attrs);
+        let mut block_for_cf: Vec<u8> = Header::region_for_cf(&attrs, pages.attrs, format);
 
-        let data_header_pos = block_for_cf.len() as i32;
+        let data_header_pos = block_for_cf.len() as i64;
 
-        block_for_cf.extend_from_slice(&Header::for_cf(data.len()));
-        block_for_cf.extend_from_slice(&data);
+        block_for_cf.extend_from_slice(&Header::region_for_cf(&data, pages.data, format));
 
-        let header_attr = Header::from_cf(&block_for_cf, 0);
-        let header_data = Header::from_cf(&block_for_cf, data_header_pos);
+        let header_attr = Header::from_cf(&block_for_cf, 0)?;
+        let header_data = Header::from_cf(&block_for_cf, data_header_pos)?;
 
-        toc.add(&header_attr, &header_data);
+        toc.add(&header_attr, &header_data)?;
         cf_data.append(&mut block_for_cf);
     }
 
@@ -37,18 +58,121 @@ pub fn inflate_cf(blocks: &Vec<Block>) -> Vec<u8> {
 
     info!("-Inflate to the configuration file.");
 
-    return cf;
+    return Ok(cf);
 }
 
-// Распаковать блоки и записать их файлы
-pub fn deflate_to_files(blocks: &Vec<Block>, path_to_dir: &String) {
+// Распаковать блоки и записать их файлы. Вместе с блоками записываются описание конфигурации
+// и исходные сжатые данные блоков, по которым при сборке восстанавливается исходный
+// конфигурационный файл.
+pub fn deflate_to_files(blocks: &Vec<Block>,
+                        container: &ContainerLayout,
+                        path_to_dir: &String,
+                        workers: usize)
+                        -> Result<(), ConfError> {
 
     info!("Deflate to files.");
 
-    for block in blocks {
+    let layouts = process_blocks(blocks, workers, |block| {
         trace!("Write block to the file: {}", block.id());
-        block.write_to_file(path_to_dir);
-    }
+        block.write_to_file(path_to_dir)?;
+
+        let original_data = block.original_data()?;
+        if !original_data.is_empty() {
+            manifest::write_compressed_data(path_to_dir, block.id(), &original_data)?;
+        }
+
+        block.layout()
+    })?;
+
+    let manifest = Manifest {
+        container: container.clone(),
+        blocks: layouts,
+    };
+    manifest.write(path_to_dir)?;
 
     info!("-Deflate to files.");
+
+    Ok(())
+}
+
+// Обработать блоки в указанном количестве потоков.
+// Результаты возвращаются в порядке следования блоков, при ошибке возвращается
+// ошибка блока, который находится раньше остальных.
+pub fn process_blocks<T, F>(blocks: &Vec<Block>, workers: usize, f: F) -> Result<Vec<T>, ConfError>
+    where T: Send,
+          F: Fn(&Block) -> Result<T, ConfError> + Sync
+{
+    let workers = cmp::max(1, cmp::min(workers, blocks.len()));
+
+    if workers == 1 {
+        return blocks.iter().map(|block| f(block)).collect();
+    }
+
+    debug!("Processing of the blocks: workers={}", workers);
+
+    let next_index = AtomicUsize::new(0);
+    let (sender, receiver) = mpsc::channel();
+
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            let sender = sender.clone();
+            let next_index = &next_index;
+            let f = &f;
+
+            scope.spawn(move || {
+                loop {
+                    let index = next_index.fetch_add(1, Ordering::SeqCst);
+                    let block = match blocks.get(index) {
+                        Some(v) => v,
+                        None => break,
+                    };
+
+                    let result = f(block);
+                    let is_err = result.is_err();
+
+                    if sender.send((index, result)).is_err() || is_err {
+                        // Остальные блоки обрабатывать не нужно
+                        next_index.store(blocks.len(), Ordering::SeqCst);
+                        break;
+                    }
+                }
+            });
+        }
+    });
+
+    drop(sender);
+
+    let mut results: Vec<Option<Result<T, ConfError>>> = (0..blocks.len()).map(|_| None).collect();
+    for (index, result) in receiver {
+        results[index] = Some(result);
+    }
+
+    let mut retval: Vec<T> = Vec::new();
+    for result in results {
+        match result {
+            Some(v) => retval.push(v?),
+            None => (), // блок не обработан из-за ошибки в другом блоке
+        }
+    }
+
+    return Ok(retval);
+}
+
+#[test]
+fn test_inflate_cf_order() {
+    use structure::reader;
+
+    let blocks: Vec<Block> = (0..20)
+        .map(|i| Block::new(&*format!("b{}", i), &format!("data{}", i).into_bytes()))
+        .collect();
+
+    let serial = inflate_cf(&blocks, &ContainerLayout::new(), 1).unwrap();
+    let parallel = inflate_cf(&blocks, &ContainerLayout::new(), 4).unwrap();
+    assert_eq!(serial, parallel);
+
+    let (test, _) = reader::from_cf(parallel).unwrap();
+    assert_eq!(20, test.len());
+    for (i, block) in test.iter().enumerate() {
+        assert_eq!(format!("b{}", i), *block.id());
+    }
 }
@@ -0,0 +1,239 @@
+extern crate conf_v8;
+extern crate zlib_wrapper;
+extern crate file_system;
+
+use conf_v8::{CF, ContainerFormat};
+
+use std::env;
+use std::process;
+
+// Блок конфигурационного файла: атрибуты, данные и размер страницы области данных
+struct TestBlock {
+    attrs: Vec<u8>,
+    data: Vec<u8>,
+    data_page_size: usize,
+}
+
+fn header(valuable_size: usize, page_size: usize, format: ContainerFormat) -> Vec<u8> {
+    let total_size = if valuable_size > page_size { valuable_size } else { page_size };
+    match format {
+        ContainerFormat::Bits32 => {
+            format!("\r\n{:08x} {:08x} 7fffffff \r\n", valuable_size, total_size).into_bytes()
+        }
+        ContainerFormat::Bits64 => {
+            format!("\r\n{:016x} {:016x} ffffffffffffffff \r\n", valuable_size, total_size)
+                .into_bytes()
+        }
+    }
+}
+
+fn region(data: &[u8], page_size: usize, format: ContainerFormat) -> Vec<u8> {
+    let mut retval = header(data.len(), page_size, format);
+    let size = retval.len() + page_size;
+    retval.extend_from_slice(data);
+    while retval.len() < size {
+        retval.push(0x00);
+    }
+    retval
+}
+
+// Адрес в оглавлении контейнера: 4 или 8 байт
+fn address(position: usize, format: ContainerFormat) -> Vec<u8> {
+    match format {
+        ContainerFormat::Bits32 => (position as u32).to_le_bytes().to_vec(),
+        ContainerFormat::Bits64 => (position as u64).to_le_bytes().to_vec(),
+    }
+}
+
+fn attrs(creation_date: u64,
+         modification_date: u64,
+         group_type: i32,
+         id: &str,
+         unknown: [u8; 4])
+         -> Vec<u8> {
+    let mut retval: Vec<u8> = Vec::new();
+    for i in 0..8 {
+        retval.push((creation_date >> (i * 8)) as u8);
+    }
+    for i in 0..8 {
+        retval.push((modification_date >> (i * 8)) as u8);
+    }
+    for i in 0..4 {
+        retval.push((group_type >> (i * 8)) as u8);
+    }
+    for c in id.bytes() {
+        retval.push(c);
+        retval.push(0x00);
+    }
+    retval.extend_from_slice(&unknown);
+    retval
+}
+
+// Собрать контейнер: префикс, оглавление и блоки
+fn container(prefix: &[u8],
+             toc_page_size: usize,
+             blocks: &Vec<TestBlock>,
+             format: ContainerFormat)
+             -> Vec<u8> {
+
+    let address_size = address(0, format).len() * 3;
+    let toc_size = if blocks.len() * address_size > toc_page_size {
+        blocks.len() * address_size
+    } else {
+        toc_page_size
+    };
+    let mut position = prefix.len() + header(0, 0, format).len() + toc_size;
+
+    let mut toc: Vec<u8> = Vec::new();
+    let mut data: Vec<u8> = Vec::new();
+
+    for block in blocks {
+        let attrs_region = region(&block.attrs, 0, format);
+        let data_region = region(&block.data, block.data_page_size, format);
+
+        toc.extend_from_slice(&address(position, format));
+        toc.extend_from_slice(&address(position + attrs_region.len(), format));
+        toc.extend_from_slice(&prefix[0..address_size / 3]); // пустое значение = маркер группы
+
+        position += attrs_region.len() + data_region.len();
+        data.extend_from_slice(&attrs_region);
+        data.extend_from_slice(&data_region);
+    }
+
+    let mut retval = prefix.to_vec();
+    retval.extend_from_slice(&region(&toc, toc_page_size, format));
+    retval.extend_from_slice(&data);
+    retval
+}
+
+// Сжать данные без сжатия: поток deflate из несжатых блоков, который zlib_wrapper не создает
+fn stored_deflate(data: &Vec<u8>) -> Vec<u8> {
+    let mut retval: Vec<u8> = Vec::new();
+    let chunks: Vec<&[u8]> = data.chunks(0xFFFF).collect();
+    for (i, chunk) in chunks.iter().enumerate() {
+        let is_final = i + 1 == chunks.len();
+        retval.push(if is_final { 0x01 } else { 0x00 });
+        retval.extend_from_slice(&(chunk.len() as u16).to_le_bytes());
+        retval.extend_from_slice(&(!(chunk.len() as u16)).to_le_bytes());
+        retval.extend_from_slice(chunk);
+    }
+    retval
+}
+
+fn zlib_compress(data: &Vec<u8>) -> Vec<u8> {
+    zlib_wrapper::compress(data).unwrap()
+}
+
+fn test_cf(format: ContainerFormat) -> Vec<u8> {
+    test_cf_with(format, zlib_compress)
+}
+
+fn test_cf_with(format: ContainerFormat, compress: fn(&Vec<u8>) -> Vec<u8>) -> Vec<u8> {
+
+    let id = "e4bbe7d6-3a92-4d5d-a3e8-d2ee0a2b5c55";
+    let date = 0x0002422330C114B0;
+
+    let mut prefix = CF::prefix(format);
+    let nested_prefix = CF::prefix(format);
+    let unknown_pos = prefix.len() - 8;
+    prefix[unknown_pos] = 0x01;
+
+    let text = TestBlock {
+        attrs: attrs(date, date, 0, "text", [0; 4]),
+        data: "Процедура Тест() КонецПроцедуры".as_bytes().to_vec(),
+        data_page_size: 512,
+    };
+    let info = TestBlock {
+        attrs: attrs(date, date + 0x10, 0, "info", [0; 4]),
+        data: "{3,1,{0},\"\",0}".as_bytes().to_vec(),
+        data_page_size: 0,
+    };
+    let module = container(&nested_prefix, 512, &vec![text, info], format);
+
+    let root = TestBlock {
+        attrs: attrs(date, date + 0x20, 0, "root", [0; 4]),
+        data: compress(&format!("{{2,{},}}", id).into_bytes()),
+        data_page_size: 512,
+    };
+    let module_block = TestBlock {
+        attrs: attrs(date - 0x100, date - 0x100, 740, &format!("{}.0", id), [0x7F, 0xFF, 0xFF, 0xFF]),
+        data: compress(&module),
+        data_page_size: 1024,
+    };
+
+    container(&prefix, 512, &vec![root, module_block], format)
+}
+
+#[test]
+fn test_unpack_build_round_trip() {
+    unpack_build_round_trip(ContainerFormat::Bits32);
+}
+
+#[test]
+fn test_unpack_build_round_trip_64() {
+    unpack_build_round_trip(ContainerFormat::Bits64);
+}
+
+#[test]
+fn test_unpack_build_round_trip_foreign_compression() {
+
+    let path_to_dir = file_system::path_to_str(env::temp_dir()
+        .join(format!("conf_v8_round_trip_stored_{}", process::id()))
+        .as_path());
+    file_system::create_dir(&path_to_dir);
+
+    let data = test_cf_with(ContainerFormat::Bits32, stored_deflate);
+    assert!(data != test_cf(ContainerFormat::Bits32));
+
+    let cf = CF::from_cf(data.clone()).unwrap();
+    cf.deflate_to_files(&path_to_dir).unwrap();
+
+    // после распаковки блоков в памяти исходные сжатые данные сохраняются
+    assert!(data == cf.for_cf().unwrap(), "The rebuilt file differs from the source file.");
+
+    let test = CF::from_file(&path_to_dir).unwrap().for_cf().unwrap();
+    file_system::remove(&path_to_dir).unwrap();
+
+    assert_eq!(data.len(), test.len());
+    assert!(data == test, "The built file differs from the source file.");
+}
+
+#[test]
+fn test_build_container_format() {
+
+    let source = test_cf(ContainerFormat::Bits32);
+    let expected = test_cf(ContainerFormat::Bits64);
+
+    let mut cf = CF::from_cf(source).unwrap();
+    assert_eq!(ContainerFormat::Bits32, cf.container_format());
+
+    cf.set_container_format(ContainerFormat::Bits64).unwrap();
+    let test = cf.for_cf().unwrap();
+
+    // префикс нового формата создается заново, остальные данные совпадают
+    let prefix_size = CF::prefix(ContainerFormat::Bits64).len();
+    assert_eq!(CF::prefix(ContainerFormat::Bits64), &test[0..prefix_size]);
+    assert!(expected[prefix_size..] == test[prefix_size..],
+            "The built file differs from the 64-bit file.");
+    assert_eq!(ContainerFormat::Bits64,
+               CF::from_cf(test).unwrap().container_format());
+}
+
+fn unpack_build_round_trip(format: ContainerFormat) {
+
+    let path_to_dir = file_system::path_to_str(env::temp_dir()
+        .join(format!("conf_v8_round_trip_{:?}_{}", format, process::id()))
+        .as_path());
+    file_system::create_dir(&path_to_dir);
+
+    let data = test_cf(format);
+
+    let cf = CF::from_cf(data.clone()).unwrap();
+    cf.deflate_to_files(&path_to_dir).unwrap();
+
+    let test = CF::from_file(&path_to_dir).unwrap().for_cf().unwrap();
+    file_system::remove(&path_to_dir).unwrap();
+
+    assert_eq!(data.len(), test.len());
+    assert!(data == test, "The built file differs from the source file.");
+}
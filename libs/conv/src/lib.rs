@@ -101,6 +101,16 @@ pub fn int32_to_hex_bytes(value: i32) -> [u8; 8] {
     return buf;
 }
 
+#[inline(always)]
+pub fn int64_to_hex_bytes(value: u64) -> [u8; 16] {
+
+    let hex_str = format!("{:016x}", value);
+    let mut buf: [u8; 16] = [0u8; 16];
+    buf.copy_from_slice(hex_str.as_bytes());
+
+    return buf;
+}
+
 #[inline(always)]
 pub fn hex_to_int(bytes: &[u8]) -> i32 {
 
@@ -125,6 +135,24 @@ pub fn hex_to_int(bytes: &[u8]) -> i32 {
     return result;
 }
 
+#[inline(always)]
+pub fn hex_to_int64(bytes: &[u8]) -> u64 {
+
+    let mut result: u64 = 0;
+
+    for symbol in bytes {
+        if *symbol >= b'0' && *symbol <= b'9' {
+            result = (result << 4) + (*symbol - b'0') as u64;
+        } else if *symbol >= b'a' && *symbol <= b'f' {
+            result = (result << 4) + (*symbol - b'a' + 10) as u64;
+        } else {
+            break;
+        }
+    }
+
+    return result;
+}
+
 #[inline(always)]
 pub fn bytes_to_int64(bytes: &[u8]) -> u64 {
     return LittleEndian::read_u64(&bytes);
@@ -138,8 +166,8 @@ pub fn bytes_to_int32(bytes: &[u8]) -> i32 {
 
 #[cfg(test)]
 mod tests {
-    use {utf16_to_utf8, utf8_to_utf16, hex_to_int, int32_to_hex_bytes, int64_to_bytes,
-         bytes_to_int64, bytes_to_int32, unicode_to_str};
+    use {utf16_to_utf8, utf8_to_utf16, hex_to_int, hex_to_int64, int32_to_hex_bytes,
+         int64_to_hex_bytes, int64_to_bytes, bytes_to_int64, bytes_to_int32, unicode_to_str};
 
     #[test]
     fn test_utf16_utf8() {
@@ -169,6 +197,17 @@ mod tests {
         assert_eq!(hex_to_int(&data), hex_to_int(&test));
     }
 
+    #[test]
+    fn test_int64_to_hex_bytes() {
+
+        let data = b"00000000000000ac";
+        let test = int64_to_hex_bytes(172);
+
+        assert_eq!(data, &test);
+        assert_eq!(172, hex_to_int64(&test));
+        assert_eq!(u64::MAX, hex_to_int64(&int64_to_hex_bytes(u64::MAX)));
+    }
+
     #[test]
     fn test_int64_to_bytes() {
         assert_eq!([0xE4, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
@@ -7,7 +7,7 @@ use std::fs::{self, File, create_dir_all, metadata};
 use std::io::{Read, BufWriter, Write};
 use std::path::Path;
 use std::env::{current_dir, split_paths};
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 // Прочитать данные файла в буфер
 pub fn read_file(path: &str) -> Result<Vec<u8>, String> {
@@ -52,10 +52,11 @@ pub fn write_file(path: &str, data: &Vec<u8>) -> Result<(), String> {
     };
 }
 
-// Возвращает коллекцию с путями к файлам, которые находятся внутри каталога
-pub fn files_in_dir(path_to_dir: &String) -> HashMap<String, String> {
+// Возвращает коллекцию с путями к файлам, которые находятся внутри каталога.
+// Файлы упорядочены по имени, чтобы результат обхода каталога не зависел от файловой системы.
+pub fn files_in_dir(path_to_dir: &String) -> BTreeMap<String, String> {
 
-    let mut retval: HashMap<String, String> = HashMap::new();
+    let mut retval: BTreeMap<String, String> = BTreeMap::new();
 
     if is_dir(path_to_dir) {
         let dir = match fs::read_dir(path_to_dir) {
@@ -212,7 +213,8 @@ pub fn get_current_dir() -> Result<String, String> {
 
 #[cfg(test)]
 mod tests {
-    use {read_file, path_to_str, get_current_dir, exist};
+    use {read_file, write_file, create_dir, remove, files_in_dir, path_to_str, get_current_dir,
+         exist};
     use std::path::Path;
 
     #[test]
@@ -234,4 +236,22 @@ mod tests {
         };
         assert_eq!(36908, buffer.len());
     }
+
+    #[test]
+    fn test_files_in_dir_sorted() {
+        use std::env;
+
+        let path_to_dir = path_to_str(env::temp_dir().join("file_system_files_in_dir").as_path());
+        create_dir(&path_to_dir);
+
+        for name in ["module", "form", "info", "a.0", "text"].iter() {
+            let path = path_to_str(Path::new(&path_to_dir).join(name).as_path());
+            write_file(&path, &Vec::new()).unwrap();
+        }
+
+        let test: Vec<String> = files_in_dir(&path_to_dir).keys().cloned().collect();
+        remove(&path_to_dir).unwrap();
+
+        assert_eq!(vec!["a.0", "form", "info", "module", "text"], test);
+    }
 }
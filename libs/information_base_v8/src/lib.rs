@@ -284,7 +284,7 @@ mod tests {
             Err(e) => panic!("{}", e),
         };
 
-        return settings::Settings::new(&pom1c_xml);
+        return settings::Settings::new(&pom1c_xml).unwrap();
     }
 
     fn get_target_dir() -> String {
@@ -11,79 +11,63 @@ pub mod metadata;
 pub struct Settings {
     source_ib_connection_settings: HashMap<String, String>, /* Настройки подключения к исходной информационной базе */
     metadata_selections: Vec<metadata::Metadata>, // Настройки отбора метаданных
+    metadata_exclusions: Vec<metadata::Metadata>, // Объекты, которые исключаются из отбора
+    subsystem_selections: Vec<metadata::Metadata>, // Подсистемы, объекты которых отбираются
+    dependencies: bool, // Оставить объекты метаданных, на которые ссылаются отобранные объекты
 }
 
 impl Settings {
     // Пример xml файла с настройками: conf_robber/test_data/pom1c.xml
-    pub fn new(xml_text: &String) -> Settings {
+    // Возвращает ошибку, если xml не разобран или отбор объекта задан неверно.
+    pub fn new(xml_text: &String) -> Result<Settings, String> {
 
-        let xml = xml::XmlDOM::parse(xml_text);
+        let xml = xml::XmlDOM::parse(xml_text)?;
         let xml_root = xml.root();
 
         let mut source_ib_connection_settings: HashMap<String, String> = HashMap::new();
         let mut metadata_selections: Vec<metadata::Metadata> = Vec::new();
+        let mut metadata_exclusions: Vec<metadata::Metadata> = Vec::new();
+        let mut subsystem_selections: Vec<metadata::Metadata> = Vec::new();
+        let mut dependencies = false;
 
         match xml_root.first("sourceIB") {
             Some(v) => {
                 source_ib_connection_settings = v.attributes.clone();
 
-                metadata_selections = match v.first("objects") {
-                    None => Vec::new(),
-                    Some(v) => {
-                        let mut collection: Vec<metadata::Metadata> = Vec::new();
-                        for item in &v.childrens {
-                            let object_name = match item.attributes.get("name") {
-                                Some(v) => v,
-                                None => {
-                                    error!("Not found attribute name.");
-                                    panic!("Not found attribute name.");
-                                }
-                            };
-
-                            let mut except_forms = <Vec<String>>::new();
-                            let mut except_templates = <Vec<String>>::new();
-                            for child_node in &item.childrens {
-                                if child_node.name == "except_forms" {
-                                    except_forms = child_node.text
-                                        .clone()
-                                        .split(',')
-                                        .map(|x| x.trim().to_string())
-                                        .collect();
-                                }
-                                if child_node.name == "except_templates" {
-                                    except_templates = child_node.text
-                                        .clone()
-                                        .split(',')
-                                        .map(|x| x.trim().to_string())
-                                        .collect();
-                                }
+                if let Some(v) = v.first("objects") {
+                    dependencies = v.attributes
+                        .get("dependencies")
+                        .map_or(false, |x| x.eq("true"));
+
+                    for item in &v.childrens {
+                        // Исключения: <exclude><Тип name="..." regex="..."/></exclude>
+                        if item.name == "exclude" {
+                            for child in &item.childrens {
+                                metadata_exclusions.push(metadata_object(child)?);
                             }
+                            continue;
+                        }
 
-                            let object = match metadata::Metadata::new(&item.name,
-                                                                       &object_name,
-                                                                       &item.attributes,
-                                                                       except_forms,
-                                                                       except_templates) {
-                                Err(e) => {
-                                    error!("{}", e);
-                                    panic!("{}", e);
-                                }
-                                Ok(v) => v,
-                            };
-
-                            collection.push(object);
+                        // Объекты подсистемы: <subsystem name="..." [main="true"]/>
+                        if item.name == "subsystem" {
+                            subsystem_selections.push(metadata_object(item)?);
+                            continue;
                         }
-                        collection
+
+                        metadata_selections.push(metadata_object(item)?);
                     }
-                };
+                }
             }
             _ => (),
         }
 
-        Settings {
+        Ok(Settings {
             source_ib_connection_settings: source_ib_connection_settings,
             metadata_selections: metadata_selections,
-        }
+            metadata_exclusions: metadata_exclusions,
+            subsystem_selections: subsystem_selections,
+            dependencies: dependencies,
+        })
     }
 
     /// Возвращает параметры подключения к исходной базе данных
@@ -101,6 +85,37 @@ impl Settings {
         };
     }
 
+    /// Возвращает признак того, что для всех отобранных объектов нужно оставить объекты
+    /// метаданных, на которые они ссылаются
+    pub fn dependencies(&self) -> bool {
+        return self.dependencies;
+    }
+
+    /// Проверить, что объект исключен из отбора
+    pub fn is_excluded(&self, type_name: &str, object_name: &str) -> bool {
+        self.metadata_exclusions
+            .iter()
+            .any(|x| type_name.eq(&**x.type_name()) && x.is_match(object_name))
+    }
+
+    /// Возвращает имена типов метаданных, указанные в отборе и исключениях
+    pub fn metadata_type_names(&self) -> Vec<&String> {
+        let mut retval: Vec<&String> = self.metadata_selections
+            .iter()
+            .chain(self.metadata_exclusions.iter())
+            .map(|x| x.type_name())
+            .collect();
+        retval.sort();
+        retval.dedup();
+
+        return retval;
+    }
+
+    /// Возвращает параметры отбора подсистем, все объекты которых отбираются
+    pub fn subsystem_selections(&self) -> &Vec<metadata::Metadata> {
+        &self.subsystem_selections
+    }
+
     /// Возвращает параметры отбора метаданных запрошенного типа
     pub fn metadata_selections(&self, type_name: &String) -> Option<Vec<metadata::Metadata>> {
 
@@ -120,6 +135,74 @@ impl Settings {
     }
 }
 
+// Прочитать настройки отбора объекта из узла <Тип name="..." [regex="..."] [main="true"]>
+fn metadata_object(item: &xml::XmlElement) -> Result<metadata::Metadata, String> {
+
+    let object_name = match (item.attributes.get("name"), item.attributes.get("regex")) {
+        (Some(v), _) => v.clone(),
+        (None, Some(_)) => String::new(),
+        (None, None) => {
+            error!("Not found attribute name.");
+            return Err(format!("Not found attribute name: <{}>.", item.name));
+        }
+    };
+
+    // Отбор вложенных объектов: <except_<вид>>имя, ...</except_<вид>>, <only_<вид>>...</only_<вид>>
+    let mut except: Vec<(&str, Vec<String>)> = Vec::new();
+    let mut only: Vec<(&str, Vec<String>)> = Vec::new();
+    for child_node in &item.childrens {
+        let names: Vec<String> = child_node.text
+            .clone()
+            .split(',')
+            .map(|x| x.trim().to_string())
+            .collect();
+
+        let mut is_known = false;
+        for kind in metadata::NESTED_KINDS.iter() {
+            if child_node.name == format!("except_{}", kind) {
+                except.push((*kind, names.clone()));
+                is_known = true;
+            } else if child_node.name == format!("only_{}", kind) {
+                only.push((*kind, names.clone()));
+                is_known = true;
+            }
+        }
+
+        // Опечатка в имени узла не должна приводить к тому, что отбор молча не выполняется
+        if !is_known {
+            let message = format!("Unknown node <{}> of the object <{} name=\"{}\">. Expected \
+                                   except_<kind> or only_<kind>, where kind: {}.",
+                                  child_node.name,
+                                  item.name,
+                                  object_name,
+                                  metadata::NESTED_KINDS.join(", "));
+            error!("{}", message);
+            return Err(message);
+        }
+    }
+
+    let mut retval = match metadata::Metadata::new(&item.name,
+                                                   &object_name,
+                                                   &item.attributes,
+                                                   Vec::new(),
+                                                   Vec::new()) {
+        Err(e) => {
+            error!("{}", e);
+            return Err(e);
+        }
+        Ok(v) => v,
+    };
+
+    for (kind, names) in except {
+        retval.set_except(kind, names);
+    }
+    for (kind, names) in only {
+        retval.set_only(kind, names);
+    }
+
+    return Ok(retval);
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -163,6 +246,120 @@ mod tests {
                    test_data.except_templates());
     }
 
+    #[test]
+    fn test_dependencies() {
+        assert!(!create().dependencies());
+
+        let settings = Settings::new(&String::from("<project><sourceIB><objects dependencies=\"true\">\
+                                                    <Документы name=\"Заказ\"/></objects>\
+                                                    </sourceIB></project>")).unwrap();
+        assert!(settings.dependencies());
+        assert!(settings.metadata_selections(&String::from("Документы")).is_some());
+    }
+
+    #[test]
+    fn test_exclusions() {
+        let settings = Settings::new(&String::from("<project><sourceIB><objects>\
+                                                    <Справочники name=\"ок_*\"/>\
+                                                    <ОбщиеМодули regex=\"ок_\\w+\"/>\
+                                                    <exclude>\
+                                                    <Справочники name=\"ок_*_Служебный\"/>\
+                                                    </exclude>\
+                                                    </objects></sourceIB></project>")).unwrap();
+
+        let catalogs = settings.metadata_selections(&String::from("Справочники")).unwrap();
+        assert_eq!(1, catalogs.len());
+        assert!(catalogs[0].is_match("ок_Валюты_Служебный"));
+
+        let modules = settings.metadata_selections(&String::from("ОбщиеМодули")).unwrap();
+        assert!(modules[0].is_match("ок_Сервер"));
+        assert!(settings.metadata_selections(&String::from("exclude")).is_none());
+
+        assert!(settings.subsystem_selections().is_empty());
+        assert!(settings.is_excluded("Справочники", "ок_Валюты_Служебный"));
+        assert!(!settings.is_excluded("Справочники", "ок_Валюты"));
+        assert!(!settings.is_excluded("ОбщиеМодули", "ок_Валюты_Служебный"));
+
+        assert_eq!(vec!["ОбщиеМодули", "Справочники"], settings.metadata_type_names());
+    }
+
+    #[test]
+    fn test_subsystem_selections() {
+        let settings = Settings::new(&String::from("<project><sourceIB><objects>\
+                                                    <subsystem name=\"Продажи\" main=\"true\"/>\
+                                                    </objects></sourceIB></project>")).unwrap();
+
+        let subsystems = settings.subsystem_selections();
+        assert_eq!(1, subsystems.len());
+        assert!(subsystems[0].is_match("Продажи"));
+        assert!(subsystems[0].main());
+        assert!(settings.metadata_selections(&String::from("subsystem")).is_none());
+    }
+
+    #[test]
+    fn test_nested_objects() {
+        let settings = Settings::new(&String::from("<project><sourceIB><objects>\
+                                                    <Справочники name=\"Товары\" main=\"true\">\
+                                                    <except_tabular_sections>История*\
+                                                    </except_tabular_sections>\
+                                                    <only_commands>Печать, Открыть</only_commands>\
+                                                    </Справочники>\
+                                                    </objects></sourceIB></project>")).unwrap();
+
+        let catalogs = settings.metadata_selections(&String::from("Справочники")).unwrap();
+        assert_eq!(vec![String::from("История*")], catalogs[0].except("tabular_sections"));
+        assert_eq!(vec![String::from("Печать"), String::from("Открыть")],
+                   catalogs[0].only("commands"));
+        assert!(catalogs[0].except_forms().is_empty());
+    }
+
+    #[test]
+    fn test_settings_errors() {
+        assert!(Settings::new(&String::from("<project><sourceIB>")).is_err());
+
+        let settings = Settings::new(&String::from("<project><sourceIB><objects>\
+                                                    <Справочники main=\"true\"/>\
+                                                    </objects></sourceIB></project>"));
+        assert_eq!(Some(String::from("Not found attribute name: <Справочники>.")),
+                   settings.err());
+    }
+
+    #[test]
+    fn test_names_with_yo() {
+        let settings = Settings::new(&String::from("<project><sourceIB><objects>\
+                                                    <ПланыСчетов name=\"СчётаУчёта\"/>\
+                                                    <Справочники name=\"Ёмкости*\"/>\
+                                                    </objects></sourceIB></project>")).unwrap();
+
+        let charts = settings.metadata_selections(&String::from("ПланыСчетов")).unwrap();
+        assert!(charts[0].is_match("СчётаУчёта"));
+        assert!(!charts[0].is_match("СчетаУчета"));
+
+        let catalogs = settings.metadata_selections(&String::from("Справочники")).unwrap();
+        assert!(catalogs[0].is_match("ЁмкостиТары"));
+    }
+
+    #[test]
+    fn test_invalid_regex() {
+        let settings = Settings::new(&String::from("<project><sourceIB><objects>\
+                                                    <ОбщиеМодули regex=\"ок_(\\w+\"/>\
+                                                    </objects></sourceIB></project>"));
+
+        assert!(settings.err().unwrap().starts_with("Failed regex 'ок_(\\w+'"));
+    }
+
+    #[test]
+    fn test_unknown_nested_node() {
+        let settings = Settings::new(&String::from("<project><sourceIB><objects>\
+                                                    <Справочники name=\"Товары\" main=\"true\">\
+                                                    <except_form>Форма</except_form>\
+                                                    </Справочники>\
+                                                    </objects></sourceIB></project>"));
+
+        assert!(settings.err().unwrap().starts_with("Unknown node <except_form> of the object \
+                                                     <Справочники name=\"Товары\">"));
+    }
+
     #[test]
     fn test_source_ib_connection_settings() {
         let settings = create();
@@ -198,6 +395,6 @@ mod tests {
             Err(e) => panic!("{}", e),
         };
 
-        return Settings::new(&pom1c_xml);
+        return Settings::new(&pom1c_xml).unwrap();
     }
 }
@@ -2,14 +2,25 @@
 use regex;
 use std::collections::HashMap;
 
+/// Виды вложенных объектов, отбор которых задается узлами except_<вид> и only_<вид>
+pub const NESTED_KINDS: [&'static str; 5] = ["forms",
+                                             "templates",
+                                             "commands",
+                                             "attributes",
+                                             "tabular_sections"];
+
 /// Настройки объекта
 #[derive(PartialEq,Debug,Clone)]
 pub struct Metadata {
     type_name: String, /* Наименование типа которому принадлежит объект */
-    name: String, // Наименование
+    name: String, /* Шаблон наименования: '*' - любые символы, '?' - один любой символ */
+    regex: Option<regex::Regex>, /* Регулярное выражение, которому должно полностью соответствовать наименование */
+    exact: bool, /* Наименование сравнивается без шаблона, как есть */
     main: bool, /* Является основным объектом сборки (не будут удаляться команды, шаблоны, формы) */
-    except_forms: Vec<String>, /* Удалить формы из основного объекта сборки */
-    except_templates: Vec<String>, /* Удалить шаблоны из основного объекта сборки */
+    dependencies: bool, /* Оставить объекты метаданных, на которые ссылается объект */
+    except: HashMap<String, Vec<String>>, /* Удалить вложенные объекты вида (формы, шаблоны и т.д.)
+                                           * из основного объекта сборки */
+    only: HashMap<String, Vec<String>>, /* Оставить только указанные вложенные объекты вида */
 }
 
 impl Metadata {
@@ -20,7 +31,7 @@ impl Metadata {
                except_templates: Vec<String>)
                -> Result<Metadata, String> {
 
-        let re = match regex::Regex::new(r"(?P<bad_symbol>[^a-zA-Zа-яА-Я0-9_*])") {
+        let re = match regex::Regex::new(r"(?P<bad_symbol>[^a-zA-Zа-яА-ЯёЁ0-9_*?])") {
             Ok(v) => v,
             Err(e) => {
                 panic!(r"Failed regex string: {}", e);
@@ -29,21 +40,92 @@ impl Metadata {
 
         let name_temp = re.replace_all(&*name.clone(), "<$bad_symbol>");
 
+        let name_regex = match attributes.get("regex") {
+            None => None,
+            Some(v) => {
+                match regex::Regex::new(&format!("^(?:{})$", v)) {
+                    Ok(v) => Some(v),
+                    Err(e) => return Err(format!("Failed regex '{}': {}", v, e)),
+                }
+            }
+        };
+
         if name_temp.find("<").is_some() {
             return Err(format!("Filed name: {}", name_temp));
-        } else if name_temp.is_empty() {
+        } else if name_temp.is_empty() && name_regex.is_none() {
             return Err(format!("Empty name of metadata object: {:?}", type_name));
         }
 
         Ok(Metadata {
             type_name: type_name.clone(),
             name: name_temp,
+            regex: name_regex,
+            exact: false,
             main: attributes.get("main").unwrap_or(&String::from("false")).eq("true"),
-            except_forms: except_forms,
-            except_templates: except_templates,
+            dependencies: attributes.get("dependencies").unwrap_or(&String::from("false")).eq("true"),
+            except: vec![(String::from("forms"), except_forms),
+                         (String::from("templates"), except_templates)]
+                .into_iter()
+                .collect(),
+            only: HashMap::new(),
         })
     }
 
+    /// Создать настройки объекта с точным наименованием (например, для объекта, на который
+    /// ссылается отобранный объект). Наименование не проверяется и не является шаблоном.
+    pub fn with_exact_name(type_name: &String, name: &String) -> Metadata {
+        Metadata {
+            type_name: type_name.clone(),
+            name: name.clone(),
+            regex: None,
+            exact: true,
+            main: false,
+            dependencies: false,
+            except: HashMap::new(),
+            only: HashMap::new(),
+        }
+    }
+
+    /// Установить имена вложенных объектов вида, которые удаляются
+    pub fn set_except(&mut self, kind: &str, names: Vec<String>) {
+        self.except.insert(kind.to_string(), names);
+    }
+
+    /// Установить имена вложенных объектов вида, которые оставляются (остальные удаляются)
+    pub fn set_only(&mut self, kind: &str, names: Vec<String>) {
+        self.only.insert(kind.to_string(), names);
+    }
+
+    /// Проверить, что вложенный объект вида остается: имя соответствует одному из шаблонов
+    /// only_<вид> (если они указаны) и не соответствует ни одному из шаблонов except_<вид>
+    pub fn is_nested_kept(&self, kind: &str, name: &str) -> bool {
+
+        let only = self.only(kind);
+        if !only.is_empty() && !only.iter().any(|x| glob_match(x, name)) {
+            return false;
+        }
+
+        return !self.except(kind).iter().any(|x| glob_match(x, name));
+    }
+
+    /// Проверить соответствие имени объекта шаблону наименования и регулярному выражению.
+    /// Если указаны и шаблон, и регулярное выражение, то имя должно соответствовать обоим.
+    pub fn is_match(&self, object_name: &str) -> bool {
+
+        if self.exact {
+            return self.name.eq(object_name);
+        }
+
+        if !self.name.is_empty() && !glob_match(&self.name, object_name) {
+            return false;
+        }
+
+        return match self.regex {
+            Some(ref v) => v.is_match(object_name),
+            None => true,
+        };
+    }
+
     pub fn type_name<'a>(&'a self) -> &'a String {
         &self.type_name
     }
@@ -56,15 +138,57 @@ impl Metadata {
         self.main
     }
 
+    pub fn dependencies(&self) -> bool {
+        self.dependencies
+    }
+
     pub fn except_forms(&self) -> Vec<String> {
-        self.except_forms.clone()
+        self.except("forms")
     }
 
     pub fn except_templates(&self) -> Vec<String> {
-        self.except_templates.clone()
+        self.except("templates")
+    }
+
+    pub fn except(&self, kind: &str) -> Vec<String> {
+        self.except.get(kind).cloned().unwrap_or_default()
+    }
+
+    pub fn only(&self, kind: &str) -> Vec<String> {
+        self.only.get(kind).cloned().unwrap_or_default()
     }
 }
 
+/// Сравнить имя с шаблоном: '*' - любые символы (в том числе ни одного), '?' - один любой символ
+pub fn glob_match(pattern: &str, name: &str) -> bool {
+
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+
+    let (mut p, mut n) = (0, 0);
+    // Позиция последней '*' в шаблоне и позиция в имени, с которой она сопоставлена
+    let mut star: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, n));
+            p += 1;
+        } else if let Some((star_p, star_n)) = star {
+            // '*' поглощает еще один символ имени
+            star = Some((star_p, star_n + 1));
+            p = star_p + 1;
+            n = star_n + 1;
+        } else {
+            return false;
+        }
+    }
+
+    return pattern[p..].iter().all(|&c| c == '*');
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -78,6 +202,23 @@ mod tests {
         assert_eq!("type", test_data.type_name());
         assert_eq!("name", test_data.name());
         assert_eq!(true, test_data.main());
+        assert_eq!(false, test_data.dependencies());
+    }
+
+    #[test]
+    fn test_dependencies_attribute() {
+
+        let mut attributes: HashMap<String, String> = HashMap::new();
+        attributes.insert(String::from("dependencies"), String::from("true"));
+
+        let test_data = Metadata::new(&String::from("type"),
+                                      &String::from("name"),
+                                      &attributes,
+                                      <Vec<String>>::new(),
+                                      <Vec<String>>::new())
+            .unwrap();
+        assert_eq!(true, test_data.dependencies());
+        assert_eq!(false, test_data.main());
     }
 
     #[test]
@@ -96,10 +237,96 @@ mod tests {
         let test_data = create(r"azAZаяАЯ09_*", "").unwrap();
         assert_eq!(r"azAZаяАЯ09_*", test_data.name());
 
+        let test_data = create(r"СчётаУчёта_Ё*", "").unwrap();
+        assert!(test_data.is_match("СчётаУчёта_Ёмкости"));
+
         let test_data = create(r"nameаяАЯ+?-", "");
         assert!(test_data.is_err());
-        assert_eq!(Err(String::from(r"Filed name: nameаяАЯ<+><?><->")),
+        assert_eq!(Err(String::from(r"Filed name: nameаяАЯ<+>?<->")),
                    test_data);
+
+        let test_data = create(r"ок_*_Служ?бный", "").unwrap();
+        assert!(test_data.is_match("ок_Заказы_Служебный"));
+        assert!(!test_data.is_match("ок_Заказы"));
+    }
+
+    #[test]
+    fn test_exact_name() {
+
+        let test_data = Metadata::with_exact_name(&String::from("type"),
+                                                  &String::from("СчётФактура"));
+        assert_eq!("СчётФактура", test_data.name());
+        assert!(test_data.is_match("СчётФактура"));
+        assert!(!test_data.is_match("СчетФактура"));
+        assert!(!test_data.main());
+        assert!(!test_data.dependencies());
+
+        let test_data = Metadata::with_exact_name(&String::from("type"), &String::from("Счёт*"));
+        assert!(!test_data.is_match("Счёт1"));
+    }
+
+    #[test]
+    fn test_regex_attribute() {
+
+        let mut attributes: HashMap<String, String> = HashMap::new();
+        attributes.insert(String::from("regex"), String::from(r"ок_\w+_(Служебный|Тест)"));
+
+        let test_data = Metadata::new(&String::from("type"),
+                                      &String::new(),
+                                      &attributes,
+                                      <Vec<String>>::new(),
+                                      <Vec<String>>::new())
+            .unwrap();
+        assert!(test_data.is_match("ок_Заказы_Тест"));
+        assert!(!test_data.is_match("ок_Заказы_Тест2")); // выражение должно совпадать с именем полностью
+        assert!(!test_data.is_match("Заказы"));
+
+        attributes.insert(String::from("regex"), String::from(r"(ок_"));
+        let test_data = Metadata::new(&String::from("type"),
+                                      &String::new(),
+                                      &attributes,
+                                      <Vec<String>>::new(),
+                                      <Vec<String>>::new());
+        assert!(test_data.is_err());
+    }
+
+    #[test]
+    fn test_nested_objects() {
+
+        let mut test_data = Metadata::new(&String::from("type"),
+                                          &String::from("name"),
+                                          &HashMap::new(),
+                                          vec![String::from("ФормаЭлемента")],
+                                          Vec::new())
+            .unwrap();
+        test_data.set_except("tabular_sections", vec![String::from("Тяжелая*")]);
+        test_data.set_only("commands", vec![String::from("Печать*"), String::from("Открыть")]);
+
+        assert!(!test_data.is_nested_kept("forms", "ФормаЭлемента"));
+        assert!(test_data.is_nested_kept("forms", "ФормаСписка"));
+        assert!(!test_data.is_nested_kept("tabular_sections", "ТяжелаяИстория"));
+        assert!(test_data.is_nested_kept("tabular_sections", "Товары"));
+        assert!(test_data.is_nested_kept("commands", "ПечатьСчета"));
+        assert!(!test_data.is_nested_kept("commands", "Удалить"));
+        assert!(test_data.is_nested_kept("attributes", "Код"));
+        assert_eq!(vec![String::from("ФормаЭлемента")], test_data.except_forms());
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("*", ""));
+        assert!(glob_match("*", "name"));
+        assert!(glob_match("name", "name"));
+        assert!(!glob_match("name", "names"));
+        assert!(glob_match("test*", "test_1"));
+        assert!(glob_match("*test", "my_test"));
+        assert!(!glob_match("*test", "test_1"));
+        assert!(glob_match("ок_*_Служебный", "ок_Заказы_Служебный"));
+        assert!(glob_match("ок_*_Служебный", "ок_А_Б_Служебный"));
+        assert!(!glob_match("ок_*_Служебный", "ок_Служебный"));
+        assert!(glob_match("a?c*", "abcdef"));
+        assert!(!glob_match("a?c", "ac"));
+        assert!(glob_match("**a**", "bab"));
     }
 
     fn create(name: &str, main: &str) -> Result<Metadata, String> {
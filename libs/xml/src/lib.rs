@@ -49,7 +49,9 @@ pub struct XmlDOM {
 }
 
 impl XmlDOM {
-    pub fn parse(xml_text: &String) -> XmlDOM {
+    /// Parse the text of the xml. Returns an error if the xml is malformed or has not exactly
+    /// one root element.
+    pub fn parse(xml_text: &String) -> Result<XmlDOM, String> {
 
         let parser = xml::reader::EventReader::from_str(&*(*xml_text));
         let mut childrens: Vec<XmlElement> = Vec::new();
@@ -93,7 +95,7 @@ impl XmlDOM {
                     let parent: *mut XmlElement = match stack.last() {
                         None => {
                             error!("Not found parent element for text.");
-                            panic!("Not found parent element for text.");
+                            return Err(String::from("Not found parent element for text."));
                         }
                         Some(v) => *v,
                     };
@@ -107,8 +109,8 @@ impl XmlDOM {
                     stack.pop();
                 }
                 Err(e) => {
-                    println!("Failed xml: {}", e);
-                    break;
+                    error!("Failed xml: {}", e);
+                    return Err(format!("Failed xml: {}", e));
                 }
                 _ => {}
             }
@@ -116,10 +118,10 @@ impl XmlDOM {
 
         if childrens.len() != 1 {
             error!("A few root elements.");
-            panic!("A few root elements.");
+            return Err(String::from("A few root elements."));
         }
 
-        return XmlDOM { childrens: childrens };
+        return Ok(XmlDOM { childrens: childrens });
     }
 
     pub fn root(&self) -> &XmlElement {
@@ -135,7 +137,7 @@ mod tests {
     fn test_xml_parse() {
 
         let pom1c_xml = get_test_data();
-        let xml = XmlDOM::parse(&pom1c_xml);
+        let xml = XmlDOM::parse(&pom1c_xml).unwrap();
         let root = xml.root();
         assert_eq!("project", root.name);
         assert_eq!("", root.text);
@@ -169,6 +171,14 @@ mod tests {
         assert_eq!(1, source_ib.find("objects").len());
     }
 
+    #[test]
+    fn test_xml_parse_error() {
+        assert!(XmlDOM::parse(&String::new()).is_err());
+        assert!(XmlDOM::parse(&String::from("<project><sourceIB>")).is_err());
+        assert!(XmlDOM::parse(&String::from("<project></sourceIB>")).is_err());
+        assert!(XmlDOM::parse(&String::from("<project/>")).is_ok());
+    }
+
     fn get_test_data() -> String {
         extern crate file_system;
         use std::path::Path;
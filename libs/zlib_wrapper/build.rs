@@ -1,10 +1,15 @@
 
 use std::process::Command;
-use std::env::{current_dir, split_paths};
+use std::env::{self, current_dir, split_paths};
 use std::path::Path;
 
 fn main() {
 
+    // C++ библиотека нужна только при сборке с опцией "cpp"
+    if env::var_os("CARGO_FEATURE_CPP").is_none() {
+        return;
+    }
+
     let paths = current_dir().ok().expect("Failed to get current directory");
     let path_to_current_dir = split_paths(&paths).nth(0).unwrap();
     let path_to_cpp = match path_to_current_dir.as_path()
@@ -0,0 +1,69 @@
+// Сжатие и распаковка данных с помощью C++ библиотеки zlib
+
+use std::ptr;
+use std::slice;
+
+// Интерфейс C++ библиотеки
+mod zlib {
+    #[link(name = "zlibwrapper")]
+    extern "C" {
+        #[no_mangle]
+        pub fn compress_data(source_data: *const u8,
+                             source_data_size: u32,
+                             data: &*mut u8,
+                             size: *mut u32)
+                             -> bool;
+        #[no_mangle]
+        pub fn decompress_data(source_data: *const u8,
+                               source_data_size: u32,
+                               data: &*mut u8,
+                               size: *mut u32)
+                               -> bool;
+        #[no_mangle]
+        pub fn free_data(data: &*mut u8);
+    }
+}
+
+// Сжать данные
+pub fn compress(source_data: &Vec<u8>) -> Result<Vec<u8>, String> {
+
+    let src: &[u8] = &source_data[..];
+    let mut retval: Vec<u8> = Vec::new();
+
+    unsafe {
+        let data: *mut u8 = ptr::null_mut();
+        let mut size: u32 = 0;
+
+        if zlib::compress_data(src.as_ptr(), src.len() as u32, &data, &mut size) == true {
+            retval.extend_from_slice(slice::from_raw_parts(data, size as usize));
+            zlib::free_data(&data);
+        } else {
+            trace!("Unable to compress the data: \n{:?}.", source_data);
+            return Err(String::from("Unable to compress the data."));
+        }
+    }
+
+    return Ok(retval);
+}
+
+// Распаковать данные
+pub fn decompress(source_data: &Vec<u8>) -> Result<Vec<u8>, String> {
+
+    let src: &[u8] = &source_data[..];
+    let mut retval: Vec<u8> = Vec::new();
+
+    unsafe {
+        let data: *mut u8 = ptr::null_mut();
+        let mut size: u32 = 0;
+
+        if zlib::decompress_data(src.as_ptr(), src.len() as u32, &data, &mut size) == true {
+            retval.extend_from_slice(slice::from_raw_parts(data, size as usize));
+            zlib::free_data(&data);
+        } else {
+            trace!("Unable to decompress the data: \n{:?}.", source_data);
+            return Err(String::from("Unable to decompress the data."));
+        }
+    }
+
+    return Ok(retval);
+}
@@ -0,0 +1,127 @@
+// Сжатие и распаковка данных на Rust (без C++ библиотеки zlib)
+//
+// Данные блоков конфигурационного файла сжимаются без заголовка zlib, а последний
+// блок сжатых данных не помечается как завершающий и не дополняется до границы байта
+// (так же, как это делает C++ библиотека при сжатии с параметром Z_BLOCK).
+
+use flate2::{Decompress, FlushDecompress, Status};
+use miniz_oxide::deflate::core::{compress_to_output, CompressorOxide, TDEFLFlush, TDEFLStatus};
+use miniz_oxide::DataFormat;
+
+const BUFFER_SIZE: usize = 16384;
+const BEST_COMPRESSION: u8 = 9;
+
+// Сжать данные
+pub fn compress(source_data: &Vec<u8>) -> Result<Vec<u8>, String> {
+
+    let mut retval: Vec<u8> = Vec::with_capacity(source_data.len() / 2 + BUFFER_SIZE);
+    if source_data.is_empty() {
+        return Ok(retval);
+    }
+
+    let mut compressor = CompressorOxide::default();
+    compressor.set_format_and_level(DataFormat::Raw, BEST_COMPRESSION);
+
+    // Сброс без синхронизации: биты последнего байта, который заполнен не полностью,
+    // в результат не попадают
+    let (status, consumed) = compress_to_output(&mut compressor,
+                                                &source_data[..],
+                                                TDEFLFlush::NoSync,
+                                                |data| {
+                                                    retval.extend_from_slice(data);
+                                                    true
+                                                });
+
+    if status != TDEFLStatus::Okay || consumed != source_data.len() {
+        trace!("Unable to compress the data: {:?}\n{:?}.", status, source_data);
+        return Err(String::from("Unable to compress the data."));
+    }
+
+    // Обычно в неполном байте остается только часть кода конца блока. Если же без него
+    // данные не распаковываются полностью, то неполный байт добавляется в результат.
+    if compressor.unwritten_bit_count() != 0 &&
+       decompress(&retval).ok().as_ref() != Some(source_data) {
+
+        let mut tail: Vec<u8> = Vec::new();
+        let (status, _) = compress_to_output(&mut compressor, &[], TDEFLFlush::SyncOpt, |data| {
+            tail.extend_from_slice(data);
+            true
+        });
+
+        match (status, tail.first()) {
+            (TDEFLStatus::Okay, Some(v)) => retval.push(*v),
+            _ => {
+                trace!("Unable to compress the data: {:?}\n{:?}.", status, source_data);
+                return Err(String::from("Unable to compress the data."));
+            }
+        }
+    }
+
+    return Ok(retval);
+}
+
+// Распаковать данные
+pub fn decompress(source_data: &Vec<u8>) -> Result<Vec<u8>, String> {
+
+    let mut decompressor = Decompress::new(false);
+    let mut retval: Vec<u8> = Vec::with_capacity(source_data.len() * 2 + BUFFER_SIZE);
+
+    loop {
+        let consumed = decompressor.total_in() as usize;
+        let produced = decompressor.total_out();
+
+        if retval.len() == retval.capacity() {
+            retval.reserve(retval.capacity());
+        }
+
+        let status = match decompressor.decompress_vec(&source_data[consumed..],
+                                                       &mut retval,
+                                                       FlushDecompress::None) {
+            Ok(v) => v,
+            Err(e) => {
+                trace!("Unable to decompress the data: {}\n{:?}.", e, source_data);
+                return Err(String::from("Unable to decompress the data."));
+            }
+        };
+
+        // Последний блок сжатых данных не завершен, поэтому распаковка заканчивается,
+        // когда данные больше не поступают
+        let is_progress = decompressor.total_in() as usize != consumed ||
+                          decompressor.total_out() != produced;
+
+        if status == Status::StreamEnd ||
+           (!is_progress && retval.len() < retval.capacity()) {
+            break;
+        }
+    }
+
+    // Распаковка остановилась до конца данных: данные повреждены
+    if (decompressor.total_in() as usize) < source_data.len() {
+        trace!("Unable to decompress the data: unexpected data at the position {}\n{:?}.",
+               decompressor.total_in(),
+               source_data);
+        return Err(String::from("Unable to decompress the data."));
+    }
+
+    return Ok(retval);
+}
+
+#[test]
+fn test_compress_repeated_data() {
+
+    // без неполного байта теряется часть кода последнего повтора
+    let data: Vec<u8> = vec![0x61; 133];
+    let compress_data = compress(&data).unwrap();
+
+    assert_eq!(data, decompress(&compress_data).unwrap());
+}
+
+#[test]
+fn test_decompress_data_after_end() {
+
+    // завершающий блок "hello" и данные после него
+    let data: Vec<u8> = vec![0xCB, 0x48, 0xCD, 0xC9, 0xC9, 0x07, 0x00, 0x01, 0x02];
+
+    assert!(decompress(&data).is_err());
+    assert_eq!(b"hello".to_vec(), decompress(&data[..7].to_vec()).unwrap());
+}
@@ -1,83 +1,31 @@
 
 #[macro_use]
 extern crate log;
+#[cfg(feature = "cpp")]
 extern crate libc;
+#[cfg(not(feature = "cpp"))]
+extern crate flate2;
+#[cfg(not(feature = "cpp"))]
+extern crate miniz_oxide;
+#[cfg(test)]
 extern crate file_system;
 
-use std::ptr;
-use std::slice;
-
-// Интерфейс C++ библиотеки
-mod zlib {
-    #[link(name = "zlibwrapper")]
-    extern "C" {
-        #[no_mangle]
-        pub fn compress_data(source_data: *const u8,
-                             source_data_size: u32,
-                             data: &*mut u8,
-                             size: *mut u32)
-                             -> bool;
-        #[no_mangle]
-        pub fn decompress_data(source_data: *const u8,
-                               source_data_size: u32,
-                               data: &*mut u8,
-                               size: *mut u32)
-                               -> bool;
-        #[no_mangle]
-        pub fn free_data(data: &*mut u8);
-    }
-}
-
-// Сжать данные
-pub fn compress(source_data: &Vec<u8>) -> Vec<u8> {
-
-    let src: &[u8] = &source_data[..];
-    let mut retval: Vec<u8> = Vec::new();
-
-    unsafe {
-        let data: *mut u8 = ptr::null_mut();
-        let mut size: u32 = 0;
-
-        if zlib::compress_data(src.as_ptr(), src.len() as u32, &data, &mut size) == true {
-            retval.extend_from_slice(slice::from_raw_parts(data, size as usize));
-            zlib::free_data(&data);
-        } else {
-            error!("Unable to compress the data: \n{:?}.", source_data);
-            panic!("Unable to compress the data.");
-        }
-    }
-
-    return retval;
-}
-
-// Распаковать данные
-pub fn decompress(source_data: &Vec<u8>) -> Vec<u8> {
-
-    let src: &[u8] = &source_data[..];
-    let mut retval: Vec<u8> = Vec::new();
-
-    unsafe {
-        let data: *mut u8 = ptr::null_mut();
-        let mut size: u32 = 0;
-
-        if zlib::decompress_data(src.as_ptr(), src.len() as u32, &data, &mut size) == true {
-            retval.extend_from_slice(slice::from_raw_parts(data, size as usize));
-            zlib::free_data(&data);
-        } else {
-            error!("Unable to decompress the data: \n{:?}.", source_data);
-            panic!("Unable to decompress the data.");
-        }
-    }
+#[cfg(feature = "cpp")]
+mod cpp;
+#[cfg(not(feature = "cpp"))]
+mod deflate;
 
-    return retval;
-}
+#[cfg(feature = "cpp")]
+pub use cpp::{compress, decompress};
+#[cfg(not(feature = "cpp"))]
+pub use deflate::{compress, decompress};
 
 #[test]
 fn test_zlib_compress() {
 
     let data: Vec<u8> = vec![0x68, 0x65, 0x6C, 0x6C, 0x6F]; // hello
     let res: Vec<u8> = vec![0xCA, 0x48, 0xCD, 0xC9, 0xC9, 0x07];
-    let test = compress(&data);
+    let test = compress(&data).unwrap();
 
     assert_eq!(res, test);
 }
@@ -87,7 +35,7 @@ fn test_zlib_decompress() {
 
     let data: Vec<u8> = vec![0xCA, 0x48, 0xCD, 0xC9, 0xC9, 0x07];
     let res: Vec<u8> = vec![0x68, 0x65, 0x6C, 0x6C, 0x6F]; // hello
-    let test = decompress(&data);
+    let test = decompress(&data).unwrap();
 
     assert_eq!(res.len(), test.len());
     assert_eq!(res, test);
@@ -97,12 +45,54 @@ fn test_zlib_decompress() {
 fn test_zlib_compress_decompress() {
 
     let data: Vec<u8> = vec![0x68, 0x65, 0x6C, 0x6C, 0x6F]; // hello
-    let compress_data = compress(&data);
-    let decompress_data = decompress(&compress_data);
+    let compress_data = compress(&data).unwrap();
+    let decompress_data = decompress(&compress_data).unwrap();
 
     assert_eq!(data, decompress_data);
 }
 
+// Генератор псевдослучайных чисел для тестов (xorshift)
+#[cfg(test)]
+fn random_data(seed: u64, len: usize, alphabet: usize) -> Vec<u8> {
+    let mut state = seed;
+    let mut retval: Vec<u8> = Vec::with_capacity(len);
+
+    while retval.len() < len {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        retval.push((state % alphabet as u64) as u8);
+    }
+
+    retval
+}
+
+#[test]
+fn test_zlib_random_round_trip() {
+
+    let sizes = [0usize, 1, 16384, 63589, 65536, 65537, 200000, 1 << 20];
+
+    for (i, size) in sizes.iter().enumerate() {
+        let seed = 0x9E3779B97F4A7C15 ^ i as u64;
+
+        // случайные (несжимаемые) данные и данные с малым алфавитом и повторами
+        let mut compressible = random_data(seed, *size / 2, 4);
+        let text = b"{1,{0,0,e4bbe7d6-3a92-4d5d-a3e8-d2ee0a2b5c55},\"\xd0\xa2\xd0\xbe\xd0\xb2\xd0\xb0\xd1\x80\",0}";
+        while compressible.len() < *size {
+            compressible.extend_from_slice(text);
+        }
+        compressible.truncate(*size);
+
+        for data in [random_data(seed, *size, 256), compressible].iter() {
+            let compress_data = compress(data).unwrap();
+            let decompress_data = decompress(&compress_data).unwrap();
+
+            assert_eq!(data.len(), decompress_data.len());
+            assert!(*data == decompress_data, "Round trip failed: size={}", size);
+        }
+    }
+}
+
 #[test]
 fn test_zlib_big_data() {
     use std::path::Path;
@@ -124,8 +114,8 @@ fn test_zlib_big_data() {
 
     while !data.is_empty() {
 
-        let compress_data = compress(&data);
-        let decompress_data = decompress(&compress_data);
+        let compress_data = compress(&data).unwrap();
+        let decompress_data = decompress(&compress_data).unwrap();
 
         assert_eq!(decompress_data, data);
 
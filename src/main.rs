@@ -4,43 +4,172 @@ extern crate log;
 extern crate logger;
 extern crate conf_v8;
 extern crate file_system;
+extern crate clap;
 
 mod utils;
 
 use std::env;
+use std::process;
 use std::path::Path;
 
+use clap::ErrorKind;
+use conf_v8::{ConfError, ContainerFormat, ContainerKind, Format, Stage};
+use utils::args::Command;
+
+// Коды завершения программы
+const EXIT_FAILED_PARAMETERS: i32 = 1; // неверные параметры запуска
+const EXIT_IO: i32 = 2; // ошибка чтения или записи файлов
+const EXIT_HEADER: i32 = 3; // ошибка чтения заголовка области
+const EXIT_TOC: i32 = 4; // ошибка чтения оглавления
+const EXIT_ATTRIBUTES: i32 = 5; // ошибка чтения атрибутов блока
+const EXIT_INFLATE: i32 = 6; // ошибка распаковки (упаковки) данных блока
+const EXIT_METADATA: i32 = 7; // ошибка разбора описания объектов метаданных
+const EXIT_MERGE_CONFLICTS: i32 = 8; // при объединении конфигураций обнаружены конфликты
+
 fn main() {
 
-    let args = utils::args::Args::new(env::args().collect());
-    println!("Operation type={}", args.operation());
+    let args = match utils::args::Args::new(env::args_os()) {
+        Ok(v) => v,
+        Err(e) => {
+            match e.kind {
+                ErrorKind::HelpDisplayed | ErrorKind::VersionDisplayed => {
+                    println!("{}", e.message);
+                    process::exit(0);
+                }
+                _ => {
+                    eprintln!("{}", e.message);
+                    process::exit(EXIT_FAILED_PARAMETERS);
+                }
+            }
+        }
+    };
 
-    match &*args.operation() {
-        "-P" => {
-            unpack_to_dir(args.cf().unwrap(),
-                          args.target().unwrap(),
-                          args.log_level(),
-                          args.settings())
+    let result = match *args.command() {
+        Command::Unpack { ref cf, ref target, ref settings, threads, dry_run, json } => {
+            match (dry_run, settings.as_ref()) {
+                (true, Some(settings)) => filter_report(cf, target, args.log_level(), settings, json),
+                _ => unpack_to_dir(cf, target, args.log_level(), settings.as_ref(), threads),
+            }
+        }
+        Command::Build { ref dir, ref target, threads, format } => {
+            build_cf(dir, target, args.log_level(), threads, format)
+        }
+        Command::Format { ref dir, ref target } => {
+            format_text(dir, target, args.log_level(), Format::Pretty)
         }
-        "-B" => {
-            build_cf(args.dir().unwrap(),
-                     args.target().unwrap(),
-                     args.log_level())
+        Command::Minify { ref dir, ref target } => {
+            format_text(dir, target, args.log_level(), Format::Minify)
         }
-        "-F" => {
-            format_text(args.dir().unwrap(),
-                        args.target().unwrap(),
-                        args.log_level())
+        Command::Export { ref cf, ref target, threads } => {
+            export_to_dir(cf, target, args.log_level(), threads)
         }
-        _ => panic!("Failed parameters."),
+        Command::Import { ref dir, ref target, threads } => {
+            import_cf(dir, target, args.log_level(), threads)
+        }
+        Command::ExtractModules { ref cf, ref target, threads } => {
+            extract_modules(cf, target, args.log_level(), threads)
+        }
+        Command::ExtractExternal { ref cf, ref name, ref target } => {
+            extract_external(cf, name, target, args.log_level())
+        }
+        Command::EmbedExternal { ref cf, ref external, ref target } => {
+            embed_external(cf, external, target, args.log_level())
+        }
+        Command::Diff { ref old, ref new, ref target, json } => {
+            diff_cf(old, new, target, args.log_level(), json)
+        }
+        Command::Merge { ref base, ref ours, ref theirs, ref target, unpack } => {
+            match merge_cf(base, ours, theirs, target, args.log_level(), unpack) {
+                Ok(false) => {
+                    eprintln!("Merge conflicts. See conflicts.txt in the target directory.");
+                    process::exit(EXIT_MERGE_CONFLICTS);
+                }
+                v => v.map(|_| ()),
+            }
+        }
+    };
+
+    match result {
+        Ok(_) => (),
+        Err(e) => {
+            error!("{}", e);
+            eprintln!("{}", e);
+            process::exit(exit_code(&e));
+        }
+    }
+}
+
+// Возвращает код завершения программы по этапу, на котором возникла ошибка
+fn exit_code(e: &ConfError) -> i32 {
+    match e.stage() {
+        Stage::Io => EXIT_IO,
+        Stage::Header => EXIT_HEADER,
+        Stage::Toc => EXIT_TOC,
+        Stage::Attributes => EXIT_ATTRIBUTES,
+        Stage::Inflate => EXIT_INFLATE,
+        Stage::Metadata => EXIT_METADATA,
+    }
+}
+
+// Прочитать данные файла
+fn read_file(path: &String) -> Result<Vec<u8>, ConfError> {
+    match file_system::read_file(path) {
+        Ok(v) => Ok(v),
+        Err(e) => Err(ConfError::Io {
+            path: path.clone(),
+            block_id: None,
+            position: None,
+            message: e,
+        }),
+    }
+}
+
+// Прочитать текст файла настроек
+fn read_settings(path: &String) -> Result<String, ConfError> {
+    match String::from_utf8(read_file(path)?) {
+        Ok(v) => Ok(v),
+        Err(e) => Err(ConfError::Io {
+            path: path.clone(),
+            block_id: None,
+            position: None,
+            message: format!("{}", e),
+        }),
+    }
+}
+
+// Записать данные в файл
+fn write_file(path: &String, data: &Vec<u8>) -> Result<(), ConfError> {
+    match file_system::write_file(path, data) {
+        Ok(_) => Ok(()),
+        Err(e) => Err(ConfError::Io {
+            path: path.clone(),
+            block_id: None,
+            position: None,
+            message: e,
+        }),
     }
 }
 
+// Возвращает путь к собираемому файлу: configuration.cf для конфигурации, <Имя>.cfe для расширения,
+// <Имя>.epf или <Имя>.erf для внешней обработки или отчета
+fn output_file_name(cf: &conf_v8::CF, path_to_target_dir: &String) -> Result<String, ConfError> {
+
+    let file_name = match cf.kind()? {
+        ContainerKind::Configuration => String::from("configuration.cf"),
+        ContainerKind::Extension => format!("{}.cfe", cf.extension()?.name()),
+        kind => format!("{}.{}", cf.external_object()?.name(), kind.extension()),
+    };
+
+    return Ok(file_system::path_to_str(&Path::new(&path_to_target_dir).join(file_name)));
+}
+
 // Распаковать конфигурационный файл в каталог
 fn unpack_to_dir(path_to_cf: &String,
                  path_to_target_dir: &String,
                  log_level: Option<&String>,
-                 settings: Option<&String>) {
+                 settings: Option<&String>,
+                 threads: Option<usize>)
+                 -> Result<(), ConfError> {
 
     logger::init_log(&path_to_target_dir, log_level);
 
@@ -50,131 +179,333 @@ fn unpack_to_dir(path_to_cf: &String,
     let unpack_dir = file_system::path_to_str(&Path::new(&path_to_target_dir).join("unpack"));
     file_system::create_dir(&*unpack_dir);
 
-    match file_system::read_file(&*path_to_cf) {
-        Err(v) => {
-            error!("{}", v);
-            panic!("{}", v)
+    let mut cf = conf_v8::CF::from_path(path_to_cf)?;
+    if let Some(threads) = threads {
+        cf.set_workers(threads);
+    }
+
+    if let Ok(kind) = cf.kind() {
+        info!("Kind of the file: *.{}", kind.extension());
+
+        // Для расширения записываем список собственных и заимствованных объектов
+        if kind == ContainerKind::Extension {
+            let file_name = file_system::path_to_str(&Path::new(&path_to_target_dir)
+                .join("extension.txt"));
+            write_file(&file_name, &cf.extension()?.to_text().into_bytes())?;
         }
-        Ok(data) => {
-            let mut cf = conf_v8::CF::from_cf(&data);
-            if settings.is_some() {
-                let settings = settings.unwrap();
-                let settings = match file_system::read_file(&settings) {
-                    Ok(v) => String::from_utf8(v).unwrap(),
-                    Err(e) => panic!("{}", e),
-                };
-
-                cf.filter(&settings);
-            }
+    }
 
-            cf.deflate_to_files(&unpack_dir);
+    if settings.is_some() {
+        let settings = read_settings(settings.unwrap())?;
+
+        let dependencies = cf.filter(&settings)?;
+        if !dependencies.is_empty() {
+            let text: String = dependencies.iter()
+                .map(|v| format!("{}\n", v.description()))
+                .collect();
+            let file_name = file_system::path_to_str(&Path::new(&path_to_target_dir)
+                .join("dependencies.txt"));
+            write_file(&file_name, &text.into_bytes())?;
         }
     }
 
+    cf.deflate_to_files(&unpack_dir)?;
+
     info!("End");
+
+    Ok(())
 }
 
-// Упаковать данные каталога в конфигурационный файл
-fn build_cf(path_to_dir: &String, path_to_target_dir: &String, log_level: Option<&String>) {
+// Записать отчет о фильтрации конфигурационного файла по настройкам в файл
+// filter_report.txt или filter_report.json, не распаковывая конфигурацию
+fn filter_report(path_to_cf: &String,
+                 path_to_target_dir: &String,
+                 log_level: Option<&String>,
+                 settings: &String,
+                 json: bool)
+                 -> Result<(), ConfError> {
 
     logger::init_log(&path_to_target_dir, log_level);
 
-    info!("Path to the directory:{}", path_to_dir);
+    info!("Path to the configuration file:{}", path_to_cf);
+    info!("Path to the settings file:{}", settings);
     info!("Begin");
 
-    let file_name = file_system::path_to_str(&Path::new(&path_to_target_dir)
-        .join("configuration.cf"));
+    let settings = read_settings(settings)?;
+    let cf = conf_v8::CF::from_path(path_to_cf)?;
 
-    let cf = conf_v8::CF::from_file(&path_to_dir);
-    match file_system::write_file(&*file_name, &cf.for_cf()) {
-        Ok(_) => (),
-        Err(e) => {
-            error!("Error writing file of the result: {}", e);
-            panic!("Error writing file of the result: {}", e);
-        }
+    let report = cf.filter_report(&settings)?;
+
+    let (file_name, text) = match json {
+        true => ("filter_report.json", report.to_json()),
+        false => ("filter_report.txt", report.to_text()),
+    };
+    let file_name = file_system::path_to_str(&Path::new(&path_to_target_dir).join(file_name));
+    write_file(&file_name, &text.into_bytes())?;
+
+    info!("End. Kept objects: {}, removed objects: {}",
+          report.kept.len(),
+          report.removed.len());
+
+    Ok(())
+}
+
+// Выгрузить конфигурационный файл в каталог в виде дерева объектов метаданных
+fn export_to_dir(path_to_cf: &String,
+                 path_to_target_dir: &String,
+                 log_level: Option<&String>,
+                 threads: Option<usize>)
+                 -> Result<(), ConfError> {
+
+    logger::init_log(&path_to_target_dir, log_level);
+
+    info!("Path to the configuration file:{}", path_to_cf);
+    info!("Begin");
+
+    let export_dir = file_system::path_to_str(&Path::new(&path_to_target_dir).join("export"));
+    file_system::create_dir(&*export_dir);
+
+    let mut cf = conf_v8::CF::from_path(path_to_cf)?;
+    if let Some(threads) = threads {
+        cf.set_workers(threads);
     }
 
+    cf.export_to_dir(&export_dir)?;
+
     info!("End");
+
+    Ok(())
 }
 
-// Выполнить форматирование текста распакованных блоков конфигурации
-fn format_text(path_to_dir: &String, path_to_target_dir: &String, log_level: Option<&String>) {
+// Записать тексты модулей конфигурационного файла в каталог
+fn extract_modules(path_to_cf: &String,
+                   path_to_target_dir: &String,
+                   log_level: Option<&String>,
+                   threads: Option<usize>)
+                   -> Result<(), ConfError> {
 
     logger::init_log(&path_to_target_dir, log_level);
 
-    info!("Path to the directory:{}", path_to_dir);
+    info!("Path to the configuration file:{}", path_to_cf);
     info!("Begin");
 
-    dir_iterator(path_to_dir);
+    let modules_dir = file_system::path_to_str(&Path::new(&path_to_target_dir).join("modules"));
 
-    info!("End");
+    let mut cf = conf_v8::CF::from_path(path_to_cf)?;
+    if let Some(threads) = threads {
+        cf.set_workers(threads);
+    }
+
+    let count = cf.extract_modules(&modules_dir)?;
+
+    info!("End. Modules: {}", count);
+
+    Ok(())
 }
 
-fn dir_iterator(dir: &String) {
+// Извлечь обработку или отчет конфигурации и записать в файл <Имя>.epf или <Имя>.erf
+fn extract_external(path_to_cf: &String,
+                    name: &String,
+                    path_to_target_dir: &String,
+                    log_level: Option<&String>)
+                    -> Result<(), ConfError> {
 
-    let add_tabs = |count: &usize, vec: &mut Vec<u8>, pos: &mut usize| {
-        let count_spaces = *count * 4;
-        for _ in 0..count_spaces {
-            vec.insert(*pos, b' ');
-        }
-        *pos += count_spaces;
+    logger::init_log(&path_to_target_dir, log_level);
+
+    info!("Path to the configuration file:{}", path_to_cf);
+    info!("Begin");
+
+    let cf = conf_v8::CF::from_path(path_to_cf)?;
+    let external = cf.extract_external(name)?;
+
+    let file_name = output_file_name(&external, path_to_target_dir)?;
+    write_file(&file_name, &external.for_cf()?)?;
+
+    info!("End. File: {}", file_name);
+
+    Ok(())
+}
+
+// Встроить внешнюю обработку или отчет в конфигурацию и записать результат в файл configuration.cf
+fn embed_external(path_to_cf: &String,
+                  path_to_external: &String,
+                  path_to_target_dir: &String,
+                  log_level: Option<&String>)
+                  -> Result<(), ConfError> {
+
+    logger::init_log(&path_to_target_dir, log_level);
+
+    info!("Path to the configuration file:{}", path_to_cf);
+    info!("Path to the external file:{}", path_to_external);
+    info!("Begin");
+
+    let mut cf = conf_v8::CF::from_path(path_to_cf)?;
+    let external = conf_v8::CF::from_path(path_to_external)?;
+
+    let id = cf.embed_external(&external)?;
+
+    let file_name = output_file_name(&cf, path_to_target_dir)?;
+    write_file(&file_name, &cf.for_cf()?)?;
+
+    info!("End. Object id: {}", id);
+
+    Ok(())
+}
+
+// Сравнить конфигурационные файлы и записать различия в файл diff.txt или diff.json
+fn diff_cf(path_to_old_cf: &String,
+           path_to_new_cf: &String,
+           path_to_target_dir: &String,
+           log_level: Option<&String>,
+           json: bool)
+           -> Result<(), ConfError> {
+
+    logger::init_log(&path_to_target_dir, log_level);
+
+    info!("Path to the original configuration file:{}", path_to_old_cf);
+    info!("Path to the changed configuration file:{}", path_to_new_cf);
+    info!("Begin");
+
+    let old_cf = conf_v8::CF::from_path(path_to_old_cf)?;
+    let new_cf = conf_v8::CF::from_path(path_to_new_cf)?;
+
+    let diff = old_cf.diff(&new_cf)?;
+
+    let (file_name, text) = match json {
+        true => ("diff.json", diff.to_json()),
+        false => ("diff.txt", diff.to_text()),
     };
+    let file_name = file_system::path_to_str(&Path::new(&path_to_target_dir).join(file_name));
+    write_file(&file_name, &text.into_bytes())?;
 
-    for (_, path) in &file_system::files_in_dir(dir) {
+    info!("End. Changed objects: {}", diff.objects.len());
 
-        if file_system::is_dir(path) {
-            dir_iterator(path);
-        } else {
-            let mut text = match file_system::read_file(path) {
-                Ok(v) => v,
-                Err(e) => {
-                    error!("{}", e);
-                    panic!("{}", e);
-                }
-            };
-
-            let mut pos = 0;
-            let mut prev: u8 = 0;
-            let mut tabs: usize = 0;
-
-            while pos < text.len() {
-                let s = *text.get(pos).unwrap();
-                match s {
-                    b'{' => {
-                        tabs += 1;
-                        if prev.ne(&b'\n') {
-                            text.insert(pos, b'\n');
-                            pos += 1;
-                        }
-                        add_tabs(&tabs, &mut text, &mut pos);
-                        prev = b'{';
-                    }
-                    b'}' => {
-                        let next = match text.get(pos + 1) {
-                            None => 0x00,
-                            Some(v) => *v,
-                        };
-                        if next.ne(&b'\r') {
-                            pos += 1;
-                            text.insert(pos, b'\n');
-                            prev = b'\n';
-                            pos += 1;
-                        }
-                        tabs -= 1;
-                    }
-                    _ => (),
-                }
+    Ok(())
+}
 
-                if prev.eq(&b'\n') {
-                    let tmp_tabs = tabs + 1;
-                    add_tabs(&tmp_tabs, &mut text, &mut pos);
-                }
+// Объединить изменения конфигурационных файлов и записать результат в файл configuration.cf
+// (<Имя>.epf или <Имя>.erf для внешней обработки или отчета).
+// При конфликтах записывается файл conflicts.txt и, если указано, распакованная конфигурация
+// с маркерами конфликтов в каталог unpack. Возвращает false, если есть конфликты.
+fn merge_cf(path_to_base_cf: &String,
+            path_to_ours_cf: &String,
+            path_to_theirs_cf: &String,
+            path_to_target_dir: &String,
+            log_level: Option<&String>,
+            unpack: bool)
+            -> Result<bool, ConfError> {
 
-                prev = s;
-                pos += 1;
-            }
+    logger::init_log(&path_to_target_dir, log_level);
 
-            let _ = file_system::write_file(path, &text);
-        }
+    info!("Path to the base configuration file:{}", path_to_base_cf);
+    info!("Path to our configuration file:{}", path_to_ours_cf);
+    info!("Path to their configuration file:{}", path_to_theirs_cf);
+    info!("Begin");
+
+    let base_cf = conf_v8::CF::from_path(path_to_base_cf)?;
+    let ours_cf = conf_v8::CF::from_path(path_to_ours_cf)?;
+    let theirs_cf = conf_v8::CF::from_path(path_to_theirs_cf)?;
+
+    let (cf, conflicts) = conf_v8::CF::merge(&base_cf, &ours_cf, &theirs_cf)?;
+
+    if conflicts.is_empty() {
+        let file_name = output_file_name(&cf, path_to_target_dir)?;
+        write_file(&file_name, &cf.for_cf()?)?;
+
+        info!("End");
+
+        return Ok(true);
+    }
+
+    let text: String = conflicts.iter().map(|v| format!("{}\n", v.description())).collect();
+    let file_name = file_system::path_to_str(&Path::new(&path_to_target_dir).join("conflicts.txt"));
+    write_file(&file_name, &text.into_bytes())?;
+
+    if unpack {
+        let unpack_dir = file_system::path_to_str(&Path::new(&path_to_target_dir).join("unpack"));
+        cf.deflate_to_files(&unpack_dir)?;
     }
+
+    info!("End. Conflicts: {}", conflicts.len());
+
+    Ok(false)
+}
+
+// Упаковать данные каталога в конфигурационный файл (внешнюю обработку или отчет)
+fn build_cf(path_to_dir: &String,
+            path_to_target_dir: &String,
+            log_level: Option<&String>,
+            threads: Option<usize>,
+            format: Option<ContainerFormat>)
+            -> Result<(), ConfError> {
+
+    logger::init_log(&path_to_target_dir, log_level);
+
+    info!("Path to the directory:{}", path_to_dir);
+    info!("Begin");
+
+    let mut cf = conf_v8::CF::from_file(&path_to_dir)?;
+    if let Some(threads) = threads {
+        cf.set_workers(threads);
+    }
+    if let Some(format) = format {
+        info!("Address size: {:?}", format);
+        cf.set_container_format(format)?;
+    }
+    let file_name = output_file_name(&cf, path_to_target_dir)?;
+    write_file(&file_name, &cf.for_cf()?)?;
+
+    info!("End");
+
+    Ok(())
+}
+
+// Собрать конфигурационный файл из выгруженного дерева объектов метаданных
+fn import_cf(path_to_dir: &String,
+             path_to_target_dir: &String,
+             log_level: Option<&String>,
+             threads: Option<usize>)
+             -> Result<(), ConfError> {
+
+    logger::init_log(&path_to_target_dir, log_level);
+
+    info!("Path to the directory:{}", path_to_dir);
+    info!("Begin");
+
+    let mut cf = conf_v8::CF::from_export(&path_to_dir)?;
+    if let Some(threads) = threads {
+        cf.set_workers(threads);
+    }
+    let file_name = output_file_name(&cf, path_to_target_dir)?;
+    write_file(&file_name, &cf.for_cf()?)?;
+
+    info!("End");
+
+    Ok(())
+}
+
+// Выполнить форматирование текста распакованных блоков конфигурации.
+// Результат записывается в каталог <target>/format или <target>/minify, исходный каталог не изменяется.
+fn format_text(path_to_dir: &String,
+               path_to_target_dir: &String,
+               log_level: Option<&String>,
+               format: Format)
+               -> Result<(), ConfError> {
+
+    logger::init_log(&path_to_target_dir, log_level);
+
+    info!("Path to the directory:{}", path_to_dir);
+    info!("Begin");
+
+    let dir_name = match format {
+        Format::Pretty => "format",
+        Format::Minify => "minify",
+    };
+    let format_dir = file_system::path_to_str(&Path::new(&path_to_target_dir).join(dir_name));
+
+    let count = conf_v8::format_dir(path_to_dir, &format_dir, format)?;
+
+    info!("End. Formatted files: {}", count);
+
+    Ok(())
 }
@@ -1,176 +1,731 @@
 
-use std::collections::HashMap;
-
-const PACK: &'static str = "-P"; // Разобрать конфигурационный файл на блоки и записать их в файлы
-const BUILD: &'static str = "-B"; // Создать файл конфигурации на основании раннее распакованной в файлы конфигурации
-const FORMAT: &'static str = "-F"; // Выполнить форматирование текста в файлах блоков
-const CF: &'static str = "--cf"; // Путь к конфигурационному файлу
-const DIR: &'static str = "--dir"; // Каталог
-const TARGET: &'static str = "--target"; // Путь к каталогу в который будет помещен результат
-const LOG_LEVEL: &'static str = "--log-level"; // Уровень логирования при выполнении операции
-const SETTINGS: &'static str = "--settings"; // Настройки сборки
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand, Error, ErrorKind};
+
+use std::env;
+use std::ffi::OsString;
+use std::path::Path;
+
+use file_system;
+use conf_v8::ContainerFormat;
+
+const UNPACK: &'static str = "unpack"; // Разобрать конфигурационный файл на блоки и записать их в файлы
+const BUILD: &'static str = "build"; // Создать файл конфигурации на основании раннее распакованной в файлы конфигурации
+const FORMAT: &'static str = "format"; // Выполнить форматирование текста в файлах блоков
+const MINIFY: &'static str = "minify"; // Удалить форматирование текста в файлах блоков
+const EXPORT: &'static str = "export"; // Выгрузить конфигурацию в виде дерева объектов метаданных
+const IMPORT: &'static str = "import"; // Собрать конфигурацию из дерева объектов метаданных
+const EXTRACT_MODULES: &'static str = "extract-modules"; // Записать тексты модулей в файлы
+const EXTRACT_EXTERNAL: &'static str = "extract-external"; // Извлечь обработку или отчет во внешний файл
+const EMBED_EXTERNAL: &'static str = "embed-external"; // Встроить внешнюю обработку или отчет в конфигурацию
+const DIFF: &'static str = "diff"; // Сравнить два конфигурационных файла
+const MERGE: &'static str = "merge"; // Объединить изменения двух конфигурационных файлов
+
+const CF: &'static str = "cf"; // Путь к конфигурационному файлу
+const DIR: &'static str = "dir"; // Каталог
+const TARGET: &'static str = "target"; // Путь к каталогу в который будет помещен результат
+const LOG_LEVEL: &'static str = "log-level"; // Уровень логирования при выполнении операции
+const SETTINGS: &'static str = "settings"; // Настройки сборки
+const THREADS: &'static str = "threads"; // Количество потоков для упаковки и распаковки блоков
+const OLD: &'static str = "old"; // Путь к исходному конфигурационному файлу
+const NEW: &'static str = "new"; // Путь к новому конфигурационному файлу
+const JSON: &'static str = "json"; // Записать результат в формате JSON
+const BASE: &'static str = "base"; // Путь к общему исходному конфигурационному файлу
+const OURS: &'static str = "ours"; // Путь к нашему конфигурационному файлу
+const THEIRS: &'static str = "theirs"; // Путь к их конфигурационному файлу
+const UNPACK_CONFLICTS: &'static str = "unpack"; // Распаковать конфигурацию с конфликтами
+const DRY_RUN: &'static str = "dry-run"; // Только записать отчет о фильтрации по настройкам
+const NAME: &'static str = "name"; // Имя объекта метаданных
+const EXTERNAL: &'static str = "external"; // Путь к внешней обработке или отчету
+const ADDRESS_SIZE: &'static str = "address-size"; // Разрядность адресов собираемого файла
+
+const LOG_LEVELS: [&'static str; 5] = ["error", "warn", "info", "debug", "trace"];
+const ADDRESS_SIZES: [&'static str; 2] = ["32", "64"];
+
+// Команда, переданная в программу
+pub enum Command {
+    // Разобрать конфигурационный файл на блоки и записать их в файлы
+    Unpack {
+        cf: String,
+        target: String,
+        settings: Option<String>,
+        threads: Option<usize>,
+        dry_run: bool,
+        json: bool,
+    },
+    // Создать файл конфигурации на основании раннее распакованной в файлы конфигурации
+    Build {
+        dir: String,
+        target: String,
+        threads: Option<usize>,
+        format: Option<ContainerFormat>,
+    },
+    // Выполнить форматирование текста в файлах блоков
+    Format { dir: String, target: String },
+    // Удалить форматирование текста в файлах блоков
+    Minify { dir: String, target: String },
+    // Выгрузить конфигурацию в виде дерева объектов метаданных
+    Export {
+        cf: String,
+        target: String,
+        threads: Option<usize>,
+    },
+    // Собрать файл конфигурации из ранее выгруженного дерева объектов метаданных
+    Import {
+        dir: String,
+        target: String,
+        threads: Option<usize>,
+    },
+    // Записать тексты модулей объектов метаданных в файлы
+    ExtractModules {
+        cf: String,
+        target: String,
+        threads: Option<usize>,
+    },
+    // Извлечь обработку или отчет конфигурации во внешнюю обработку (отчет)
+    ExtractExternal {
+        cf: String,
+        name: String,
+        target: String,
+    },
+    // Встроить внешнюю обработку (отчет) в конфигурацию
+    EmbedExternal {
+        cf: String,
+        external: String,
+        target: String,
+    },
+    // Сравнить два конфигурационных файла по объектам метаданных
+    Diff {
+        old: String,
+        new: String,
+        target: String,
+        json: bool,
+    },
+    // Трехстороннее объединение конфигурационных файлов
+    Merge {
+        base: String,
+        ours: String,
+        theirs: String,
+        target: String,
+        unpack: bool,
+    },
+}
 
 // Аргументы переданные в программу
 pub struct Args {
-    operation: String,
-    params: HashMap<String, String>,
+    command: Command,
+    log_level: Option<String>,
 }
 
 impl Args {
-    // Инициализировать объекта на основании коллекции аргументов переданных в программу
-    pub fn new(args: Vec<String>) -> Args {
-
-        let mut params = HashMap::new();
-        let mut operation = String::new();
-
-        for arv in args {
-            let values: Vec<&str> = arv.split('=').collect();
-
-            match values.len() {
-                1 => {
-                    let key = String::from(*values.get(0).unwrap());
-
-                    operation = match key.eq(PACK) || key.eq(BUILD) || key.eq(FORMAT) {
-                        true => key,
-                        _ => String::new(),
-                    };
+    // Инициализировать объект на основании коллекции аргументов переданных в программу.
+    // Если аргументы указаны неверно или запрошена справка, то возвращается ошибка с текстом
+    // сообщения.
+    pub fn new<I, T>(args: I) -> Result<Args, Error>
+        where I: IntoIterator<Item = T>,
+              T: Into<OsString> + Clone
+    {
+        let matches = Args::app().get_matches_from_safe(args)?;
+
+        let command = match matches.subcommand() {
+            (UNPACK, Some(m)) => {
+                Command::Unpack {
+                    cf: path_value(m, CF),
+                    target: path_value(m, TARGET),
+                    settings: m.value_of(SETTINGS).map(absolute_path),
+                    threads: threads_value(m),
+                    dry_run: m.is_present(DRY_RUN),
+                    json: m.is_present(JSON),
                 }
-                2 => {
-                    let key = String::from(*values.get(0).unwrap());
-                    let val = String::from(*values.get(1).unwrap());
-
-                    if val.len() > 0 &&
-                       (key.eq(CF) || key.eq(DIR) || key.eq(TARGET) || key.eq(LOG_LEVEL)) ||
-                       key.eq(SETTINGS) {
-                        params.insert(key, val);
-                    }
+            }
+            (BUILD, Some(m)) => {
+                Command::Build {
+                    dir: path_value(m, DIR),
+                    target: path_value(m, TARGET),
+                    threads: threads_value(m),
+                    format: m.value_of(ADDRESS_SIZE).and_then(ContainerFormat::from_bits),
                 }
-                _ => {
-                    error!("The value should not include the symbol '=' or be empty.");
-                    panic!("The value should not include the symbol '=' or be empty.");
+            }
+            (FORMAT, Some(m)) => {
+                Command::Format {
+                    dir: path_value(m, DIR),
+                    target: path_value(m, TARGET),
                 }
             }
-        }
+            (MINIFY, Some(m)) => {
+                Command::Minify {
+                    dir: path_value(m, DIR),
+                    target: path_value(m, TARGET),
+                }
+            }
+            (EXPORT, Some(m)) => {
+                Command::Export {
+                    cf: path_value(m, CF),
+                    target: path_value(m, TARGET),
+                    threads: threads_value(m),
+                }
+            }
+            (IMPORT, Some(m)) => {
+                Command::Import {
+                    dir: path_value(m, DIR),
+                    target: path_value(m, TARGET),
+                    threads: threads_value(m),
+                }
+            }
+            (EXTRACT_MODULES, Some(m)) => {
+                Command::ExtractModules {
+                    cf: path_value(m, CF),
+                    target: path_value(m, TARGET),
+                    threads: threads_value(m),
+                }
+            }
+            (EXTRACT_EXTERNAL, Some(m)) => {
+                Command::ExtractExternal {
+                    cf: path_value(m, CF),
+                    name: String::from(m.value_of(NAME).unwrap_or("")),
+                    target: path_value(m, TARGET),
+                }
+            }
+            (EMBED_EXTERNAL, Some(m)) => {
+                Command::EmbedExternal {
+                    cf: path_value(m, CF),
+                    external: path_value(m, EXTERNAL),
+                    target: path_value(m, TARGET),
+                }
+            }
+            (DIFF, Some(m)) => {
+                Command::Diff {
+                    old: path_value(m, OLD),
+                    new: path_value(m, NEW),
+                    target: path_value(m, TARGET),
+                    json: m.is_present(JSON),
+                }
+            }
+            (MERGE, Some(m)) => {
+                Command::Merge {
+                    base: path_value(m, BASE),
+                    ours: path_value(m, OURS),
+                    theirs: path_value(m, THEIRS),
+                    target: path_value(m, TARGET),
+                    unpack: m.is_present(UNPACK_CONFLICTS),
+                }
+            }
+            _ => {
+                return Err(Error::with_description("The command is not specified.",
+                                                   ErrorKind::MissingSubcommand))
+            }
+        };
 
-        let retval = Args {
-            operation: operation,
-            params: params,
+        let log_level = match matches.subcommand() {
+            (_, Some(m)) => m.value_of(LOG_LEVEL).map(String::from),
+            _ => None,
         };
 
-        if retval.operation().eq(PACK) {
-            if retval.cf() == None || retval.target() == None {
-                panic!("{}", Args::desc_unpuck_params());
-            }
+        return Ok(Args {
+            command: command,
+            log_level: log_level,
+        });
+    }
 
-        } else if retval.operation().eq(BUILD) {
-            if retval.dir() == None || retval.target() == None {
-                panic!("{}", Args::desc_build_params());
-            }
+    // Возвращает выполняемую команду
+    pub fn command(&self) -> &Command {
+        return &self.command;
+    }
 
-        } else if retval.operation().eq(FORMAT) {
-            if retval.dir() == None || retval.target() == None {
-                panic!("{}", Args::desc_format_params());
-            }
+    // Возвращает уровень логирования
+    pub fn log_level(&self) -> Option<&String> {
+        return self.log_level.as_ref();
+    }
 
-        } else {
-            let mut desc = String::new();
-            desc.push_str(&*Args::desc_unpuck_params());
-            desc.push_str("\n\n");
-            desc.push_str(&*Args::desc_build_params());
-            desc.push_str("\n\n");
-            desc.push_str(&*Args::desc_format_params());
+    // Описание команд и параметров программы
+    fn app() -> App<'static, 'static> {
+        App::new("conf_robber")
+            .version(env!("CARGO_PKG_VERSION"))
+            .about("Unpacks and builds configuration files of 1C:Enterprise 8 (*.cf), extensions (*.cfe), \
+                    external data processors (*.epf) and reports (*.erf)")
+            .setting(AppSettings::SubcommandRequiredElseHelp)
+            .setting(AppSettings::VersionlessSubcommands)
+            .subcommand(Args::unpack_command())
+            .subcommand(Args::build_command())
+            .subcommand(Args::format_command())
+            .subcommand(Args::minify_command())
+            .subcommand(Args::export_command())
+            .subcommand(Args::import_command())
+            .subcommand(Args::extract_modules_command())
+            .subcommand(Args::extract_external_command())
+            .subcommand(Args::embed_external_command())
+            .subcommand(Args::diff_command())
+            .subcommand(Args::merge_command())
+    }
 
-            panic!("{}", desc);
-        }
+    // Команда распаковки конфигурационного файла
+    fn unpack_command() -> App<'static, 'static> {
+        SubCommand::with_name(UNPACK)
+            .about("Unpack the configuration file (*.cf), extension (*.cfe), external data processor (*.epf) \
+                    or report (*.erf)")
+            .arg(Arg::with_name(CF)
+                .long(CF)
+                .short("c")
+                .value_name("FILE")
+                .help("Path to the source file *.cf, *.cfe, *.epf or *.erf")
+                .required(true))
+            .arg(Arg::with_name(TARGET)
+                .long(TARGET)
+                .short("t")
+                .value_name("DIR")
+                .help("Path to the target directory")
+                .required(true))
+            .arg(Arg::with_name(SETTINGS)
+                .long(SETTINGS)
+                .short("s")
+                .value_name("FILE")
+                .help("Path to the settings file *.xml"))
+            .arg(Arg::with_name(DRY_RUN)
+                .long(DRY_RUN)
+                .requires(SETTINGS)
+                .help("Don't unpack, write the report of filtering by the settings to \
+                       <target>/filter_report.txt"))
+            .arg(Arg::with_name(JSON)
+                .long(JSON)
+                .requires(DRY_RUN)
+                .help("Write the report in JSON format to <target>/filter_report.json"))
+            .arg(threads_arg("Number of threads for unpacking blocks"))
+            .arg(log_level_arg())
+    }
 
-        return retval;
+    // Команда сборки конфигурационного файла
+    fn build_command() -> App<'static, 'static> {
+        SubCommand::with_name(BUILD)
+            .about("Build the configuration file (*.cf), extension (*.cfe), external data processor (*.epf) \
+                    or report (*.erf)")
+            .arg(Arg::with_name(DIR)
+                .long(DIR)
+                .short("d")
+                .value_name("DIR")
+                .help("Path to the directory with the source files")
+                .required(true))
+            .arg(Arg::with_name(TARGET)
+                .long(TARGET)
+                .short("t")
+                .value_name("DIR")
+                .help("Path to the directory for the built file (configuration.cf, <Name>.cfe, \
+                       <Name>.epf or <Name>.erf)")
+                .required(true))
+            .arg(Arg::with_name(ADDRESS_SIZE)
+                .long(ADDRESS_SIZE)
+                .value_name("BITS")
+                .help("Address size of the built file: 32 or 64 for large files of newer platform \
+                       versions. By default the format of the unpacked file is kept")
+                .possible_values(&ADDRESS_SIZES))
+            .arg(threads_arg("Number of threads for packing blocks"))
+            .arg(log_level_arg())
     }
 
-    // Возвращает тип выполняемой операции
-    pub fn operation(&self) -> String {
-        return self.operation.clone();
+    // Команда форматирования текста распакованных блоков
+    fn format_command() -> App<'static, 'static> {
+        SubCommand::with_name(FORMAT)
+            .about("Pretty-print descriptions in the files of unpacked blocks to <target>/format")
+            .arg(dir_arg("Path to the directory with the unpacked blocks"))
+            .arg(target_arg("Path to the target directory"))
+            .arg(log_level_arg())
     }
 
-    // Возвращает путь к конфигурационному файлу
-    pub fn cf(&self) -> Option<&String> {
-        return self.params.get(CF);
+    // Команда удаления форматирования текста распакованных блоков
+    fn minify_command() -> App<'static, 'static> {
+        SubCommand::with_name(MINIFY)
+            .about("Minify descriptions in the formatted files of unpacked blocks to \
+                    <target>/minify for building")
+            .arg(dir_arg("Path to the directory with the formatted blocks"))
+            .arg(target_arg("Path to the target directory"))
+            .arg(log_level_arg())
     }
 
-    // Возвращает каталог
-    pub fn dir(&self) -> Option<&String> {
-        return self.params.get(DIR);
+    // Команда выгрузки конфигурации в виде дерева объектов метаданных
+    fn export_command() -> App<'static, 'static> {
+        SubCommand::with_name(EXPORT)
+            .about("Export the configuration file (*.cf), extension (*.cfe), external data processor (*.epf) \
+                    or report (*.erf) as a tree of metadata objects")
+            .arg(Arg::with_name(CF)
+                .long(CF)
+                .short("c")
+                .value_name("FILE")
+                .help("Path to the source file *.cf, *.cfe, *.epf or *.erf")
+                .required(true))
+            .arg(Arg::with_name(TARGET)
+                .long(TARGET)
+                .short("t")
+                .value_name("DIR")
+                .help("Path to the target directory")
+                .required(true))
+            .arg(threads_arg("Number of threads for unpacking blocks"))
+            .arg(log_level_arg())
     }
 
-    // Возвращает путь к каталогу в котором будет результат выполнения
-    pub fn target(&self) -> Option<&String> {
-        return self.params.get(TARGET);
+    // Команда сборки конфигурационного файла из дерева объектов метаданных
+    fn import_command() -> App<'static, 'static> {
+        SubCommand::with_name(IMPORT)
+            .about("Build the configuration file (*.cf) from the exported tree of metadata objects")
+            .arg(Arg::with_name(DIR)
+                .long(DIR)
+                .short("d")
+                .value_name("DIR")
+                .help("Path to the directory with the exported configuration")
+                .required(true))
+            .arg(Arg::with_name(TARGET)
+                .long(TARGET)
+                .short("t")
+                .value_name("DIR")
+                .help("Path to the directory for the built file (configuration.cf, <Name>.cfe, \
+                       <Name>.epf or <Name>.erf)")
+                .required(true))
+            .arg(threads_arg("Number of threads for packing blocks"))
+            .arg(log_level_arg())
     }
 
-    // Возвращает уровень логирования
-    pub fn log_level(&self) -> Option<&String> {
-        return self.params.get(LOG_LEVEL);
+    // Команда записи текстов модулей в файлы
+    fn extract_modules_command() -> App<'static, 'static> {
+        SubCommand::with_name(EXTRACT_MODULES)
+            .about("Write the source code of modules to files <Type>.<Object>.<Module>.bsl")
+            .arg(Arg::with_name(CF)
+                .long(CF)
+                .short("c")
+                .value_name("FILE")
+                .help("Path to the source file *.cf, *.cfe, *.epf or *.erf")
+                .required(true))
+            .arg(Arg::with_name(TARGET)
+                .long(TARGET)
+                .short("t")
+                .value_name("DIR")
+                .help("Path to the target directory")
+                .required(true))
+            .arg(threads_arg("Number of threads for unpacking blocks"))
+            .arg(log_level_arg())
     }
 
-    // Возвращает настройки
-    pub fn settings(&self) -> Option<&String> {
-        return self.params.get(SETTINGS);
+    // Команда извлечения обработки или отчета конфигурации во внешний файл
+    fn extract_external_command() -> App<'static, 'static> {
+        SubCommand::with_name(EXTRACT_EXTERNAL)
+            .about("Extract a data processor or report of the configuration file (*.cf) to \
+                    <target>/<Name>.epf or <target>/<Name>.erf")
+            .arg(Arg::with_name(CF)
+                .long(CF)
+                .short("c")
+                .value_name("FILE")
+                .help("Path to the source file *.cf")
+                .required(true))
+            .arg(Arg::with_name(NAME)
+                .long(NAME)
+                .short("n")
+                .value_name("NAME")
+                .help("Name of the data processor or report")
+                .required(true))
+            .arg(target_arg("Path to the target directory"))
+            .arg(log_level_arg())
     }
 
-    // Возвращает справку для выполнения операции по распаковке конфигурационного файла
-    fn desc_unpuck_params() -> String {
+    // Команда встраивания внешней обработки или отчета в конфигурацию
+    fn embed_external_command() -> App<'static, 'static> {
+        SubCommand::with_name(EMBED_EXTERNAL)
+            .about("Embed an external data processor (*.epf) or report (*.erf) into the configuration \
+                    file (*.cf) and write <target>/configuration.cf")
+            .arg(Arg::with_name(CF)
+                .long(CF)
+                .short("c")
+                .value_name("FILE")
+                .help("Path to the source file *.cf")
+                .required(true))
+            .arg(Arg::with_name(EXTERNAL)
+                .long(EXTERNAL)
+                .short("e")
+                .value_name("FILE")
+                .help("Path to the file *.epf or *.erf")
+                .required(true))
+            .arg(target_arg("Path to the target directory"))
+            .arg(log_level_arg())
+    }
 
-        let mut desc = String::new();
+    // Команда сравнения конфигурационных файлов
+    fn diff_command() -> App<'static, 'static> {
+        SubCommand::with_name(DIFF)
+            .about("Compare two configuration files (*.cf) by metadata objects and write \
+                    <target>/diff.txt or <target>/diff.json")
+            .arg(Arg::with_name(OLD)
+                .long(OLD)
+                .value_name("FILE")
+                .help("Path to the original file *.cf")
+                .required(true))
+            .arg(Arg::with_name(NEW)
+                .long(NEW)
+                .value_name("FILE")
+                .help("Path to the changed file *.cf")
+                .required(true))
+            .arg(target_arg("Path to the target directory"))
+            .arg(Arg::with_name(JSON)
+                .long(JSON)
+                .help("Write the result in JSON format"))
+            .arg(log_level_arg())
+    }
 
-        desc.push_str("Operation type: unpack the configuration file (*.cf)\n");
-        desc.push_str("Options:\n");
-        desc.push_str(PACK);
-        desc.push_str(" - operation type\n");
-        desc.push_str(CF);
-        desc.push_str("=Path to the source file *.cf\n");
-        desc.push_str(TARGET);
-        desc.push_str("=Path to the target directory\n");
-        desc.push_str(LOG_LEVEL);
-        desc.push_str("=Log level (optional)\n");
-        desc.push_str(SETTINGS);
-        desc.push_str("=path to the settings file *.xml (optional)\n");
+    // Команда трехстороннего объединения конфигурационных файлов
+    fn merge_command() -> App<'static, 'static> {
+        SubCommand::with_name(MERGE)
+            .about("Merge changes of two configuration files (*.cf) made from a common base \
+                    to <target>/configuration.cf or write conflicts to <target>/conflicts.txt")
+            .arg(Arg::with_name(BASE)
+                .long(BASE)
+                .value_name("FILE")
+                .help("Path to the common base file *.cf")
+                .required(true))
+            .arg(Arg::with_name(OURS)
+                .long(OURS)
+                .value_name("FILE")
+                .help("Path to our changed file *.cf")
+                .required(true))
+            .arg(Arg::with_name(THEIRS)
+                .long(THEIRS)
+                .value_name("FILE")
+                .help("Path to their changed file *.cf")
+                .required(true))
+            .arg(target_arg("Path to the target directory"))
+            .arg(Arg::with_name(UNPACK_CONFLICTS)
+                .long(UNPACK_CONFLICTS)
+                .help("Unpack the merged configuration with conflict markers to <target>/unpack"))
+            .arg(log_level_arg())
+    }
+}
+
+fn dir_arg(help: &'static str) -> Arg<'static, 'static> {
+    Arg::with_name(DIR)
+        .long(DIR)
+        .short("d")
+        .value_name("DIR")
+        .help(help)
+        .required(true)
+}
 
-        return desc;
+fn target_arg(help: &'static str) -> Arg<'static, 'static> {
+    Arg::with_name(TARGET)
+        .long(TARGET)
+        .short("t")
+        .value_name("DIR")
+        .help(help)
+        .required(true)
+}
+
+fn log_level_arg() -> Arg<'static, 'static> {
+    Arg::with_name(LOG_LEVEL)
+        .long(LOG_LEVEL)
+        .short("l")
+        .value_name("LEVEL")
+        .help("Log level")
+        .possible_values(&LOG_LEVELS)
+}
+
+fn threads_arg(help: &'static str) -> Arg<'static, 'static> {
+    Arg::with_name(THREADS)
+        .long(THREADS)
+        .short("j")
+        .value_name("COUNT")
+        .help(help)
+        .validator(|v| match v.parse::<usize>() {
+            Ok(count) if count > 0 => Ok(()),
+            _ => Err(String::from("the value should be a positive number")),
+        })
+}
+
+// Возвращает значение обязательного параметра с путем к файлу или каталогу
+fn path_value(matches: &ArgMatches, name: &str) -> String {
+    absolute_path(matches.value_of(name).unwrap_or(""))
+}
+
+// Возвращает количество потоков
+fn threads_value(matches: &ArgMatches) -> Option<usize> {
+    matches.value_of(THREADS).and_then(|v| v.parse::<usize>().ok())
+}
+
+// Возвращает полный путь. Относительный путь указывается относительно текущего каталога.
+fn absolute_path(path: &str) -> String {
+
+    let path = Path::new(path);
+    if path.is_absolute() {
+        return file_system::path_to_str(path);
     }
 
-    // Возвращает справку для выполнения операции по сборке конфигурационного файла
-    fn desc_build_params() -> String {
+    match env::current_dir() {
+        Ok(v) => file_system::path_to_str(v.join(path).as_path()),
+        Err(_) => file_system::path_to_str(path),
+    }
+}
 
-        let mut desc = String::new();
+#[cfg(test)]
+mod tests {
+    use super::{Args, Command};
+    use conf_v8::ContainerFormat;
+    use clap::ErrorKind;
+    use std::path::Path;
+
+    #[test]
+    fn test_unpack_args() {
+        let args = Args::new(vec!["conf_robber",
+                                  "unpack",
+                                  "--cf=conf=1.cf",
+                                  "-t",
+                                  "/tmp/target",
+                                  "--threads",
+                                  "4",
+                                  "-l",
+                                  "info"])
+            .unwrap();
+
+        assert_eq!(Some(&String::from("info")), args.log_level());
+
+        match *args.command() {
+            Command::Unpack { ref cf, ref target, ref settings, threads, dry_run, json } => {
+                assert!(Path::new(cf).is_absolute());
+                assert!(cf.ends_with("conf=1.cf"));
+                assert_eq!("/tmp/target", target);
+                assert_eq!(&None, settings);
+                assert_eq!(Some(4), threads);
+                assert!(!dry_run);
+                assert!(!json);
+            }
+            _ => panic!("Expected command: unpack"),
+        }
+    }
 
-        desc.push_str("Operation type: build the configuration file (*.cf)\n");
-        desc.push_str("Options:\n");
-        desc.push_str(BUILD);
-        desc.push_str(" - operation type\n");
-        desc.push_str(DIR);
-        desc.push_str("=Path to the directory with the source files\n");
-        desc.push_str(TARGET);
-        desc.push_str("=Path to the configuration file (*.cf)\n");
-        desc.push_str(LOG_LEVEL);
-        desc.push_str("=Log level (optional)\n");
+    #[test]
+    fn test_dry_run_args() {
+        let args = Args::new(vec!["conf_robber", "unpack", "-c", "/tmp/1.cf", "-t", "/tmp/target",
+                                  "-s", "/tmp/settings.xml", "--dry-run", "--json"])
+            .unwrap();
+
+        match *args.command() {
+            Command::Unpack { ref settings, dry_run, json, .. } => {
+                assert_eq!(&Some(String::from("/tmp/settings.xml")), settings);
+                assert!(dry_run);
+                assert!(json);
+            }
+            _ => panic!("Expected command: unpack"),
+        }
 
-        return desc;
+        let test = Args::new(vec!["conf_robber", "unpack", "-c", "1.cf", "-t", "dir", "--dry-run"]);
+        assert_eq!(ErrorKind::MissingRequiredArgument, test.err().unwrap().kind);
     }
 
-    fn desc_format_params() -> String {
+    #[test]
+    fn test_failed_args() {
+        let test = Args::new(vec!["conf_robber", "build", "--dir=dir"]);
+        assert_eq!(ErrorKind::MissingRequiredArgument, test.err().unwrap().kind);
 
-        let mut desc = String::new();
+        let test = Args::new(vec!["conf_robber", "build", "-d", "dir", "-t", "dir", "-j", "0"]);
+        assert_eq!(ErrorKind::ValueValidation, test.err().unwrap().kind);
+
+        let test = Args::new(vec!["conf_robber", "build", "-d", "dir", "-t", "dir",
+                                  "--address-size", "16"]);
+        assert_eq!(ErrorKind::InvalidValue, test.err().unwrap().kind);
+
+        let test = Args::new(vec!["conf_robber", "pack"]);
+        assert!(test.is_err());
+
+        let test = Args::new(vec!["conf_robber", "format", "--help"]);
+        assert_eq!(ErrorKind::HelpDisplayed, test.err().unwrap().kind);
+
+        let test = Args::new(vec!["conf_robber", "minify", "-d", "dir"]);
+        assert_eq!(ErrorKind::MissingRequiredArgument, test.err().unwrap().kind);
+    }
 
-        desc.push_str("Operation type: format a text in the files with data of unpacked blocks\n");
-        desc.push_str("Options:\n");
-        desc.push_str(FORMAT);
-        desc.push_str(" - operation type\n");
-        desc.push_str(DIR);
-        desc.push_str("=Path to the directory with the source files\n");
-        desc.push_str(TARGET);
-        desc.push_str("=Path to the configuration file (*.cf)\n");
-        desc.push_str(LOG_LEVEL);
-        desc.push_str("=Log level (optional)\n");
+    #[test]
+    fn test_build_args() {
+        let args = Args::new(vec!["conf_robber", "build", "-d", "/tmp/src", "-t", "/tmp/target",
+                                  "--address-size", "64"])
+            .unwrap();
+
+        match *args.command() {
+            Command::Build { ref dir, ref target, threads, format } => {
+                assert_eq!("/tmp/src", dir);
+                assert_eq!("/tmp/target", target);
+                assert_eq!(None, threads);
+                assert_eq!(Some(ContainerFormat::Bits64), format);
+            }
+            _ => panic!("Expected command: build"),
+        }
+
+        let args = Args::new(vec!["conf_robber", "build", "-d", "/tmp/src", "-t", "/tmp/target"])
+            .unwrap();
+
+        match *args.command() {
+            Command::Build { format, .. } => assert_eq!(None, format),
+            _ => panic!("Expected command: build"),
+        }
+    }
+
+    #[test]
+    fn test_diff_args() {
+        let args = Args::new(vec!["conf_robber", "diff", "--old", "/tmp/old.cf", "--new",
+                                  "/tmp/new.cf", "-t", "/tmp/target", "--json"])
+            .unwrap();
+
+        match *args.command() {
+            Command::Diff { ref old, ref new, ref target, json } => {
+                assert_eq!("/tmp/old.cf", old);
+                assert_eq!("/tmp/new.cf", new);
+                assert_eq!("/tmp/target", target);
+                assert!(json);
+            }
+            _ => panic!("Expected command: diff"),
+        }
+    }
+
+    #[test]
+    fn test_extract_external_args() {
+        let args = Args::new(vec!["conf_robber", "extract-external", "-c", "/tmp/1.cf", "-n",
+                                  "ЗагрузкаДанных", "-t", "/tmp/target"])
+            .unwrap();
+
+        match *args.command() {
+            Command::ExtractExternal { ref cf, ref name, ref target } => {
+                assert_eq!("/tmp/1.cf", cf);
+                assert_eq!("ЗагрузкаДанных", name);
+                assert_eq!("/tmp/target", target);
+            }
+            _ => panic!("Expected command: extract-external"),
+        }
+
+        let test = Args::new(vec!["conf_robber", "extract-external", "-c", "1.cf", "-t", "dir"]);
+        assert_eq!(ErrorKind::MissingRequiredArgument, test.err().unwrap().kind);
+
+        let args = Args::new(vec!["conf_robber", "embed-external", "-c", "/tmp/1.cf", "-e",
+                                  "/tmp/Загрузка.epf", "-t", "/tmp/target"])
+            .unwrap();
+
+        match *args.command() {
+            Command::EmbedExternal { ref cf, ref external, ref target } => {
+                assert_eq!("/tmp/1.cf", cf);
+                assert_eq!("/tmp/Загрузка.epf", external);
+                assert_eq!("/tmp/target", target);
+            }
+            _ => panic!("Expected command: embed-external"),
+        }
+    }
+
+    #[test]
+    fn test_merge_args() {
+        let args = Args::new(vec!["conf_robber", "merge", "--base", "/tmp/base.cf", "--ours",
+                                  "/tmp/ours.cf", "--theirs", "/tmp/theirs.cf", "-t", "/tmp/target"])
+            .unwrap();
+
+        match *args.command() {
+            Command::Merge { ref base, ref ours, ref theirs, ref target, unpack } => {
+                assert_eq!("/tmp/base.cf", base);
+                assert_eq!("/tmp/ours.cf", ours);
+                assert_eq!("/tmp/theirs.cf", theirs);
+                assert_eq!("/tmp/target", target);
+                assert!(!unpack);
+            }
+            _ => panic!("Expected command: merge"),
+        }
 
-        return desc;
+        let test = Args::new(vec!["conf_robber", "merge", "--base", "b.cf", "--ours", "o.cf",
+                                  "-t", "dir"]);
+        assert_eq!(ErrorKind::MissingRequiredArgument, test.err().unwrap().kind);
     }
 }
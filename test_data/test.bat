@@ -24,9 +24,9 @@ for %%f in ("%PATH_TO_TARGET_PARSE%", "%PATH_TO_TARGET_BUILD%", "%PATH_TO_TARGET
 SET "CF=original.cf"
 
 rem Full pack/unpack
-rem %PATH_TO_EXE% -P "--cf=%CURRENT_DIR%\test_data\%CF%" "--target=%PATH_TO_TARGET_PARSE%" --log-level=info
-rem %PATH_TO_EXE% -B "--dir=%PATH_TO_TARGET_PARSE%\unpack" "--target=%PATH_TO_TARGET_BUILD%" --log-level=info
+rem %PATH_TO_EXE% unpack --cf "%CURRENT_DIR%\test_data\%CF%" --target "%PATH_TO_TARGET_PARSE%" --log-level info
+rem %PATH_TO_EXE% build --dir "%PATH_TO_TARGET_PARSE%\unpack" --target "%PATH_TO_TARGET_BUILD%" --log-level info
 
 rem Pack/unpack with settings
-%PATH_TO_EXE% -P "--cf=%CURRENT_DIR%\test_data\%CF%" "--target=%PATH_TO_TARGET_PARSE_WITH_SETTINGS%" --log-level=info "--settings=%cd%/settings.xml"
-%PATH_TO_EXE% -B "--dir=%PATH_TO_TARGET_PARSE_WITH_SETTINGS%\unpack" "--target=%PATH_TO_TARGET_BUILD_WITH_SETTINGS%" --log-level=info
+%PATH_TO_EXE% unpack --cf "%CURRENT_DIR%\test_data\%CF%" --target "%PATH_TO_TARGET_PARSE_WITH_SETTINGS%" --log-level info --settings "%cd%/settings.xml"
+%PATH_TO_EXE% build --dir "%PATH_TO_TARGET_PARSE_WITH_SETTINGS%\unpack" --target "%PATH_TO_TARGET_BUILD_WITH_SETTINGS%" --log-level info